@@ -0,0 +1,316 @@
+use std::collections::HashSet;
+
+use bevy_renet::netcode::{ClientAuthentication, NetcodeClientTransport};
+
+use crate::{
+    path::{PathScript, Walker},
+    prelude::*,
+    stats::BotStats,
+};
+
+/// Same rate the client sends `PlayerUpdate` messages at.
+pub const PLAYER_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+pub const RTT_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Settings shared by all bots of a run.
+pub struct BotSettings {
+    pub server_addr: SocketAddr,
+    pub password: Option<String>,
+    pub path: PathScript,
+    pub chunk_radius: IVec3,
+    pub block_interval: Option<Duration>,
+    pub chat_interval: Option<Duration>,
+}
+
+enum BotState {
+    Connecting,
+    Playing(Walker),
+    Disconnected,
+}
+
+/// A simulated player speaking the game protocol without any rendering.
+pub struct Bot {
+    pub username: Username,
+    seed: u64,
+    client: RenetClient,
+    transport: NetcodeClientTransport,
+    state: BotState,
+    started_at: Instant,
+    requested_chunks: HashSet<IVec3>,
+    pending_chunks: HashMap<IVec3, Instant>,
+    last_chunk_position: Option<IVec3>,
+    update_sequence: u32,
+    since_update: Duration,
+    since_block_change: Duration,
+    since_chat: Duration,
+    since_rtt_sample: Duration,
+    placed_block: Option<IVec3>,
+    pub stats: BotStats,
+}
+
+impl Bot {
+    pub fn connect(username: Username, settings: &BotSettings) -> Result<Self, String> {
+        let seed = rand::random::<u64>();
+        let authentication = ClientAuthentication::Unsecure {
+            server_addr: settings.server_addr,
+            client_id: seed,
+            user_data: Some(username.to_netcode_user_data()),
+            protocol_id: PROTOCOL_ID,
+        };
+
+        let bind_addr: SocketAddr = if settings.server_addr.is_ipv6() {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(bind_addr)
+            .map_err(|err| format!("Failed to bind socket on {bind_addr}: {err}"))?;
+        let current_time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let transport = NetcodeClientTransport::new(current_time, authentication, socket)
+            .map_err(|err| format!("Failed to create transport: {err}"))?;
+
+        Ok(Self {
+            username,
+            seed,
+            client: RenetClient::new(connection_config()),
+            transport,
+            state: BotState::Connecting,
+            started_at: Instant::now(),
+            requested_chunks: HashSet::new(),
+            pending_chunks: HashMap::new(),
+            last_chunk_position: None,
+            update_sequence: 0,
+            since_update: Duration::ZERO,
+            since_block_change: Duration::ZERO,
+            since_chat: Duration::ZERO,
+            since_rtt_sample: Duration::ZERO,
+            placed_block: None,
+            stats: BotStats::default(),
+        })
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        matches!(self.state, BotState::Disconnected)
+    }
+
+    pub fn update(&mut self, settings: &BotSettings, delta: Duration) {
+        if self.is_disconnected() {
+            return;
+        }
+
+        self.client.update(delta);
+        if let Err(err) = self.transport.update(delta, &mut self.client) {
+            self.disconnect(format!("Transport error: {err}"));
+            return;
+        }
+        if self.client.is_disconnected() {
+            let reason = match self.client.disconnect_reason() {
+                Some(reason) => format!("Disconnected from server: {reason}"),
+                None => String::from("Disconnected from server"),
+            };
+            self.disconnect(reason);
+            return;
+        }
+
+        self.receive_messages(settings);
+        self.act(settings, delta);
+
+        if let Err(err) = self.transport.send_packets(&mut self.client) {
+            self.disconnect(format!("Transport error: {err}"));
+        }
+    }
+
+    pub fn disconnect(&mut self, reason: String) {
+        if self.is_disconnected() {
+            return;
+        }
+        if self.stats.disconnect_reason.is_none() {
+            self.stats.disconnect_reason = Some(format!("{}: {reason}", self.username));
+        }
+
+        self.client.disconnect();
+        self.transport.disconnect();
+        self.state = BotState::Disconnected;
+    }
+
+    /// Leaves without recording a disconnect reason, used at the end of a run.
+    pub fn leave(&mut self) {
+        if self.is_disconnected() {
+            return;
+        }
+        self.client.disconnect();
+        self.transport.disconnect();
+        self.state = BotState::Disconnected;
+    }
+
+    fn send(&mut self, channel: DefaultChannel, message: &NetworkingMessage) {
+        let bytes = bincode::serialize(message).expect("Message should always be sendable");
+        self.stats.record_sent(bytes.len());
+        self.client.send_message(channel, bytes);
+    }
+
+    fn receive_messages(&mut self, settings: &BotSettings) {
+        for channel in [
+            DefaultChannel::ReliableOrdered,
+            DefaultChannel::ReliableUnordered,
+            DefaultChannel::Unreliable,
+        ] {
+            while let Some(bytes) = self.client.receive_message(channel) {
+                self.stats.record_received(bytes.len());
+
+                match deserialize_message(&bytes) {
+                    Ok(message) => self.handle_message(settings, message),
+                    Err(err) => {
+                        self.disconnect(format!("Could not deserialize message: {err}"));
+                        return;
+                    }
+                }
+                if self.is_disconnected() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, settings: &BotSettings, message: NetworkingMessage) {
+        match message {
+            NetworkingMessage::Kick { reason } => {
+                self.disconnect(format!("Disconnected by the server: {reason}"));
+            }
+            NetworkingMessage::LoginRequired => match &settings.password {
+                Some(password) => {
+                    let message = NetworkingMessage::Login {
+                        password: password.clone(),
+                    };
+                    self.send(DefaultChannel::ReliableOrdered, &message);
+                }
+                None => self.disconnect(String::from(
+                    "The server requires a password, start the bots with --password",
+                )),
+            },
+            NetworkingMessage::PlayerAccept(player_state) => {
+                self.stats.connected = true;
+                self.stats.join_latency.record(self.started_at.elapsed());
+                self.state = BotState::Playing(Walker::new(settings.path, player_state, self.seed));
+            }
+            NetworkingMessage::PlayerTeleport(position) => {
+                if let BotState::Playing(walker) = &self.state {
+                    let spawn = PlayerState {
+                        position,
+                        rotation: walker.state().rotation,
+                    };
+                    self.state = BotState::Playing(Walker::new(settings.path, spawn, self.seed));
+                }
+            }
+            NetworkingMessage::ChunkBatchResponse(chunks) => self.receive_chunks(chunks),
+            NetworkingMessage::CompressedChunkBatchResponse(bytes) => {
+                match decompress_chunks(&bytes) {
+                    Ok(chunks) => self.receive_chunks(chunks),
+                    Err(err) => self.disconnect(format!("Invalid chunk batch: {err}")),
+                }
+            }
+            NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch() => {
+                let positions: Vec<IVec3> = self.requested_chunks.iter().copied().collect();
+                self.request_chunks(positions);
+            }
+            _ => {}
+        }
+    }
+
+    fn receive_chunks(&mut self, chunks: Vec<Chunk>) {
+        let now = Instant::now();
+        for chunk in chunks {
+            self.stats.chunks_received += 1;
+            if let Some(requested_at) = self.pending_chunks.remove(&chunk.position) {
+                self.stats.chunk_latency.record(now - requested_at);
+            }
+        }
+    }
+
+    fn request_chunks(&mut self, positions: Vec<IVec3>) {
+        let now = Instant::now();
+        for batch in positions.chunks(MAX_CHUNK_BATCH_REQUEST_SIZE) {
+            for position in batch {
+                self.pending_chunks.insert(*position, now);
+            }
+            let message = NetworkingMessage::ChunkBatchRequest(batch.to_vec());
+            self.send(DefaultChannel::ReliableUnordered, &message);
+        }
+    }
+
+    fn act(&mut self, settings: &BotSettings, delta: Duration) {
+        let BotState::Playing(walker) = &mut self.state else {
+            return;
+        };
+        let player_state = walker.advance(delta);
+        let block_position = player_state.position.as_ivec3();
+
+        self.since_update += delta;
+        if self.since_update >= PLAYER_UPDATE_INTERVAL {
+            self.since_update = Duration::ZERO;
+            self.update_sequence = self.update_sequence.wrapping_add(1);
+            let message = NetworkingMessage::PlayerUpdate {
+                sequence: self.update_sequence,
+                state: player_state.into(),
+            };
+            self.send(DefaultChannel::Unreliable, &message);
+        }
+
+        // Request the chunks around the bot the way the client does, skipping
+        // everything that was requested before.
+        let chunk_position = ChunkManager::world_position_to_chunk_position(block_position);
+        if self.last_chunk_position != Some(chunk_position) {
+            self.last_chunk_position = Some(chunk_position);
+            let positions: Vec<IVec3> = ChunkManager::get_sorted_chunk_positions_in_range(
+                chunk_position,
+                settings.chunk_radius,
+            )
+            .into_iter()
+            .filter(|position| self.requested_chunks.insert(*position))
+            .collect();
+            self.request_chunks(positions);
+        }
+
+        if let Some(interval) = settings.block_interval {
+            self.since_block_change += delta;
+            if self.since_block_change >= interval {
+                self.since_block_change = Duration::ZERO;
+                // Alternate between placing a block next to the bot and breaking it again.
+                let (position, block) = match self.placed_block.take() {
+                    Some(position) => (position, BlockId::Air),
+                    None => {
+                        let position = block_position + IVec3::new(1, 1, 0);
+                        self.placed_block = Some(position);
+                        (position, BlockId::Stone)
+                    }
+                };
+                let message = NetworkingMessage::BlockUpdate { position, block };
+                self.send(DefaultChannel::ReliableOrdered, &message);
+                self.stats.blocks_changed += 1;
+            }
+        }
+
+        if let Some(interval) = settings.chat_interval {
+            self.since_chat += delta;
+            if self.since_chat >= interval {
+                self.since_chat = Duration::ZERO;
+                self.stats.chat_messages_sent += 1;
+                let message = NetworkingMessage::ChatMessageSend(format!(
+                    "Hello from {} #{}",
+                    self.username, self.stats.chat_messages_sent
+                ));
+                self.send(DefaultChannel::ReliableOrdered, &message);
+            }
+        }
+
+        self.since_rtt_sample += delta;
+        if self.since_rtt_sample >= RTT_SAMPLE_INTERVAL {
+            self.since_rtt_sample = Duration::ZERO;
+            self.stats
+                .rtt
+                .record(Duration::from_secs_f64(self.client.rtt().max(0.0)));
+        }
+    }
+}
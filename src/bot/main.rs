@@ -0,0 +1,132 @@
+use crate::prelude::*;
+
+pub mod prelude;
+
+mod bot;
+mod path;
+mod stats;
+
+use bot::{Bot, BotSettings};
+use clap::Parser;
+use path::PathScript;
+use stats::{BotStats, Report};
+
+const TICK_INTERVAL: Duration = Duration::from_micros(16_667);
+
+#[derive(Debug, Parser)]
+#[command(version)]
+#[command(about = "Connect simulated players to a server for load and soak testing")]
+struct Cli {
+    /// Server address as 'host:port'
+    #[arg(long, default_value = "127.0.0.1:5000")]
+    server: String,
+
+    /// Number of bots to connect
+    #[arg(short, long, default_value_t = 10)]
+    bots: usize,
+
+    /// Seconds to run before the bots leave and the report is printed
+    #[arg(long, default_value_t = 60)]
+    duration: u64,
+
+    /// Bots are named '<prefix><number>'
+    #[arg(long, default_value = "bot")]
+    name_prefix: String,
+
+    /// Path the bots walk along
+    #[arg(long, value_enum, default_value_t = PathScript::Random)]
+    path: PathScript,
+
+    /// Chunks requested around every bot in each direction
+    #[arg(long, default_value_t = 2)]
+    chunk_radius: i32,
+
+    /// Milliseconds between two bots connecting
+    #[arg(long, default_value_t = 100)]
+    spawn_interval: u64,
+
+    /// Seconds between block changes of every bot, 0 to disable
+    #[arg(long, default_value_t = 5.0)]
+    block_interval: f64,
+
+    /// Seconds between chat messages of every bot, 0 to disable
+    #[arg(long, default_value_t = 30.0)]
+    chat_interval: f64,
+
+    /// Password used when the server asks the bots to log in
+    #[arg(long)]
+    password: Option<String>,
+}
+
+fn interval(seconds: f64) -> Option<Duration> {
+    (seconds > 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(&cli) {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), String> {
+    let server_addr = cli
+        .server
+        .to_socket_addrs()
+        .map_err(|err| format!("Failed to resolve '{}': {err}", cli.server))?
+        .next()
+        .ok_or_else(|| format!("'{}' did not resolve to any address", cli.server))?;
+    let usernames = (0..cli.bots)
+        .map(|index| Username::new(&format!("{}{index}", cli.name_prefix)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let settings = BotSettings {
+        server_addr,
+        password: cli.password.clone(),
+        path: cli.path,
+        chunk_radius: IVec3::splat(cli.chunk_radius.max(0)),
+        block_interval: interval(cli.block_interval),
+        chat_interval: interval(cli.chat_interval),
+    };
+
+    println!(
+        "Connecting {} bots to {server_addr} for {} seconds...",
+        cli.bots, cli.duration
+    );
+
+    let run_duration = Duration::from_secs(cli.duration);
+    let spawn_interval = Duration::from_millis(cli.spawn_interval);
+    let started_at = Instant::now();
+    let mut last_tick = started_at;
+    let mut bots: Vec<Bot> = Vec::with_capacity(cli.bots);
+
+    while started_at.elapsed() < run_duration {
+        if bots.len() < usernames.len()
+            && started_at.elapsed() >= spawn_interval * bots.len() as u32
+        {
+            bots.push(Bot::connect(usernames[bots.len()], &settings)?);
+        }
+
+        let now = Instant::now();
+        let delta = now - last_tick;
+        last_tick = now;
+        for bot in &mut bots {
+            bot.update(&settings, delta);
+        }
+
+        if bots.len() == usernames.len() && bots.iter().all(Bot::is_disconnected) {
+            println!("All bots disconnected");
+            break;
+        }
+        std::thread::sleep(TICK_INTERVAL.saturating_sub(now.elapsed()));
+    }
+
+    let elapsed = started_at.elapsed();
+    for bot in &mut bots {
+        bot.leave();
+    }
+
+    let stats: Vec<BotStats> = bots.into_iter().map(|bot| bot.stats).collect();
+    println!("{}", Report::new(&stats, elapsed));
+    Ok(())
+}
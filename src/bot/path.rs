@@ -0,0 +1,118 @@
+use clap::ValueEnum;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::prelude::*;
+
+/// Roughly the walking speed of a player.
+pub const WALK_SPEED: f32 = 4.3;
+pub const WANDER_RADIUS: f32 = 64.0;
+pub const CIRCLE_RADIUS: f32 = 24.0;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathScript {
+    /// Walk to random points around the spawn point
+    Random,
+    /// Walk in a circle around the spawn point
+    Circle,
+}
+
+/// Moves a bot along its path, starting at the position the server accepted it with.
+pub struct Walker {
+    script: PathScript,
+    origin: Vec3,
+    state: PlayerState,
+    angle: f32,
+    target: Vec3,
+    rng: SmallRng,
+}
+
+impl Walker {
+    pub fn new(script: PathScript, spawn: PlayerState, seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let angle = rng.random_range(0.0..TAU);
+        // Circles pass through the spawn point instead of starting with a jump.
+        let origin = match script {
+            PathScript::Random => spawn.position,
+            PathScript::Circle => {
+                spawn.position - Vec3::new(angle.cos(), 0.0, angle.sin()) * CIRCLE_RADIUS
+            }
+        };
+
+        Self {
+            script,
+            origin,
+            state: spawn,
+            angle,
+            target: spawn.position,
+            rng,
+        }
+    }
+
+    pub fn state(&self) -> PlayerState {
+        self.state
+    }
+
+    pub fn advance(&mut self, delta: Duration) -> PlayerState {
+        let step = WALK_SPEED * delta.as_secs_f32();
+        let next = match self.script {
+            PathScript::Random => {
+                if self.state.position.distance(self.target) <= step {
+                    let angle = self.rng.random_range(0.0..TAU);
+                    let distance = self.rng.random_range(0.0..WANDER_RADIUS);
+                    self.target = self.origin + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
+                }
+                self.state.position + (self.target - self.state.position).normalize_or_zero() * step
+            }
+            PathScript::Circle => {
+                self.angle = (self.angle + step / CIRCLE_RADIUS) % TAU;
+                self.origin + Vec3::new(self.angle.cos(), 0.0, self.angle.sin()) * CIRCLE_RADIUS
+            }
+        };
+
+        let direction = next - self.state.position;
+        if direction.length_squared() > f32::EPSILON {
+            self.state.rotation = Quat::from_rotation_y(direction.x.atan2(direction.z) + PI);
+        }
+        self.state.position = next;
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn test_circle_returns_to_spawn() {
+        let spawn = PlayerState::default();
+        let mut walker = Walker::new(PathScript::Circle, spawn, 1);
+        let lap = TAU * CIRCLE_RADIUS / (WALK_SPEED * STEP.as_secs_f32());
+
+        let mut previous = spawn.position;
+        for _ in 0..lap.round() as usize {
+            let state = walker.advance(STEP);
+            assert!(state.position.distance(spawn.position) <= 2.0 * CIRCLE_RADIUS + 0.01);
+            assert!(state.position.distance(previous) <= WALK_SPEED * STEP.as_secs_f32() + 0.01);
+            assert_eq!(state.position.y, spawn.position.y);
+            previous = state.position;
+        }
+        assert!(previous.distance(spawn.position) < WALK_SPEED * STEP.as_secs_f32());
+    }
+
+    #[test]
+    fn test_random_walk_stays_near_spawn() {
+        let spawn = PlayerState::default();
+        let mut walker = Walker::new(PathScript::Random, spawn, 7);
+
+        let mut previous = walker.state().position;
+        for _ in 0..1000 {
+            let state = walker.advance(STEP);
+            assert!(state.position.distance(spawn.position) <= WANDER_RADIUS);
+            assert!(state.position.distance(previous) <= WALK_SPEED * STEP.as_secs_f32() + 0.001);
+            previous = state.position;
+        }
+        assert_ne!(previous, spawn.position);
+    }
+}
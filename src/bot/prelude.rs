@@ -0,0 +1,14 @@
+// std crates
+pub use std::collections::HashMap;
+pub use std::f32::consts::*;
+pub use std::{net::*, time::*};
+
+// bevy crates
+pub use bevy::math::{IVec3, Quat, Vec3};
+
+// networking crates
+pub use renet::{DefaultChannel, RenetClient};
+
+// my crates
+pub use lib::*;
+pub use rsmc as lib;
@@ -0,0 +1,206 @@
+use std::{fmt::Display, time::Duration};
+
+/// Collects latency samples and reports percentiles.
+#[derive(Default, Clone)]
+pub struct LatencyStats {
+    samples: Vec<Duration>,
+}
+
+impl LatencyStats {
+    pub fn record(&mut self, sample: Duration) {
+        self.samples.push(sample);
+    }
+
+    pub fn merge(&mut self, other: &LatencyStats) {
+        self.samples.extend_from_slice(&other.samples);
+    }
+
+    pub fn count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Nearest rank percentile, `percentile` is between 0 and 100.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+}
+
+impl Display for LatencyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let millis = |percentile| {
+            self.percentile(percentile)
+                .map_or(0.0, |sample| sample.as_secs_f64() * 1000.0)
+        };
+
+        write!(
+            f,
+            "{} samples, p50 {:.1} ms, p95 {:.1} ms, p99 {:.1} ms, max {:.1} ms",
+            self.count(),
+            millis(50.0),
+            millis(95.0),
+            millis(99.0),
+            millis(100.0)
+        )
+    }
+}
+
+/// What a single bot did during the run.
+#[derive(Default, Clone)]
+pub struct BotStats {
+    pub connected: bool,
+    pub disconnect_reason: Option<String>,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub chunks_received: u64,
+    pub blocks_changed: u64,
+    pub chat_messages_sent: u64,
+    pub join_latency: LatencyStats,
+    pub chunk_latency: LatencyStats,
+    pub rtt: LatencyStats,
+}
+
+impl BotStats {
+    pub fn record_sent(&mut self, bytes: usize) {
+        self.messages_sent += 1;
+        self.bytes_sent += bytes as u64;
+    }
+
+    pub fn record_received(&mut self, bytes: usize) {
+        self.messages_received += 1;
+        self.bytes_received += bytes as u64;
+    }
+}
+
+/// Totals over all bots, printed at the end of a run.
+pub struct Report {
+    pub bots: usize,
+    pub connected: usize,
+    pub duration: Duration,
+    pub totals: BotStats,
+    pub disconnect_reasons: Vec<String>,
+}
+
+impl Report {
+    pub fn new(stats: &[BotStats], duration: Duration) -> Self {
+        let mut totals = BotStats::default();
+        let mut disconnect_reasons = Vec::new();
+
+        for bot in stats {
+            totals.messages_received += bot.messages_received;
+            totals.bytes_received += bot.bytes_received;
+            totals.messages_sent += bot.messages_sent;
+            totals.bytes_sent += bot.bytes_sent;
+            totals.chunks_received += bot.chunks_received;
+            totals.blocks_changed += bot.blocks_changed;
+            totals.chat_messages_sent += bot.chat_messages_sent;
+            totals.join_latency.merge(&bot.join_latency);
+            totals.chunk_latency.merge(&bot.chunk_latency);
+            totals.rtt.merge(&bot.rtt);
+            disconnect_reasons.extend(bot.disconnect_reason.clone());
+        }
+
+        Self {
+            bots: stats.len(),
+            connected: stats.iter().filter(|bot| bot.connected).count(),
+            duration,
+            totals,
+            disconnect_reasons,
+        }
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.duration.as_secs_f64().max(f64::EPSILON);
+        let totals = &self.totals;
+
+        writeln!(
+            f,
+            "Bots:          {}/{} joined in {:.1} s",
+            self.connected, self.bots, seconds
+        )?;
+        writeln!(
+            f,
+            "Received:      {} messages, {:.1} KiB/s",
+            totals.messages_received,
+            totals.bytes_received as f64 / 1024.0 / seconds
+        )?;
+        writeln!(
+            f,
+            "Sent:          {} messages, {:.1} KiB/s",
+            totals.messages_sent,
+            totals.bytes_sent as f64 / 1024.0 / seconds
+        )?;
+        writeln!(
+            f,
+            "Chunks:        {} received, {:.1}/s",
+            totals.chunks_received,
+            totals.chunks_received as f64 / seconds
+        )?;
+        writeln!(
+            f,
+            "Actions:       {} block changes, {} chat messages",
+            totals.blocks_changed, totals.chat_messages_sent
+        )?;
+        writeln!(f, "Join latency:  {}", totals.join_latency)?;
+        writeln!(f, "Chunk latency: {}", totals.chunk_latency)?;
+        write!(f, "Round trip:    {}", totals.rtt)?;
+
+        for reason in &self.disconnect_reasons {
+            write!(f, "\nDisconnected:  {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut stats = LatencyStats::default();
+        assert_eq!(stats.percentile(50.0), None);
+
+        for millis in (1..=100).rev() {
+            stats.record(Duration::from_millis(millis));
+        }
+
+        assert_eq!(stats.percentile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(stats.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(stats.percentile(95.0), Some(Duration::from_millis(95)));
+        assert_eq!(stats.percentile(100.0), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_report_sums_bots() {
+        let mut first = BotStats {
+            connected: true,
+            ..Default::default()
+        };
+        first.record_received(1024);
+        first.chunk_latency.record(Duration::from_millis(10));
+
+        let mut second = BotStats {
+            disconnect_reason: Some(String::from("Server full")),
+            ..Default::default()
+        };
+        second.record_received(1024);
+        second.chunk_latency.record(Duration::from_millis(20));
+
+        let report = Report::new(&[first, second], Duration::from_secs(2));
+        assert_eq!(report.connected, 1);
+        assert_eq!(report.totals.bytes_received, 2048);
+        assert_eq!(report.totals.chunk_latency.count(), 2);
+        assert_eq!(report.disconnect_reasons, vec![String::from("Server full")]);
+    }
+}
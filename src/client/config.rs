@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::prelude::*;
+
+pub const CONFIG_FILE: &str = "client.toml";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientSettings {
+    /// `host:port`, where host is a hostname, an IPv4 address or a bracketed IPv6 address.
+    pub server_address: String,
+    pub render_distance: IVec3,
+    pub cleanup_distance: IVec3,
+    pub interpolation_delay_ms: u64,
+    pub max_extrapolation_ms: u64,
+    /// Keep received chunks on disk and only download the ones that changed since.
+    pub chunk_cache: bool,
+    pub chunk_cache_directory: PathBuf,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        let interpolation_config = remote_player_resources::InterpolationConfig::default();
+
+        Self {
+            server_address: String::from(crate::networking::DEFAULT_SERVER_ADDR),
+            render_distance: terrain_resources::DEFAULT_RENDER_DISTANCE,
+            cleanup_distance: terrain_resources::DEFAULT_CLEANUP_DISTANCE,
+            interpolation_delay_ms: interpolation_config.delay.as_millis() as u64,
+            max_extrapolation_ms: interpolation_config.max_extrapolation.as_millis() as u64,
+            chunk_cache: true,
+            chunk_cache_directory: PathBuf::from(crate::terrain::cache::DEFAULT_CHUNK_CACHE_DIR),
+        }
+    }
+}
+
+impl ClientSettings {
+    pub fn load(args: &ClientSettingsArgs) -> Result<Self, String> {
+        let mut settings: Self = load_or_create_config(&args.config)?;
+        args.apply(&mut settings);
+        settings
+            .validate()
+            .map_err(|err| format!("Invalid client configuration: {err}"))?;
+        Ok(settings)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        validate_server_address(&self.server_address)?;
+        if self.render_distance.cmplt(IVec3::ONE).any() {
+            return Err(format!(
+                "render_distance must be at least 1 on every axis, got {}",
+                self.render_distance
+            ));
+        }
+        // Chunks between the two distances are kept around, anything closer than the render
+        // distance would be despawned and requested again as soon as the player moves.
+        if self.cleanup_distance.cmplt(self.render_distance).any() {
+            return Err(format!(
+                "cleanup_distance {} must not be smaller than render_distance {}",
+                self.cleanup_distance, self.render_distance
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn view_distance(&self) -> terrain_resources::ViewDistance {
+        terrain_resources::ViewDistance {
+            render: self.render_distance,
+            cleanup: self.cleanup_distance,
+        }
+    }
+
+    pub fn chunk_cache_directory(&self) -> Option<terrain_resources::ChunkCacheDirectory> {
+        self.chunk_cache
+            .then(|| terrain_resources::ChunkCacheDirectory(self.chunk_cache_directory.clone()))
+    }
+
+    pub fn interpolation_config(&self) -> remote_player_resources::InterpolationConfig {
+        remote_player_resources::InterpolationConfig {
+            delay: Duration::from_millis(self.interpolation_delay_ms),
+            max_extrapolation: Duration::from_millis(self.max_extrapolation_ms),
+        }
+    }
+}
+
+/// Checks the format only, the host is resolved on every connection attempt.
+fn validate_server_address(address: &str) -> Result<(), String> {
+    let (host, port) = address
+        .rsplit_once(':')
+        .ok_or_else(|| format!("server_address '{address}' must be in the form 'host:port'"))?;
+
+    if host.is_empty() {
+        return Err(format!("server_address '{address}' is missing a host"));
+    }
+    if port.parse::<u16>().is_err() {
+        return Err(format!("server_address '{address}' has an invalid port"));
+    }
+    if host.contains(':') && !(host.starts_with('[') && host.ends_with(']')) {
+        return Err(format!(
+            "server_address '{address}' must enclose IPv6 addresses in brackets, e.g. '[::1]:5000'"
+        ));
+    }
+    Ok(())
+}
+
+/// Command line overrides for the values in `client.toml`.
+#[derive(Args, Debug)]
+pub struct ClientSettingsArgs {
+    /// Path to the client configuration file, created with defaults if missing
+    #[arg(long, default_value = CONFIG_FILE)]
+    config: PathBuf,
+
+    /// Server to connect to as 'host:port', e.g. 'play.example.com:5000' or '[::1]:5000'
+    #[arg(long)]
+    server: Option<String>,
+
+    /// Chunk render distance as 'x,y,z'
+    #[arg(long, value_parser = parse_ivec3)]
+    render_distance: Option<IVec3>,
+
+    /// Distance as 'x,y,z' beyond which chunks are unloaded
+    #[arg(long, value_parser = parse_ivec3)]
+    cleanup_distance: Option<IVec3>,
+
+    /// How far in the past remote players are rendered, in milliseconds
+    #[arg(long)]
+    interpolation_delay: Option<u64>,
+
+    /// How long remote players keep moving without new updates, in milliseconds
+    #[arg(long)]
+    max_extrapolation: Option<u64>,
+
+    /// Download every chunk instead of reusing chunks cached on disk
+    #[arg(long)]
+    no_chunk_cache: bool,
+}
+
+impl ClientSettingsArgs {
+    fn apply(&self, settings: &mut ClientSettings) {
+        if let Some(server) = &self.server {
+            settings.server_address = server.clone();
+        }
+        if let Some(render_distance) = self.render_distance {
+            settings.render_distance = render_distance;
+        }
+        if let Some(cleanup_distance) = self.cleanup_distance {
+            settings.cleanup_distance = cleanup_distance;
+        }
+        if let Some(interpolation_delay) = self.interpolation_delay {
+            settings.interpolation_delay_ms = interpolation_delay;
+        }
+        if let Some(max_extrapolation) = self.max_extrapolation {
+            settings.max_extrapolation_ms = max_extrapolation;
+        }
+        if self.no_chunk_cache {
+            settings.chunk_cache = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distances_are_validated() {
+        assert!(ClientSettings::default().validate().is_ok());
+
+        let settings = ClientSettings {
+            render_distance: IVec3::new(4, 0, 4),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+
+        let settings = ClientSettings {
+            render_distance: IVec3::splat(8),
+            cleanup_distance: IVec3::splat(6),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_server_address_is_validated() {
+        for address in ["localhost:5000", "10.0.0.1:5000", "[::1]:5000"] {
+            assert!(validate_server_address(address).is_ok(), "{address}");
+        }
+        for address in ["localhost", ":5000", "localhost:port", "::1:5000"] {
+            assert!(validate_server_address(address).is_err(), "{address}");
+        }
+    }
+}
@@ -1 +1,4 @@
+use crate::prelude::*;
 
+#[derive(Component)]
+pub struct ConnectionStatusText;
@@ -15,6 +15,13 @@ pub struct GuiPlugin;
 impl Plugin for GuiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, gui_systems::setup_gui_system);
+        app.add_systems(
+            Update,
+            gui_systems::update_connection_status_system.run_if(
+                state_changed::<GameState>
+                    .or(resource_changed::<networking_resources::DisconnectNotice>),
+            ),
+        );
         app.add_plugins(FpsOverlayPlugin {
             config: FpsOverlayConfig {
                 text_config: TextFont {
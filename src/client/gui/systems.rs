@@ -11,11 +11,43 @@ pub fn setup_gui_system(mut commands: Commands, asset_server: Res<AssetServer>)
             ..default()
         },
     ));
-    commands.spawn((
-        Node::default(),
-        Name::new("menu_title_wrapper"),
-        NodeStyleSheet::new(asset_server.load("gui.css")),
-    ));
+    commands
+        .spawn((
+            Node::default(),
+            Name::new("menu_title_wrapper"),
+            NodeStyleSheet::new(asset_server.load("gui.css")),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node::default(),
+                Name::new("connection_status"),
+                gui_components::ConnectionStatusText,
+                Text::new(""),
+            ));
+        });
+}
+
+pub fn update_connection_status_system(
+    current_state: Res<State<GameState>>,
+    disconnect_notice: Res<networking_resources::DisconnectNotice>,
+    mut status_query: Query<
+        (&mut Text, &mut Visibility),
+        With<gui_components::ConnectionStatusText>,
+    >,
+) {
+    let (mut text, mut visibility) = single_mut!(status_query);
+
+    if *current_state.get() != GameState::WaitingForServer {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    *visibility = Visibility::Inherited;
+    text.0 = match &disconnect_notice.message {
+        Some(notice) if disconnect_notice.fatal => notice.clone(),
+        Some(notice) => format!("{notice}\nReconnecting..."),
+        None => String::from("Connecting to server..."),
+    };
 }
 
 pub fn handle_debug_state_transition_system(
@@ -0,0 +1,284 @@
+use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+use bevy_renet::netcode::{
+    NetcodeClientTransport, NetcodeServerTransport, ServerAuthentication, ServerConfig,
+};
+use renet::RenetServer;
+
+use crate::connection_config;
+use crate::prelude::*;
+use networking_resources::ClientCredentials;
+use std::path::{Path, PathBuf};
+
+/// Simulated time that passes with every step.
+pub const STEP: Duration = Duration::from_millis(1000 / 30);
+/// Upper bound for `Harness::run_until`, 20 simulated seconds.
+pub const MAX_STEPS: usize = 600;
+
+/// A server that speaks the game protocol without any of the server's plugins. It
+/// only answers what the test sends and keeps every message it receives, for faults
+/// the real server does not produce on demand. Everything else runs in `LiveHarness`.
+pub struct TestServer {
+    server: RenetServer,
+    transport: NetcodeServerTransport,
+    address: SocketAddr,
+    pub received: Vec<(ClientId, NetworkingMessage)>,
+}
+
+impl TestServer {
+    fn bind() -> Self {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = socket.local_addr().unwrap();
+        let server_config = ServerConfig {
+            current_time: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap(),
+            max_clients: MAX_PLAYERS,
+            protocol_id: PROTOCOL_ID,
+            public_addresses: vec![address],
+            authentication: ServerAuthentication::Unsecure,
+        };
+
+        Self {
+            server: RenetServer::new(connection_config()),
+            transport: NetcodeServerTransport::new(server_config, socket).unwrap(),
+            address,
+            received: Vec::new(),
+        }
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    fn update(&mut self) {
+        self.server.update(STEP);
+        // Errors only concern single clients, which tests see from the client side.
+        let _ = self.transport.update(STEP, &mut self.server);
+
+        for client_id in self.server.clients_id() {
+            for channel in [
+                DefaultChannel::ReliableOrdered,
+                DefaultChannel::ReliableUnordered,
+                DefaultChannel::Unreliable,
+            ] {
+                while let Some(bytes) = self.server.receive_message(client_id, channel) {
+                    let message =
+                        deserialize_message(&bytes).expect("Client messages should be valid");
+                    self.received.push((client_id, message));
+                }
+            }
+        }
+
+        self.transport.send_packets(&mut self.server);
+    }
+
+    pub fn clients(&self) -> Vec<ClientId> {
+        self.server.clients_id()
+    }
+
+    /// Sends `message` on the channel the server uses for its kind.
+    pub fn send(&mut self, client_id: ClientId, message: NetworkingMessage) {
+        let channel = match message {
+            NetworkingMessage::PlayerSync { .. } => DefaultChannel::Unreliable,
+            NetworkingMessage::ChunkBatchResponse(_)
+            | NetworkingMessage::CompressedChunkBatchResponse(_)
+            | NetworkingMessage::ChunkBatchUnchanged(_)
+            | NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch() => {
+                DefaultChannel::ReliableUnordered
+            }
+            _ => DefaultChannel::ReliableOrdered,
+        };
+        self.server
+            .send_message(client_id, channel, bincode::serialize(&message).unwrap());
+    }
+
+    /// Sends `message` and drops the client right away, so that the client sees both
+    /// in the same frame.
+    pub fn send_and_disconnect(&mut self, client_id: ClientId, message: NetworkingMessage) {
+        self.send(client_id, message);
+        self.transport.send_packets(&mut self.server);
+        self.server.disconnect(client_id);
+        let _ = self.transport.update(Duration::ZERO, &mut self.server);
+    }
+}
+
+/// Runs the client's networking plugin headless, with the resources and messages of
+/// the plugins it talks to, against a `TestServer` over loopback. Time advances by
+/// `STEP` with every step instead of wall clock time.
+pub struct Harness {
+    pub client: App,
+    pub server: TestServer,
+}
+
+impl Harness {
+    pub fn start(username: &str) -> Self {
+        let server = TestServer::bind();
+        let mut client = client_app(username, server.address(), None);
+        client.insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+
+        Self { client, server }
+    }
+
+    pub fn step(&mut self) {
+        self.client.update();
+        self.server.update();
+    }
+
+    /// Steps until `condition` holds, returns false if it did not within `MAX_STEPS`.
+    pub fn run_until(&mut self, mut condition: impl FnMut(&Harness) -> bool) -> bool {
+        for _ in 0..MAX_STEPS {
+            if condition(self) {
+                return true;
+            }
+            self.step();
+        }
+        condition(self)
+    }
+
+    /// Steps for `duration` of simulated time.
+    pub fn run_for(&mut self, duration: Duration) {
+        for _ in 0..duration.as_millis() / STEP.as_millis() {
+            self.step();
+        }
+    }
+
+    /// Waits until the server sees the client and returns its id.
+    pub fn wait_for_connection(&mut self) -> ClientId {
+        assert!(
+            self.run_until(|harness| harness.is_connected()),
+            "the client should connect"
+        );
+        self.server.clients()[0]
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client
+            .world()
+            .contains_resource::<NetcodeClientTransport>()
+            && self.client.world().resource::<RenetClient>().is_connected()
+            && !self.server.clients().is_empty()
+    }
+
+    pub fn has_transport(&self) -> bool {
+        has_transport(&self.client)
+    }
+
+    pub fn state(&self) -> GameState {
+        state(&self.client)
+    }
+}
+
+/// Runs the client's networking plugin like `Harness`, but against the server binary and
+/// in wall clock time, since the server keeps its own.
+pub struct LiveHarness {
+    pub client: App,
+    pub server: ServerProcess,
+}
+
+impl LiveHarness {
+    /// Starts the server binary with `args`, for example the world to generate.
+    pub fn start_server(args: &[&str]) -> ServerProcess {
+        ServerProcess::start(&server_binary(), args).unwrap()
+    }
+
+    pub fn connect(server: ServerProcess, username: &str, password: Option<&str>) -> Self {
+        let client = client_app(username, server.address(), password.map(String::from));
+        Self { client, server }
+    }
+
+    pub fn step(&mut self) {
+        self.client.update();
+        std::thread::sleep(STEP);
+    }
+
+    /// Steps until `condition` holds, returns false if it did not within `MAX_STEPS`.
+    pub fn run_until(&mut self, mut condition: impl FnMut(&LiveHarness) -> bool) -> bool {
+        for _ in 0..MAX_STEPS {
+            if condition(self) {
+                return true;
+            }
+            self.step();
+        }
+        condition(self)
+    }
+
+    /// Steps for `duration` of wall clock time.
+    pub fn run_for(&mut self, duration: Duration) {
+        for _ in 0..duration.as_millis() / STEP.as_millis() {
+            self.step();
+        }
+    }
+
+    pub fn has_transport(&self) -> bool {
+        has_transport(&self.client)
+    }
+
+    pub fn state(&self) -> GameState {
+        state(&self.client)
+    }
+}
+
+/// The client's networking plugin with the resources and messages of the plugins it
+/// talks to, connecting to `address`.
+fn client_app(username: &str, address: SocketAddr, password: Option<String>) -> App {
+    let mut client = App::new();
+    client.add_plugins((MinimalPlugins, StatesPlugin));
+    client.insert_state(GameState::WaitingForServer);
+
+    client.add_message::<WindowCloseRequested>();
+    client.add_message::<player_events::PlayerTeleportEvent>();
+    client.add_message::<remote_player_events::RemotePlayerSpawnedEvent>();
+    client.add_message::<remote_player_events::RemotePlayerDespawnedEvent>();
+    client.add_message::<remote_player_events::RemotePlayerSyncEvent>();
+    client.add_message::<terrain_events::BlockUpdateEvent>();
+    client.add_message::<terrain_events::ChunkMeshUpdateEvent>();
+    client.add_message::<terrain_events::WorldRegenerateEvent>();
+    client.add_message::<terrain_events::RequestChunkBatch>();
+    #[cfg(feature = "chat")]
+    client.add_message::<chat_events::ChatSyncEvent>();
+    #[cfg(feature = "chat")]
+    client.add_message::<chat_events::SingleChatSendEvent>();
+    client.insert_resource(ChunkManager::new());
+    client.insert_resource(terrain_resources::RequestedChunks::default());
+
+    client.add_plugins(NetworkingPlugin::new(
+        ClientCredentials::Username(Username::from(username)),
+        address.to_string(),
+        password,
+    ));
+    client.add_systems(
+        Update,
+        terrain_systems::handle_chunk_request_chunk_batch_event_system,
+    );
+    client.finish();
+    client.cleanup();
+    client
+}
+
+fn has_transport(client: &App) -> bool {
+    client.world().contains_resource::<NetcodeClientTransport>()
+        || client
+            .world()
+            .contains_resource::<networking_resources::PendingConnection>()
+}
+
+fn state(client: &App) -> GameState {
+    client.world().resource::<State<GameState>>().get().clone()
+}
+
+/// Cargo puts the binaries next to the directory with the test binaries when it builds
+/// them for the integration tests in `tests/`.
+fn server_binary() -> PathBuf {
+    let test_binary = std::env::current_exe().unwrap();
+    let binary = test_binary
+        .parent()
+        .and_then(Path::parent)
+        .expect("test binaries live in target/<profile>/deps")
+        .join(format!("server{}", std::env::consts::EXE_SUFFIX));
+    assert!(
+        binary.is_file(),
+        "{} is missing, build it with `cargo build --bin server`",
+        binary.display()
+    );
+    binary
+}
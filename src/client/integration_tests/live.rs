@@ -0,0 +1,128 @@
+//! Scenarios that run the client plugins against the server binary.
+
+use std::fs;
+
+use super::harness::LiveHarness;
+use crate::prelude::*;
+
+/// Upper bound for the server console to apply a command.
+const CONSOLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn register(server: &mut ServerProcess, username: &str, password: &str) {
+    server
+        .run_command(&format!("register {username} {password}"))
+        .unwrap();
+    assert!(
+        server.wait_for(CONSOLE_TIMEOUT, |server| {
+            fs::read_to_string(server.directory().join("accounts.txt"))
+                .is_ok_and(|accounts| accounts.contains(username))
+        }),
+        "the account should be registered:\n{}",
+        server.log()
+    );
+}
+
+fn wait_until_playing(harness: &mut LiveHarness) {
+    assert!(
+        harness.run_until(|harness| harness.state() == GameState::LoadingSpawnRegion),
+        "the client should be accepted:\n{}",
+        harness.server.log()
+    );
+}
+
+#[test]
+fn test_client_logs_in_and_streams_chunks() {
+    let mut server = LiveHarness::start_server(&["generate-world", "live", "--seed", "7"]);
+    register(&mut server, "Steve", "hunter22");
+    let mut harness = LiveHarness::connect(server, "Steve", Some("hunter22"));
+    wait_until_playing(&mut harness);
+
+    let identity = harness
+        .client
+        .world()
+        .get_resource::<terrain_resources::WorldIdentity>()
+        .map(|identity| identity.0.clone());
+    assert_eq!(identity.as_deref(), Some("live-7"));
+    let status = harness.server.status().unwrap();
+    assert_eq!(status.players, vec![Username::from("Steve")]);
+
+    let positions = vec![IVec3::ZERO, IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+    harness
+        .client
+        .world_mut()
+        .write_message(terrain_events::RequestChunkBatch {
+            positions: positions.clone(),
+        });
+    assert!(harness.run_until(|harness| {
+        let world = harness.client.world();
+        let chunk_manager = world.resource::<ChunkManager>();
+        positions
+            .iter()
+            .all(|position| chunk_manager.get_chunk(position).is_some())
+            && world
+                .resource::<terrain_resources::RequestedChunks>()
+                .pending_count()
+                == 0
+    }));
+}
+
+#[test]
+fn test_kicked_client_reconnects_and_logs_in_again() {
+    let mut server = LiveHarness::start_server(&["generate-world", "live"]);
+    register(&mut server, "Steve", "hunter22");
+    let mut harness = LiveHarness::connect(server, "Steve", Some("hunter22"));
+    wait_until_playing(&mut harness);
+
+    harness.server.run_command("kick Steve Cool off").unwrap();
+    assert!(harness.run_until(|harness| harness.state() == GameState::WaitingForServer));
+    let notice = harness
+        .client
+        .world()
+        .resource::<networking_resources::DisconnectNotice>();
+    assert!(!notice.fatal);
+    assert_eq!(
+        notice.message.as_deref(),
+        Some(
+            DisconnectReason::Kicked(String::from("Cool off"))
+                .to_string()
+                .as_str()
+        )
+    );
+
+    wait_until_playing(&mut harness);
+    let status = harness.server.status().unwrap();
+    assert_eq!(status.players, vec![Username::from("Steve")]);
+}
+
+#[test]
+fn test_banned_client_stops_reconnecting() {
+    let server = LiveHarness::start_server(&["generate-world", "live"]);
+    let mut harness = LiveHarness::connect(server, "Griefer", None);
+    wait_until_playing(&mut harness);
+
+    harness
+        .server
+        .run_command("ban Griefer Broke spawn")
+        .unwrap();
+    assert!(harness.run_until(|harness| !harness.has_transport()));
+
+    // Well past the first reconnect delays.
+    harness.run_for(networking_resources::INITIAL_RECONNECT_DELAY * 4);
+    assert!(!harness.has_transport());
+    assert_eq!(harness.state(), GameState::WaitingForServer);
+    assert!(harness.server.status().unwrap().players.is_empty());
+
+    let notice = harness
+        .client
+        .world()
+        .resource::<networking_resources::DisconnectNotice>();
+    assert!(notice.fatal);
+    assert_eq!(
+        notice.message.as_deref(),
+        Some(
+            DisconnectReason::Banned(String::from("Broke spawn"))
+                .to_string()
+                .as_str()
+        )
+    );
+}
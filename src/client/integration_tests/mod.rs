@@ -0,0 +1,91 @@
+//! Scenarios that run the client plugins against a scripted server in the same process,
+//! to inject faults. Scenarios against the server binary are in `live`.
+
+mod harness;
+mod live;
+
+use crate::prelude::*;
+use harness::Harness;
+
+#[test]
+fn test_unchanged_chunks_missing_from_the_cache_are_requested_again() {
+    let mut harness = Harness::start("Steve");
+    let client_id = harness.wait_for_connection();
+    harness.server.send(
+        client_id,
+        NetworkingMessage::PlayerAccept(PlayerState::default()),
+    );
+    assert!(harness.run_until(|harness| harness.state() == GameState::LoadingSpawnRegion));
+
+    let position = IVec3::new(0, 1, 0);
+    let requests_for = |harness: &Harness| {
+        harness
+            .server
+            .received
+            .iter()
+            .filter(|(_, message)| {
+                matches!(message, NetworkingMessage::ChunkBatchRequest(positions) if positions.contains(&position))
+            })
+            .count()
+    };
+    harness
+        .client
+        .world_mut()
+        .write_message(terrain_events::RequestChunkBatch {
+            positions: vec![position],
+        });
+    assert!(harness.run_until(|harness| requests_for(harness) == 1));
+
+    // The client has no cache, so the chunk has to be sent in full after all.
+    harness.server.send(
+        client_id,
+        NetworkingMessage::ChunkBatchUnchanged(vec![position]),
+    );
+    assert!(harness.run_until(|harness| requests_for(harness) == 2));
+    let requests = harness
+        .client
+        .world()
+        .resource::<terrain_resources::RequestedChunks>();
+    assert_eq!(requests.pending_count(), 1);
+
+    harness.server.send(
+        client_id,
+        NetworkingMessage::ChunkBatchResponse(vec![Chunk::new(position)]),
+    );
+    assert!(harness.run_until(|harness| {
+        harness
+            .client
+            .world()
+            .resource::<terrain_resources::RequestedChunks>()
+            .pending_count()
+            == 0
+    }));
+}
+
+#[test]
+fn test_client_stops_reconnecting_after_ban_in_the_disconnect_frame() {
+    let mut harness = Harness::start("Griefer");
+    let client_id = harness.wait_for_connection();
+
+    // Both are in the socket before the client's next update, so the kick is read in
+    // the frame in which the transport reports the disconnect.
+    let reason = DisconnectReason::Banned(String::from("Broke spawn"));
+    harness.server.send_and_disconnect(
+        client_id,
+        NetworkingMessage::Kick {
+            reason: reason.clone(),
+        },
+    );
+    assert!(harness.run_until(|harness| !harness.has_transport()));
+
+    harness.run_for(networking_resources::INITIAL_RECONNECT_DELAY * 4);
+    assert!(!harness.has_transport());
+    assert!(harness.server.clients().is_empty());
+
+    let notice = harness
+        .client
+        .world()
+        .resource::<networking_resources::DisconnectNotice>();
+    assert!(notice.fatal);
+    assert_eq!(notice.message.as_deref(), Some(reason.to_string().as_str()));
+}
@@ -4,6 +4,7 @@ pub mod prelude;
 
 mod chat;
 mod collider;
+mod config;
 mod gui;
 mod networking;
 mod player;
@@ -12,8 +13,11 @@ mod scene;
 mod states;
 mod terrain;
 
+#[cfg(test)]
+mod integration_tests;
+
 use bevy_flair::FlairPlugin;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use scene::setup_scene;
 
 #[cfg(feature = "wireframe")]
@@ -36,13 +40,71 @@ mod wireframe_config {
 #[derive(Debug, Parser)]
 #[command(version)]
 #[command(long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[command(flatten)]
     networking_args: networking_commands::NetworkingArgs,
+
+    #[command(flatten)]
+    settings: config::ClientSettingsArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "Print the status of a server without joining it")]
+    Status(networking_commands::StatusArgs),
+    #[command(about = "List servers announcing themselves on the local network")]
+    Discover(networking_commands::DiscoverArgs),
 }
 
 fn main() {
     let cli = Cli::parse();
+    let settings = match config::ClientSettings::load(&cli.settings) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return;
+        }
+    };
+
+    match &cli.command {
+        Some(Command::Status(status_args)) => {
+            match status_args.query(&settings.server_address) {
+                Ok(status) => println!("{status}"),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Discover(discover_args)) => {
+            match discover_args.discover() {
+                Ok(servers) if servers.is_empty() => println!("No servers found"),
+                Ok(servers) => {
+                    for server in servers {
+                        println!(
+                            "{}  {} [{}/{}] {}",
+                            server.address,
+                            server.status.world_name,
+                            server.status.players.len(),
+                            server.status.max_players,
+                            server.status.motd
+                        );
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        None => {}
+    }
 
     let window_plugin = WindowPlugin {
         primary_window: Some(Window {
@@ -59,7 +121,10 @@ fn main() {
 
     let mut app = App::new();
 
-    match networking::NetworkingPlugin::from_args(cli.networking_args) {
+    match networking::NetworkingPlugin::from_args(
+        cli.networking_args,
+        settings.server_address.clone(),
+    ) {
         Ok(plugin) => {
             app.add_plugins(plugin);
         }
@@ -78,10 +143,10 @@ fn main() {
         EntityCountDiagnosticsPlugin::default(),
         SystemInformationDiagnosticsPlugin,
         gui::GuiPlugin,
-        terrain::TerrainPlugin,
+        terrain::TerrainPlugin::new(settings.view_distance(), settings.chunk_cache_directory()),
         collider::ColliderPlugin,
         player::PlayerPlugin,
-        remote_player::RemotePlayerPlugin,
+        remote_player::RemotePlayerPlugin::new(settings.interpolation_config()),
         #[cfg(feature = "chat")]
         chat::ChatPlugin,
     ));
@@ -1,16 +1,113 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
 use clap::*;
 
-use crate::prelude::NetworkingPlugin;
+use crate::prelude::{
+    networking_resources::ClientCredentials, networking_systems::resolve_server_address,
+    query_status, read_connect_token, DiscoveredServer, LanListener, NetworkingPlugin,
+    ServerStatus, Username, DEFAULT_DISCOVERY_DURATION, DEFAULT_DISCOVERY_PORT,
+    DEFAULT_STATUS_PORT, DEFAULT_STATUS_TIMEOUT,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct NetworkingArgs {
-    #[arg(short, long)]
-    username: String,
+    #[arg(short, long, required_unless_present = "token_file")]
+    username: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "username",
+        help = "Connect with a token issued by the server instead of a username"
+    )]
+    token_file: Option<PathBuf>,
+    #[arg(long, help = "Password for a username with an account on the server")]
+    password: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "password",
+        help = "Prompt for the account password on startup"
+    )]
+    ask_password: bool,
 }
 
 impl NetworkingPlugin {
-    pub fn from_args(args: NetworkingArgs) -> Result<Self, String> {
-        NetworkingPlugin::new(args.username)
+    pub fn from_args(args: NetworkingArgs, server_address: String) -> Result<Self, String> {
+        let credentials = match (args.token_file, args.username) {
+            (Some(token_file), _) => {
+                read_connect_token(&token_file)?;
+                ClientCredentials::TokenFile(token_file)
+            }
+            (None, Some(username)) => ClientCredentials::Username(Username::new(&username)?),
+            (None, None) => {
+                return Err(String::from(
+                    "Either a username or a token file is required",
+                ))
+            }
+        };
+
+        let password = match args.ask_password {
+            true => Some(
+                rpassword::prompt_password("Password: ")
+                    .map_err(|err| format!("Failed to read password: {err}"))?,
+            ),
+            false => args.password,
+        };
+
+        Ok(NetworkingPlugin::new(credentials, server_address, password))
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    #[arg(help = "host:port of the status endpoint, defaults to the server host on port 5001")]
+    address: Option<String>,
+    #[arg(long, help = "Seconds to wait for an answer")]
+    timeout: Option<u64>,
+}
+
+impl StatusArgs {
+    pub fn query(&self, server_address: &str) -> Result<ServerStatus, String> {
+        let address = match &self.address {
+            Some(address) => address.clone(),
+            None => {
+                let host = server_address
+                    .rsplit_once(':')
+                    .map_or(server_address, |(host, _)| host);
+                format!("{host}:{DEFAULT_STATUS_PORT}")
+            }
+        };
+        let timeout = self
+            .timeout
+            .map_or(DEFAULT_STATUS_TIMEOUT, Duration::from_secs);
+
+        query_status(resolve_server_address(&address)?, timeout)
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct DiscoverArgs {
+    #[arg(long, default_value_t = DEFAULT_DISCOVERY_PORT, help = "Port LAN announcements are sent to")]
+    port: u16,
+    #[arg(
+        long,
+        help = "Multicast group to join, for servers that announce to one"
+    )]
+    group: Option<IpAddr>,
+    #[arg(long, help = "Seconds to listen for announcements")]
+    duration: Option<u64>,
+}
+
+impl DiscoverArgs {
+    pub fn discover(&self) -> Result<Vec<DiscoveredServer>, String> {
+        let duration = self
+            .duration
+            .map_or(DEFAULT_DISCOVERY_DURATION, Duration::from_secs);
+
+        let ip = self.group.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        LanListener::bind(SocketAddr::new(ip, self.port))?.collect(duration)
     }
 }
@@ -0,0 +1,123 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::tasks::Task;
+use bevy_renet::netcode::NetcodeClientTransport;
+
+use crate::prelude::*;
+
+pub const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub enum ClientCredentials {
+    /// Unauthenticated, the server trusts the username.
+    Username(Username),
+    /// A connect token issued by the server operator, read again on every connection attempt.
+    TokenFile(PathBuf),
+}
+
+/// Where and as whom the client connects, kept around to reconnect.
+#[derive(Resource, Debug, Clone)]
+pub struct ServerConnection {
+    /// `host:port`, resolved again on every connection attempt. Connect tokens carry their own
+    /// server addresses, so this is ignored when connecting with a token.
+    pub address: String,
+    pub credentials: ClientCredentials,
+    /// Sent when the server asks for a login because the username has an account.
+    pub password: Option<String>,
+}
+
+/// Why the client was last disconnected, shown on screen while it reconnects or
+/// after it gave up.
+#[derive(Resource, Default, Debug)]
+pub struct DisconnectNotice {
+    pub message: Option<String>,
+    /// Set for disconnects that reconnecting cannot fix, the client stops trying.
+    pub fatal: bool,
+}
+
+impl DisconnectNotice {
+    pub fn fatal(message: String) -> Self {
+        Self {
+            message: Some(message),
+            fatal: true,
+        }
+    }
+}
+
+/// A connection attempt that is still being set up, resolving the server address can
+/// take a while.
+#[derive(Resource)]
+pub struct PendingConnection(pub Task<Result<NetcodeClientTransport, String>>);
+
+/// Delays connection attempts, doubling the delay after every failed attempt.
+#[derive(Resource)]
+pub struct ReconnectBackoff {
+    failed_attempts: u32,
+    remaining: Option<Duration>,
+}
+
+impl Default for ReconnectBackoff {
+    /// The first connection attempt is made right away.
+    fn default() -> Self {
+        Self {
+            failed_attempts: 0,
+            remaining: Some(Duration::ZERO),
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    /// Schedules the next attempt and returns its delay.
+    pub fn schedule(&mut self) -> Duration {
+        let delay = INITIAL_RECONNECT_DELAY
+            .saturating_mul(2u32.saturating_pow(self.failed_attempts))
+            .min(MAX_RECONNECT_DELAY);
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        self.remaining = Some(delay);
+        delay
+    }
+
+    /// Returns whether a scheduled attempt is due.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        match self.remaining {
+            Some(remaining) if remaining <= delta => {
+                self.remaining = None;
+                true
+            }
+            Some(remaining) => {
+                self.remaining = Some(remaining - delta);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.failed_attempts = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        let mut backoff = ReconnectBackoff::default();
+        assert!(backoff.tick(Duration::ZERO));
+        assert!(!backoff.tick(Duration::from_secs(60)));
+
+        let delays: Vec<Duration> = (0..7).map(|_| backoff.schedule()).collect();
+        assert_eq!(
+            delays,
+            [1, 2, 4, 8, 16, 30, 30].map(Duration::from_secs).to_vec()
+        );
+
+        assert!(!backoff.tick(Duration::from_secs(29)));
+        assert!(backoff.tick(Duration::from_secs(1)));
+
+        backoff.reset();
+        assert_eq!(backoff.schedule(), INITIAL_RECONNECT_DELAY);
+    }
+}
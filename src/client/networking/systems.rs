@@ -1,4 +1,154 @@
+use bevy::{
+    ecs::system::SystemParam,
+    tasks::{block_on, futures_lite::future, IoTaskPool},
+};
+use bevy_renet::netcode::{ClientAuthentication, NetcodeClientTransport, NetcodeTransportError};
+
+use crate::connection_config;
 use crate::prelude::*;
+use crate::terrain::cache::ChunkCache;
+use networking_resources::{
+    ClientCredentials, DisconnectNotice, PendingConnection, ReconnectBackoff, ServerConnection,
+};
+
+pub fn resolve_server_address(address: &str) -> Result<SocketAddr, String> {
+    address
+        .to_socket_addrs()
+        .map_err(|err| format!("Failed to resolve '{address}': {err}"))?
+        .next()
+        .ok_or_else(|| format!("'{address}' did not resolve to any address"))
+}
+
+pub fn create_transport(connection: &ServerConnection) -> Result<NetcodeClientTransport, String> {
+    let (server_addr, authentication) = match &connection.credentials {
+        ClientCredentials::Username(username) => {
+            let server_addr = resolve_server_address(&connection.address)?;
+            let authentication = ClientAuthentication::Unsecure {
+                server_addr,
+                client_id: rand::random::<u64>(),
+                user_data: Some(username.to_netcode_user_data()),
+                protocol_id: PROTOCOL_ID,
+            };
+            (server_addr, authentication)
+        }
+        ClientCredentials::TokenFile(token_file) => {
+            let connect_token = read_connect_token(token_file)?;
+            let server_addr = connect_token
+                .server_addresses
+                .iter()
+                .flatten()
+                .next()
+                .copied()
+                .ok_or_else(|| {
+                    format!(
+                        "Connect token in '{}' has no server address",
+                        token_file.display()
+                    )
+                })?;
+            (server_addr, ClientAuthentication::Secure { connect_token })
+        }
+    };
+
+    println!("Connecting to {server_addr}...");
+    let bind_addr: SocketAddr = if server_addr.is_ipv6() {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind_addr)
+        .map_err(|err| format!("Failed to bind socket on {bind_addr}: {err}"))?;
+
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    NetcodeClientTransport::new(current_time, authentication, socket)
+        .map_err(|err| format!("Failed to create transport: {err}"))
+}
+
+pub fn log_transport_errors_system(mut transport_errors: MessageReader<NetcodeTransportError>) {
+    for error in transport_errors.read() {
+        eprintln!("{}", error);
+    }
+}
+
+pub fn handle_disconnect_system(
+    mut commands: Commands,
+    client: Res<RenetClient>,
+    transport: Option<Res<NetcodeClientTransport>>,
+    mut backoff: ResMut<ReconnectBackoff>,
+    mut disconnect_notice: ResMut<DisconnectNotice>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !client.is_disconnected() || transport.is_none() {
+        return;
+    }
+
+    // A reason sent by the server is more useful than the transport's own.
+    if disconnect_notice.message.is_none() {
+        let notice = match client.disconnect_reason() {
+            Some(reason) => format!("Disconnected from server: {reason}"),
+            None => String::from("Disconnected from server"),
+        };
+        eprintln!("{notice}");
+        disconnect_notice.message = Some(notice);
+    }
+
+    commands.remove_resource::<NetcodeClientTransport>();
+    next_state.set(GameState::WaitingForServer);
+    if disconnect_notice.fatal {
+        println!("Not reconnecting, restart the client to try again");
+        return;
+    }
+
+    let delay = backoff.schedule();
+    println!("Reconnecting in {} seconds...", delay.as_secs());
+}
+
+/// Sets up connections on the IO task pool, so that slow name resolution does not
+/// freeze the client.
+pub fn connect_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    connection: Res<ServerConnection>,
+    mut backoff: ResMut<ReconnectBackoff>,
+    pending_connection: Option<ResMut<PendingConnection>>,
+) {
+    if let Some(mut pending_connection) = pending_connection {
+        let Some(result) = block_on(future::poll_once(&mut pending_connection.0)) else {
+            return;
+        };
+        commands.remove_resource::<PendingConnection>();
+
+        match result {
+            Ok(transport) => {
+                commands.insert_resource(RenetClient::new(connection_config()));
+                commands.insert_resource(transport);
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                let delay = backoff.schedule();
+                println!("Retrying in {} seconds...", delay.as_secs());
+            }
+        }
+        return;
+    }
+
+    if !backoff.tick(time.delta()) {
+        return;
+    }
+
+    let connection = connection.clone();
+    let task = IoTaskPool::get().spawn(async move { create_transport(&connection) });
+    commands.insert_resource(PendingConnection(task));
+}
+
+pub fn reset_reconnect_backoff_system(
+    mut backoff: ResMut<ReconnectBackoff>,
+    mut disconnect_notice: ResMut<DisconnectNotice>,
+) {
+    backoff.reset();
+    *disconnect_notice = DisconnectNotice::default();
+}
 
 pub fn exit_on_last_window_closed_system(
     close_events: MessageReader<WindowCloseRequested>,
@@ -12,6 +162,60 @@ pub fn exit_on_last_window_closed_system(
     }
 }
 
+/// Where chunks from the server end up: the chunk manager, the mesher and the
+/// chunk cache, if there is one. Their requests are marked as received.
+#[derive(SystemParam)]
+pub struct ReceivedChunks<'w> {
+    chunk_manager: ResMut<'w, ChunkManager>,
+    chunk_mesh_events: ResMut<'w, Messages<terrain_events::ChunkMeshUpdateEvent>>,
+    chunk_cache: Option<ResMut<'w, ChunkCache>>,
+    requested_chunks: ResMut<'w, terrain_resources::RequestedChunks>,
+    time: Res<'w, Time>,
+}
+
+impl ReceivedChunks<'_> {
+    fn insert(&mut self, chunk: Chunk) {
+        if let Some(cache) = &mut self.chunk_cache {
+            cache.store(&chunk);
+        }
+        self.insert_without_caching(chunk);
+    }
+
+    /// Loads chunks the server confirmed as unchanged from the cache, returning
+    /// the ones that could not be loaded.
+    fn insert_cached(&mut self, positions: Vec<IVec3>) -> Vec<IVec3> {
+        let mut missing = Vec::new();
+        for position in positions {
+            match self
+                .chunk_cache
+                .as_mut()
+                .and_then(|cache| cache.load(position))
+            {
+                Some(chunk) => self.insert_without_caching(chunk),
+                None => missing.push(position),
+            }
+        }
+        missing
+    }
+
+    /// Requests chunks the cache could not load again, without hashes so that the
+    /// server sends them in full.
+    fn request_uncached(&mut self, client: &mut RenetClient, positions: Vec<IVec3>) {
+        let positions = self
+            .requested_chunks
+            .restart(positions, self.time.elapsed());
+        terrain_systems::send_chunk_requests(client, &positions, None);
+    }
+
+    fn insert_without_caching(&mut self, chunk: Chunk) {
+        let chunk_position = chunk.position;
+        self.requested_chunks.mark_received(chunk_position);
+        self.chunk_manager.insert_chunk(chunk);
+        self.chunk_mesh_events
+            .write(terrain_events::ChunkMeshUpdateEvent { chunk_position });
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn receive_message_system(
     mut commands: Commands,
@@ -19,23 +223,51 @@ pub fn receive_message_system(
     mut player_spawn_events: ResMut<Messages<remote_player_events::RemotePlayerSpawnedEvent>>,
     mut player_despawn_events: ResMut<Messages<remote_player_events::RemotePlayerDespawnedEvent>>,
     mut player_sync_events: ResMut<Messages<remote_player_events::RemotePlayerSyncEvent>>,
+    mut player_teleport_events: ResMut<Messages<player_events::PlayerTeleportEvent>>,
     mut block_update_events: ResMut<Messages<terrain_events::BlockUpdateEvent>>,
-    mut chunk_manager: ResMut<ChunkManager>,
-    mut chunk_mesh_events: ResMut<Messages<terrain_events::ChunkMeshUpdateEvent>>,
+    mut received_chunks: ReceivedChunks,
     mut world_regenerate_events: ResMut<Messages<terrain_events::WorldRegenerateEvent>>,
     #[cfg(feature = "chat")] mut chat_events: ResMut<Messages<chat_events::ChatSyncEvent>>,
     #[cfg(feature = "chat")] mut single_chat_events: ResMut<
         Messages<chat_events::SingleChatSendEvent>,
     >,
-    mut exit_events: MessageWriter<AppExit>,
     mut next_state: ResMut<NextState<GameState>>,
+    server_connection: Res<networking_resources::ServerConnection>,
+    mut disconnect_notice: ResMut<DisconnectNotice>,
 ) {
     while let Some(message) = client.receive_message(DefaultChannel::ReliableOrdered) {
-        match bincode::deserialize(&message) {
+        match deserialize_message(&message) {
             Ok(message) => match message {
-                NetworkingMessage::PlayerReject(reject_reason) => {
-                    eprintln!("Server connection rejected: {reject_reason}");
-                    exit_events.write(AppExit::error());
+                NetworkingMessage::Kick { reason } => {
+                    eprintln!("Disconnected by the server: {reason}");
+                    *disconnect_notice = DisconnectNotice {
+                        message: Some(reason.to_string()),
+                        fatal: reason.is_terminal(),
+                    };
+                    client.disconnect();
+                }
+                NetworkingMessage::LoginRequired => match &server_connection.password {
+                    Some(password) => {
+                        let message = NetworkingMessage::Login {
+                            password: password.clone(),
+                        };
+                        client.send_message(
+                            DefaultChannel::ReliableOrdered,
+                            bincode::serialize(&message)
+                                .expect("Message should always be sendable"),
+                        );
+                    }
+                    None => {
+                        let notice = String::from(
+                            "The server requires a password for this username, start the client with --password or --ask-password",
+                        );
+                        eprintln!("{notice}");
+                        *disconnect_notice = DisconnectNotice::fatal(notice);
+                        client.disconnect();
+                    }
+                },
+                NetworkingMessage::WorldIdentity(identity) => {
+                    commands.insert_resource(terrain_resources::WorldIdentity(identity));
                 }
                 NetworkingMessage::PlayerAccept(player_state) => {
                     commands.insert_resource(player_resources::LocalPlayerSpawnState(player_state));
@@ -44,6 +276,9 @@ pub fn receive_message_system(
                     ));
                     next_state.set(GameState::LoadingSpawnRegion);
                 }
+                NetworkingMessage::PlayerTeleport(position) => {
+                    player_teleport_events.write(player_events::PlayerTeleportEvent { position });
+                }
                 NetworkingMessage::PlayerJoin(username) => {
                     player_spawn_events.write(remote_player_events::RemotePlayerSpawnedEvent {
                         username,
@@ -83,7 +318,7 @@ pub fn receive_message_system(
     }
 
     while let Some(message) = client.receive_message(DefaultChannel::ReliableUnordered) {
-        let message = bincode::deserialize(&message);
+        let message = deserialize_message(&message);
 
         if message.is_err() {
             error!("Failed to deserialize message.");
@@ -100,15 +335,33 @@ pub fn receive_message_system(
                             "Client received chunk response message for: {:?}",
                             chunk.position
                         );
-                        let chunk_position = chunk.position;
-                        chunk_manager.insert_chunk(chunk);
-                        chunk_mesh_events
-                            .write(terrain_events::ChunkMeshUpdateEvent { chunk_position });
+                        received_chunks.insert(chunk);
+                    }
+                }
+                NetworkingMessage::CompressedChunkBatchResponse(bytes) => {
+                    match decompress_chunks(&bytes) {
+                        Ok(chunks) => {
+                            info!(
+                                "Client received {} compressed chunks in {} bytes.",
+                                chunks.len(),
+                                bytes.len()
+                            );
+                            for chunk in chunks {
+                                received_chunks.insert(chunk);
+                            }
+                        }
+                        Err(err) => error!("Could not decompress chunk batch: {err}"),
                     }
                 }
-                NetworkingMessage::PlayerSync(event) => {
-                    player_sync_events
-                        .write(remote_player_events::RemotePlayerSyncEvent { players: event });
+                NetworkingMessage::ChunkBatchUnchanged(positions) => {
+                    info!(
+                        "Client received {} unchanged chunks, loading them from the cache.",
+                        positions.len()
+                    );
+                    let missing = received_chunks.insert_cached(positions);
+                    if !missing.is_empty() {
+                        received_chunks.request_uncached(&mut client, missing);
+                    }
                 }
                 NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch() => {
                     info!("Client asked for chunk batch.");
@@ -120,4 +373,22 @@ pub fn receive_message_system(
             }
         }
     }
+
+    while let Some(message) = client.receive_message(DefaultChannel::Unreliable) {
+        match deserialize_message(&message) {
+            Ok(NetworkingMessage::PlayerSync {
+                tick,
+                server_time,
+                players,
+            }) => {
+                player_sync_events.write(remote_player_events::RemotePlayerSyncEvent {
+                    tick,
+                    server_time,
+                    players,
+                });
+            }
+            Ok(_) => warn!("Received unknown message type. (Unreliable)"),
+            Err(message) => error!("Could not deserialize message {:?}", message),
+        }
+    }
 }
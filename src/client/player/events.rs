@@ -2,3 +2,8 @@ use crate::prelude::*;
 
 #[derive(Message)]
 pub struct PlayerColliderUpdateEvent;
+
+#[derive(Message)]
+pub struct PlayerTeleportEvent {
+    pub position: Vec3,
+}
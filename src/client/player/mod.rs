@@ -16,9 +16,15 @@ impl Plugin for PlayerPlugin {
         #[cfg(feature = "physics_debug")]
         app.add_plugins(RapierDebugRenderPlugin::default());
         app.add_message::<player_events::PlayerColliderUpdateEvent>();
+        app.add_message::<player_events::PlayerTeleportEvent>();
         app.insert_resource(player_resources::BlockSelection::new());
         app.insert_resource(player_resources::PlayerSpawned(false));
         app.insert_resource(player_resources::LastPlayerPosition::new());
+        app.init_resource::<player_resources::PlayerUpdateSender>();
+        app.add_systems(
+            OnEnter(GameState::WaitingForServer),
+            player_systems::despawn_player_system,
+        );
         app.add_systems(
             OnExit(GameState::LoadingSpawnRegion),
             (
@@ -33,6 +39,7 @@ impl Plugin for PlayerPlugin {
             (
                 player_systems::handle_controller_movement_system,
                 player_systems::handle_player_collider_events_system,
+                player_systems::handle_player_teleport_events_system,
             )
                 .run_if(terrain_resources::SpawnRegionLoaded::is_loaded) // TODO: doublecheck
                 .run_if(player_resources::PlayerSpawned::is_spawned),
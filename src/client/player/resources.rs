@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::prelude::*;
 
 #[derive(Resource)]
@@ -63,3 +65,99 @@ impl LastPlayerPosition {
 
 #[derive(Resource)]
 pub struct LocalPlayerSpawnState(pub PlayerState);
+
+pub const PLAYER_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+pub const PLAYER_UPDATE_KEEPALIVE: Duration = Duration::from_secs(1);
+pub const PLAYER_UPDATE_POSITION_THRESHOLD: f32 = 0.01;
+pub const PLAYER_UPDATE_ROTATION_THRESHOLD: f32 = 0.005;
+
+#[derive(Resource, Default)]
+pub struct PlayerUpdateSender {
+    since_last_tick: Duration,
+    since_last_send: Duration,
+    last_sent: Option<PlayerState>,
+    sequence: u32,
+}
+
+impl PlayerUpdateSender {
+    /// Returns the sequence number to send `state` with, or `None` if no update
+    /// is due. Unchanged states are still resent every `PLAYER_UPDATE_KEEPALIVE`
+    /// in case the last update got lost.
+    pub fn next_update(&mut self, delta: Duration, state: PlayerState) -> Option<u32> {
+        self.since_last_tick += delta;
+        self.since_last_send += delta;
+
+        if self.since_last_tick < PLAYER_UPDATE_INTERVAL {
+            return None;
+        }
+        self.since_last_tick = Duration::ZERO;
+
+        let changed = self.last_sent.is_none_or(|last_sent| {
+            last_sent.position.distance(state.position) > PLAYER_UPDATE_POSITION_THRESHOLD
+                || last_sent.rotation.angle_between(state.rotation)
+                    > PLAYER_UPDATE_ROTATION_THRESHOLD
+        });
+        if !changed && self.since_last_send < PLAYER_UPDATE_KEEPALIVE {
+            return None;
+        }
+
+        self.since_last_send = Duration::ZERO;
+        self.last_sent = Some(state);
+        self.sequence = self.sequence.wrapping_add(1);
+        Some(self.sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(10);
+
+    fn state(x: f32) -> PlayerState {
+        PlayerState {
+            position: Vec3::new(x, 0.0, 0.0),
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    fn send_for(sender: &mut PlayerUpdateSender, duration: Duration, state: PlayerState) -> usize {
+        let frames = duration.as_millis() / FRAME.as_millis();
+        (0..frames)
+            .filter_map(|_| sender.next_update(FRAME, state))
+            .count()
+    }
+
+    #[test]
+    fn test_updates_are_rate_limited() {
+        let mut sender = PlayerUpdateSender::default();
+
+        let sent = (0..100)
+            .filter_map(|frame| sender.next_update(FRAME, state(frame as f32)))
+            .count();
+
+        assert_eq!(sent, 20);
+    }
+
+    #[test]
+    fn test_unchanged_state_is_only_resent_as_keepalive() {
+        let mut sender = PlayerUpdateSender::default();
+
+        assert_eq!(
+            send_for(&mut sender, Duration::from_millis(100), state(0.0)),
+            1
+        );
+        assert_eq!(
+            send_for(&mut sender, Duration::from_millis(500), state(0.001)),
+            0
+        );
+        assert_eq!(
+            send_for(&mut sender, Duration::from_millis(500), state(0.0)),
+            1
+        );
+        assert_eq!(
+            send_for(&mut sender, Duration::from_millis(50), state(1.0)),
+            1
+        );
+    }
+}
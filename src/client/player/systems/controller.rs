@@ -96,6 +96,25 @@ pub fn setup_controller_on_area_ready_system(
     player_spawned.0 = true;
 }
 
+#[allow(clippy::type_complexity)]
+pub fn despawn_player_system(
+    mut commands: Commands,
+    mut player_spawned: ResMut<player_resources::PlayerSpawned>,
+    entities: Query<
+        Entity,
+        Or<(
+            With<player_components::Player>,
+            With<player_components::PlayerCamera>,
+            With<player_components::HighlightCube>,
+        )>,
+    >,
+) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    player_spawned.0 = false;
+}
+
 pub fn handle_controller_movement_system(
     query: Query<(Entity, &FpsControllerInput, &Transform)>,
     mut last_position: ResMut<player_resources::LastPlayerPosition>,
@@ -134,6 +153,21 @@ pub fn handle_controller_movement_system(
     }
 }
 
+pub fn handle_player_teleport_events_system(
+    mut teleport_events: MessageReader<player_events::PlayerTeleportEvent>,
+    mut query: Query<(&mut Transform, &mut Velocity), With<player_components::Player>>,
+) {
+    let Some(event) = teleport_events.read().last() else {
+        return;
+    };
+
+    for (mut transform, mut velocity) in query.iter_mut() {
+        info!("Teleporting player to {:?}", event.position);
+        transform.translation = event.position;
+        *velocity = Velocity::zero();
+    }
+}
+
 pub fn activate_fps_controller_system(mut controller_query: Query<&mut FpsController>) {
     for mut controller in &mut controller_query.iter_mut() {
         controller.enable_input = true;
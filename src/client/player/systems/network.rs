@@ -2,6 +2,8 @@ use crate::prelude::*;
 
 pub fn broadcast_player_attributes_system(
     mut client: ResMut<RenetClient>,
+    mut update_sender: ResMut<player_resources::PlayerUpdateSender>,
+    time: Res<Time>,
     query: Query<(&player_components::Player, &Transform)>,
     camera_query: Query<(&Camera3d, &player_components::PlayerCamera, &Transform)>,
 ) {
@@ -17,8 +19,16 @@ pub fn broadcast_player_attributes_system(
         rotation: camera_transform.rotation,
     };
 
+    let Some(sequence) = update_sender.next_update(time.delta(), player_state) else {
+        return;
+    };
+
     client.send_message(
-        DefaultChannel::ReliableUnordered,
-        bincode::serialize(&NetworkingMessage::PlayerUpdate(player_state)).unwrap(),
+        DefaultChannel::Unreliable,
+        bincode::serialize(&NetworkingMessage::PlayerUpdate {
+            sequence,
+            state: player_state.into(),
+        })
+        .unwrap(),
     );
 }
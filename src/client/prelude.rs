@@ -45,6 +45,7 @@ pub use crate::collider::events as collider_events;
 pub use crate::collider::systems as collider_systems;
 
 pub use crate::networking::commands as networking_commands;
+pub use crate::networking::resources as networking_resources;
 pub use crate::networking::systems as networking_systems;
 pub use crate::networking::NetworkingPlugin;
 
@@ -55,6 +56,7 @@ pub use crate::player::systems as player_systems;
 
 pub use crate::remote_player::components as remote_player_components;
 pub use crate::remote_player::events as remote_player_events;
+pub use crate::remote_player::resources as remote_player_resources;
 pub use crate::remote_player::systems as remote_player_systems;
 
 pub use crate::terrain::components as terrain_components;
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use crate::prelude::*;
 
 #[derive(Component)]
@@ -5,5 +7,86 @@ pub struct RemotePlayer {
     pub username: Username,
 }
 
+const MAX_SNAPSHOTS: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    pub server_time: f64,
+    pub state: PlayerState,
+}
+
+#[derive(Component, Default)]
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl SnapshotBuffer {
+    pub fn push(&mut self, server_time: f64, state: PlayerState) {
+        if self
+            .snapshots
+            .back()
+            .is_some_and(|last| last.server_time >= server_time)
+        {
+            return;
+        }
+
+        self.snapshots.push_back(Snapshot { server_time, state });
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Drops snapshots that can no longer be used for rendering `render_time`.
+    /// The last two are always kept so there is a velocity to extrapolate from.
+    pub fn prune(&mut self, render_time: f64) {
+        while self.snapshots.len() > 2
+            && self
+                .snapshots
+                .get(1)
+                .is_some_and(|next| next.server_time <= render_time)
+        {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Interpolates the state at `render_time`. Past the newest snapshot the
+    /// last known velocity is extrapolated for at most `max_extrapolation`.
+    pub fn sample(&self, render_time: f64, max_extrapolation: f64) -> Option<PlayerState> {
+        let first = self.snapshots.front()?;
+        if render_time <= first.server_time {
+            return Some(first.state);
+        }
+
+        let window = self
+            .snapshots
+            .iter()
+            .zip(self.snapshots.iter().skip(1))
+            .find(|(_, to)| to.server_time >= render_time);
+        if let Some((from, to)) = window {
+            let t = (render_time - from.server_time) / (to.server_time - from.server_time);
+            return Some(interpolate(from.state, to.state, t as f32));
+        }
+
+        let last = self.snapshots.back()?;
+        let Some(previous) = self.snapshots.iter().rev().nth(1) else {
+            return Some(last.state);
+        };
+
+        let elapsed = (render_time - last.server_time).min(max_extrapolation);
+        let t = 1.0 + elapsed / (last.server_time - previous.server_time);
+        Some(PlayerState {
+            position: previous.state.position.lerp(last.state.position, t as f32),
+            rotation: last.state.rotation,
+        })
+    }
+}
+
+fn interpolate(from: PlayerState, to: PlayerState, t: f32) -> PlayerState {
+    PlayerState {
+        position: from.position.lerp(to.position, t),
+        rotation: from.rotation.slerp(to.rotation, t),
+    }
+}
+
 #[derive(Default, Reflect, GizmoConfigGroup)]
 pub struct RemotePlayerGizmos;
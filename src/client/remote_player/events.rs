@@ -13,5 +13,7 @@ pub struct RemotePlayerDespawnedEvent {
 
 #[derive(Message)]
 pub struct RemotePlayerSyncEvent {
+    pub tick: u64,
+    pub server_time: f64,
     pub players: HashMap<Username, PlayerState>,
 }
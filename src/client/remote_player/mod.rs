@@ -2,11 +2,22 @@ use bevy_mod_billboard::prelude::BillboardPlugin;
 
 pub mod components;
 pub mod events;
+pub mod resources;
 pub mod systems;
 
 use crate::prelude::*;
 
-pub struct RemotePlayerPlugin;
+pub struct RemotePlayerPlugin {
+    interpolation_config: resources::InterpolationConfig,
+}
+
+impl RemotePlayerPlugin {
+    pub fn new(interpolation_config: resources::InterpolationConfig) -> Self {
+        Self {
+            interpolation_config,
+        }
+    }
+}
 
 impl Plugin for RemotePlayerPlugin {
     fn build(&self, app: &mut App) {
@@ -15,11 +26,26 @@ impl Plugin for RemotePlayerPlugin {
         app.init_gizmo_group::<remote_player_components::RemotePlayerGizmos>();
         app.add_message::<events::RemotePlayerDespawnedEvent>();
         app.add_message::<events::RemotePlayerSyncEvent>();
+        app.init_resource::<resources::RemotePlayerSyncTick>();
+        app.init_resource::<resources::ServerClock>();
+        app.insert_resource(self.interpolation_config.clone());
+        app.add_systems(
+            OnEnter(GameState::WaitingForServer),
+            remote_player_systems::despawn_all_remote_players_system,
+        );
+        app.add_systems(
+            OnEnter(GameState::LoadingSpawnRegion),
+            remote_player_systems::reset_remote_player_sync_system,
+        );
         app.add_systems(
             Update,
             (
                 remote_player_systems::spawn_remote_player_system,
-                remote_player_systems::update_remote_player_system,
+                (
+                    remote_player_systems::update_remote_player_system,
+                    remote_player_systems::interpolate_remote_players_system,
+                )
+                    .chain(),
                 remote_player_systems::despawn_remote_player_system,
                 remote_player_systems::draw_gizmos,
             ),
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use crate::prelude::*;
+
+#[derive(Resource, Default)]
+pub struct RemotePlayerSyncTick(Option<u64>);
+
+impl RemotePlayerSyncTick {
+    /// Records `tick` and returns whether it is newer than every snapshot seen so far.
+    pub fn accept(&mut self, tick: u64) -> bool {
+        if self.0.is_some_and(|latest| tick <= latest) {
+            return false;
+        }
+
+        self.0 = Some(tick);
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.0 = None;
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct InterpolationConfig {
+    /// How far in the past remote players are rendered.
+    pub delay: Duration,
+    pub max_extrapolation: Duration,
+}
+
+impl Default for InterpolationConfig {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(100),
+            max_extrapolation: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Estimates the server clock from the timestamps of incoming snapshots.
+#[derive(Resource, Default)]
+pub struct ServerClock {
+    offset: Option<f64>,
+}
+
+impl ServerClock {
+    const RESYNC_THRESHOLD_SECONDS: f64 = 1.0;
+    const SMOOTHING: f64 = 0.1;
+
+    pub fn observe(&mut self, server_time: f64, local_time: f64) {
+        let sample = server_time - local_time;
+        self.offset = match self.offset {
+            Some(offset) if (sample - offset).abs() < Self::RESYNC_THRESHOLD_SECONDS => {
+                Some(offset + (sample - offset) * Self::SMOOTHING)
+            }
+            _ => Some(sample),
+        };
+    }
+
+    pub fn server_time(&self, local_time: f64) -> Option<f64> {
+        self.offset.map(|offset| local_time + offset)
+    }
+
+    pub fn reset(&mut self) {
+        self.offset = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_snapshots_are_dropped() {
+        let mut sync_tick = RemotePlayerSyncTick::default();
+
+        assert!(sync_tick.accept(5));
+        assert!(!sync_tick.accept(4));
+        assert!(!sync_tick.accept(5));
+        assert!(sync_tick.accept(7));
+
+        sync_tick.reset();
+        assert!(sync_tick.accept(1));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::prelude::*;
 use bevy_mod_billboard::prelude::*;
 
+const REMOTE_PLAYER_OFFSET: Vec3 = Vec3::new(0.0, 0.55, 0.0);
+
 pub fn spawn_remote_player_system(
     mut commands: Commands,
     mut spawn_events: MessageReader<remote_player_events::RemotePlayerSpawnedEvent>,
@@ -24,6 +26,8 @@ pub fn spawn_remote_player_system(
                 bevy::prelude::Mesh3d(meshes.add(Cuboid::new(0.5, 0.5, 0.5))),
                 MeshMaterial3d(material),
                 remote_player_components::RemotePlayer { username },
+                remote_player_components::SnapshotBuffer::default(),
+                Transform::from_translation(event.position + REMOTE_PLAYER_OFFSET),
             ))
             .with_children(|parent| {
                 parent
@@ -57,23 +61,65 @@ pub fn despawn_remote_player_system(
     }
 }
 
+pub fn despawn_all_remote_players_system(
+    mut commands: Commands,
+    query: Query<Entity, With<remote_player_components::RemotePlayer>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn reset_remote_player_sync_system(
+    mut sync_tick: ResMut<remote_player_resources::RemotePlayerSyncTick>,
+    mut server_clock: ResMut<remote_player_resources::ServerClock>,
+) {
+    sync_tick.reset();
+    server_clock.reset();
+}
+
 pub fn update_remote_player_system(
     mut sync_events: MessageReader<remote_player_events::RemotePlayerSyncEvent>,
     mut spawn_events: MessageWriter<remote_player_events::RemotePlayerSpawnedEvent>,
-    mut query: Query<(&remote_player_components::RemotePlayer, &mut Transform)>,
+    mut despawn_events: MessageWriter<remote_player_events::RemotePlayerDespawnedEvent>,
+    mut sync_tick: ResMut<remote_player_resources::RemotePlayerSyncTick>,
+    mut server_clock: ResMut<remote_player_resources::ServerClock>,
+    time: Res<Time>,
+    mut query: Query<(
+        &remote_player_components::RemotePlayer,
+        &mut remote_player_components::SnapshotBuffer,
+    )>,
 ) {
-    let latest_event = sync_events.read().last();
+    let mut latest_event = None;
+
+    for event in sync_events
+        .read()
+        .filter(|event| sync_tick.accept(event.tick))
+    {
+        server_clock.observe(event.server_time, time.elapsed_secs_f64());
+
+        for (remote_player, mut snapshots) in query.iter_mut() {
+            if let Some(player_state) = event.players.get(&remote_player.username) {
+                snapshots.push(event.server_time, *player_state);
+            }
+        }
+
+        latest_event = Some(event);
+    }
 
     if let Some(event) = latest_event {
-        for (username, player_state) in event.players.iter() {
-            let mut player_exists = false;
-            for (remote_player, mut transform) in query.iter_mut() {
-                if remote_player.username == *username {
-                    player_exists = true;
-                    transform.translation = player_state.position + Vec3::new(0.0, 0.55, 0.0);
-                    transform.rotation = player_state.rotation;
-                }
+        for (remote_player, _) in query.iter() {
+            if !event.players.contains_key(&remote_player.username) {
+                despawn_events.write(remote_player_events::RemotePlayerDespawnedEvent {
+                    username: remote_player.username,
+                });
             }
+        }
+
+        for (username, player_state) in event.players.iter() {
+            let player_exists = query
+                .iter()
+                .any(|(remote_player, _)| remote_player.username == *username);
 
             if !player_exists {
                 spawn_events.write(remote_player_events::RemotePlayerSpawnedEvent {
@@ -85,6 +131,35 @@ pub fn update_remote_player_system(
     }
 }
 
+pub fn interpolate_remote_players_system(
+    time: Res<Time>,
+    server_clock: Res<remote_player_resources::ServerClock>,
+    config: Res<remote_player_resources::InterpolationConfig>,
+    mut query: Query<
+        (
+            &mut remote_player_components::SnapshotBuffer,
+            &mut Transform,
+        ),
+        With<remote_player_components::RemotePlayer>,
+    >,
+) {
+    let Some(server_time) = server_clock.server_time(time.elapsed_secs_f64()) else {
+        return;
+    };
+    let render_time = server_time - config.delay.as_secs_f64();
+
+    for (mut snapshots, mut transform) in query.iter_mut() {
+        snapshots.prune(render_time);
+
+        if let Some(player_state) =
+            snapshots.sample(render_time, config.max_extrapolation.as_secs_f64())
+        {
+            transform.translation = player_state.position + REMOTE_PLAYER_OFFSET;
+            transform.rotation = player_state.rotation;
+        }
+    }
+}
+
 pub fn draw_gizmos(
     mut player_gizmos: Gizmos<remote_player_components::RemotePlayerGizmos>,
     query: Query<(&remote_player_components::RemotePlayer, &Transform)>,
@@ -97,3 +172,96 @@ pub fn draw_gizmos(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_message::<remote_player_events::RemotePlayerSyncEvent>();
+        app.add_message::<remote_player_events::RemotePlayerSpawnedEvent>();
+        app.add_message::<remote_player_events::RemotePlayerDespawnedEvent>();
+        app.init_resource::<remote_player_resources::RemotePlayerSyncTick>();
+        app.init_resource::<remote_player_resources::ServerClock>();
+        app.insert_resource(remote_player_resources::InterpolationConfig {
+            delay: Duration::from_millis(100),
+            max_extrapolation: Duration::from_millis(250),
+        });
+        app.add_systems(
+            Update,
+            (
+                update_remote_player_system,
+                interpolate_remote_players_system,
+            )
+                .chain(),
+        );
+        app
+    }
+
+    fn advance(app: &mut App, millis: u64) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(millis));
+    }
+
+    fn send_snapshot(app: &mut App, tick: u64, server_time: f64, x: f32) {
+        let players = HashMap::from([(
+            Username::from("Alex"),
+            PlayerState {
+                position: Vec3::new(x, 0.0, 0.0),
+                rotation: Quat::IDENTITY,
+            },
+        )]);
+        app.world_mut()
+            .write_message(remote_player_events::RemotePlayerSyncEvent {
+                tick,
+                server_time,
+                players,
+            });
+    }
+
+    fn rendered_x(app: &App, entity: Entity) -> f32 {
+        let translation = app.world().get::<Transform>(entity).unwrap().translation;
+        translation.x - REMOTE_PLAYER_OFFSET.x
+    }
+
+    #[test]
+    fn test_remote_players_are_interpolated_and_extrapolated() {
+        let mut app = test_app();
+        let entity = app
+            .world_mut()
+            .spawn((
+                remote_player_components::RemotePlayer {
+                    username: Username::from("Alex"),
+                },
+                remote_player_components::SnapshotBuffer::default(),
+                Transform::default(),
+            ))
+            .id();
+
+        send_snapshot(&mut app, 1, 0.0, 0.0);
+        app.update();
+
+        advance(&mut app, 100);
+        send_snapshot(&mut app, 2, 0.1, 10.0);
+        app.update();
+        assert!(rendered_x(&app, entity).abs() < 0.01);
+
+        advance(&mut app, 50);
+        app.update();
+        assert!((rendered_x(&app, entity) - 5.0).abs() < 0.01);
+
+        // A stale snapshot arriving late is ignored.
+        send_snapshot(&mut app, 1, 0.0, -100.0);
+        advance(&mut app, 100);
+        app.update();
+        assert!((rendered_x(&app, entity) - 15.0).abs() < 0.01);
+
+        advance(&mut app, 1000);
+        app.update();
+        assert!((rendered_x(&app, entity) - 35.0).abs() < 0.01);
+    }
+}
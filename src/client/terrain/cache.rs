@@ -0,0 +1,180 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::prelude::*;
+
+pub const DEFAULT_CHUNK_CACHE_DIR: &str = "chunk_cache";
+const CHUNK_EXTENSION: &str = "chunk";
+
+/// Chunks received from one world of one server, one file per chunk holding the
+/// chunk's content hash followed by the chunk. Only the hashes are kept in memory.
+#[derive(Resource)]
+pub struct ChunkCache {
+    directory: PathBuf,
+    hashes: HashMap<IVec3, u64>,
+}
+
+impl ChunkCache {
+    pub fn open(root: &Path, server_address: &str, world_identity: &str) -> io::Result<Self> {
+        let directory = root
+            .join(sanitize_path_component(server_address))
+            .join(sanitize_path_component(world_identity));
+        fs::create_dir_all(&directory)?;
+
+        let mut hashes = HashMap::new();
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            let Some(position) = position_from_path(&path) else {
+                continue;
+            };
+            match read_hash(&path) {
+                Ok(hash) => {
+                    hashes.insert(position, hash);
+                }
+                Err(err) => warn!("Skipping cached chunk '{}': {err}", path.display()),
+            }
+        }
+
+        Ok(Self { directory, hashes })
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn hash(&self, position: &IVec3) -> Option<u64> {
+        self.hashes.get(position).copied()
+    }
+
+    /// Reads a cached chunk back, dropping it from the cache if the file is gone
+    /// or does not match its hash anymore.
+    pub fn load(&mut self, position: IVec3) -> Option<Chunk> {
+        let path = self.path_for(position);
+        let chunk = fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| {
+                bincode::deserialize::<(u64, Chunk)>(&bytes).map_err(|err| err.to_string())
+            })
+            .and_then(|(hash, chunk)| {
+                if chunk.position == position && chunk.content_hash() == hash {
+                    Ok(chunk)
+                } else {
+                    Err(String::from("content does not match its hash"))
+                }
+            });
+
+        match chunk {
+            Ok(chunk) => Some(chunk),
+            Err(err) => {
+                warn!("Dropping cached chunk '{}': {err}", path.display());
+                self.hashes.remove(&position);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    pub fn store(&mut self, chunk: &Chunk) {
+        let hash = chunk.content_hash();
+        if self.hashes.get(&chunk.position) == Some(&hash) {
+            return;
+        }
+
+        let path = self.path_for(chunk.position);
+        let bytes = bincode::serialize(&(hash, chunk)).expect("Chunks should always serialize");
+        match fs::write(&path, bytes) {
+            Ok(()) => {
+                self.hashes.insert(chunk.position, hash);
+            }
+            Err(err) => {
+                warn!("Failed to cache chunk '{}': {err}", path.display());
+                self.hashes.remove(&chunk.position);
+            }
+        }
+    }
+
+    fn path_for(&self, position: IVec3) -> PathBuf {
+        self.directory.join(format!(
+            "{}_{}_{}.{CHUNK_EXTENSION}",
+            position.x, position.y, position.z
+        ))
+    }
+}
+
+/// Keeps addresses and world names usable as directory names on every platform.
+fn sanitize_path_component(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn position_from_path(path: &Path) -> Option<IVec3> {
+    if path.extension()? != CHUNK_EXTENSION {
+        return None;
+    }
+    let mut coordinates = path.file_stem()?.to_str()?.split('_').map(str::parse);
+    let position = IVec3::new(
+        coordinates.next()?.ok()?,
+        coordinates.next()?.ok()?,
+        coordinates.next()?.ok()?,
+    );
+    coordinates.next().is_none().then_some(position)
+}
+
+fn read_hash(path: &Path) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    File::open(path)?.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_chunks_survive_reopening() {
+        let directory = TempDir::create("chunk_cache").unwrap();
+        let root = directory.path();
+
+        let mut chunk = Chunk::new(IVec3::new(-1, 2, -3));
+        chunk.set(0, 0, 0, BlockId::Stone);
+
+        let mut cache = ChunkCache::open(root, "[::1]:5000", "world-42").unwrap();
+        assert_eq!(cache.chunk_count(), 0);
+        cache.store(&chunk);
+
+        let mut cache = ChunkCache::open(root, "[::1]:5000", "world-42").unwrap();
+        assert_eq!(cache.chunk_count(), 1);
+        assert_eq!(cache.hash(&chunk.position), Some(chunk.content_hash()));
+        let loaded = cache.load(chunk.position).unwrap();
+        assert_eq!(loaded.content_hash(), chunk.content_hash());
+
+        let other_world = ChunkCache::open(root, "[::1]:5000", "world-7").unwrap();
+        assert_eq!(other_world.chunk_count(), 0);
+
+        fs::write(cache.path_for(chunk.position), [0; 16]).unwrap();
+        assert!(cache.load(chunk.position).is_none());
+        assert_eq!(cache.hash(&chunk.position), None);
+    }
+
+    #[test]
+    fn test_position_from_path() {
+        assert_eq!(
+            position_from_path(Path::new("cache/-1_2_-3.chunk")),
+            Some(IVec3::new(-1, 2, -3))
+        );
+        assert_eq!(position_from_path(Path::new("cache/1_2.chunk")), None);
+        assert_eq!(position_from_path(Path::new("cache/1_2_3_4.chunk")), None);
+        assert_eq!(position_from_path(Path::new("cache/1_2_3.tmp")), None);
+    }
+}
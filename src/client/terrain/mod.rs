@@ -1,22 +1,42 @@
 use crate::prelude::*;
 
+pub mod cache;
 pub mod components;
 pub mod events;
 pub mod resources;
 pub mod systems;
 pub mod util;
 
-pub struct TerrainPlugin;
+pub struct TerrainPlugin {
+    view_distance: resources::ViewDistance,
+    chunk_cache_directory: Option<resources::ChunkCacheDirectory>,
+}
+
+impl TerrainPlugin {
+    pub fn new(
+        view_distance: resources::ViewDistance,
+        chunk_cache_directory: Option<resources::ChunkCacheDirectory>,
+    ) -> Self {
+        Self {
+            view_distance,
+            chunk_cache_directory,
+        }
+    }
+}
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
         info!("Building TerrainPlugin");
+        app.insert_resource(self.view_distance);
         app.insert_resource(ChunkManager::new());
         app.insert_resource(util::TextureManager::new());
         app.insert_resource(resources::RenderMaterials::new());
         app.insert_resource(resources::MesherTasks::default());
         app.insert_resource(resources::ChunkEntityMap::default());
         app.insert_resource(resources::RequestedChunks::default());
+        if let Some(directory) = &self.chunk_cache_directory {
+            app.insert_resource(directory.clone());
+        }
         app.add_message::<terrain_events::BlockUpdateEvent>();
         app.add_message::<terrain_events::ChunkMeshUpdateEvent>();
         app.add_message::<terrain_events::WorldRegenerateEvent>();
@@ -33,9 +53,16 @@ impl Plugin for TerrainPlugin {
         {
             app.insert_resource(terrain_resources::SpawnRegionLoaded(false));
 
+            app.add_systems(
+                OnEnter(GameState::WaitingForServer),
+                terrain_systems::reset_terrain_system,
+            );
             app.add_systems(
                 OnEnter(GameState::LoadingSpawnRegion),
-                terrain_systems::generate_world_system,
+                (
+                    terrain_systems::open_chunk_cache_system,
+                    terrain_systems::generate_world_system,
+                ),
             );
             app.add_systems(
                 Update,
@@ -56,6 +83,13 @@ impl Plugin for TerrainPlugin {
                 Update,
                 terrain_systems::handle_chunk_request_chunk_batch_event_system,
             );
+            app.add_systems(
+                Update,
+                (
+                    terrain_systems::retry_timed_out_chunk_requests_system,
+                    terrain_systems::cancel_out_of_range_chunk_requests_system,
+                ),
+            );
             app.add_systems(Update, terrain_systems::cleanup_chunk_entities_system);
         }
     }
@@ -1,9 +1,28 @@
-use std::collections::HashSet;
+use std::path::PathBuf;
 
 use bevy::tasks::Task;
 
 use crate::prelude::*;
 
+pub const DEFAULT_RENDER_DISTANCE: IVec3 = IVec3::new(4, 4, 4);
+pub const DEFAULT_CLEANUP_DISTANCE: IVec3 = IVec3::new(6, 6, 6);
+
+/// Chunk distances around the player, per axis.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ViewDistance {
+    pub render: IVec3,
+    pub cleanup: IVec3,
+}
+
+impl Default for ViewDistance {
+    fn default() -> Self {
+        Self {
+            render: DEFAULT_RENDER_DISTANCE,
+            cleanup: DEFAULT_CLEANUP_DISTANCE,
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct SpawnRegionLoaded(pub bool);
 
@@ -13,11 +32,135 @@ impl SpawnRegionLoaded {
     }
 }
 
+/// How long the client waits for a requested chunk before asking again.
+pub const CHUNK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+pub const MAX_CHUNK_REQUEST_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChunkRequestState {
+    /// Last sent at `sent_at`, measured from app startup.
+    Pending {
+        sent_at: Duration,
+        attempts: u32,
+    },
+    Received,
+    /// Timed out `MAX_CHUNK_REQUEST_ATTEMPTS` times, requested again the next time
+    /// chunks around the player are requested.
+    Failed,
+}
+
 #[derive(Resource, Default)]
 pub struct RequestedChunks {
-    pub previous_chunks: HashSet<IVec3>,
+    states: HashMap<IVec3, ChunkRequestState>,
 }
 
+impl RequestedChunks {
+    /// Marks the positions that are neither pending nor received as pending and
+    /// returns them.
+    pub fn start(
+        &mut self,
+        positions: impl IntoIterator<Item = IVec3>,
+        now: Duration,
+    ) -> Vec<IVec3> {
+        positions
+            .into_iter()
+            .filter(|position| match self.states.get(position) {
+                Some(ChunkRequestState::Pending { .. } | ChunkRequestState::Received) => false,
+                Some(ChunkRequestState::Failed) | None => {
+                    self.states.insert(
+                        *position,
+                        ChunkRequestState::Pending {
+                            sent_at: now,
+                            attempts: 1,
+                        },
+                    );
+                    true
+                }
+            })
+            .collect()
+    }
+
+    /// Like `start`, but also for positions that are pending or received, because
+    /// the server changed them or the cached copy could not be loaded.
+    pub fn restart(
+        &mut self,
+        positions: impl IntoIterator<Item = IVec3>,
+        now: Duration,
+    ) -> Vec<IVec3> {
+        let positions: Vec<IVec3> = positions.into_iter().collect();
+        for position in &positions {
+            self.states.remove(position);
+        }
+        self.start(positions, now)
+    }
+
+    pub fn mark_received(&mut self, position: IVec3) {
+        self.states.insert(position, ChunkRequestState::Received);
+    }
+
+    /// Returns the pending requests that timed out and have attempts left, which
+    /// count as sent again at `now`. The others are marked as failed.
+    pub fn retry_timed_out(&mut self, now: Duration) -> Vec<IVec3> {
+        let mut retries = Vec::new();
+        for (position, state) in &mut self.states {
+            let ChunkRequestState::Pending { sent_at, attempts } = *state else {
+                continue;
+            };
+            if now.saturating_sub(sent_at) < CHUNK_REQUEST_TIMEOUT {
+                continue;
+            }
+
+            if attempts >= MAX_CHUNK_REQUEST_ATTEMPTS {
+                warn!("Giving up on chunk {position} after {attempts} requests");
+                *state = ChunkRequestState::Failed;
+            } else {
+                *state = ChunkRequestState::Pending {
+                    sent_at: now,
+                    attempts: attempts + 1,
+                };
+                retries.push(*position);
+            }
+        }
+        retries
+    }
+
+    /// Forgets every chunk further than `distance` from `origin` on any axis, so
+    /// that it is requested again once the player comes back. Returns the pending
+    /// requests among them, which the server should drop.
+    pub fn cancel_outside_distance(&mut self, origin: &IVec3, distance: &IVec3) -> Vec<IVec3> {
+        let mut cancelled = Vec::new();
+        self.states.retain(|position, state| {
+            let in_range = (position.x - origin.x).abs() <= distance.x
+                && (position.y - origin.y).abs() <= distance.y
+                && (position.z - origin.z).abs() <= distance.z;
+            if !in_range && matches!(state, ChunkRequestState::Pending { .. }) {
+                cancelled.push(*position);
+            }
+            in_range
+        });
+        cancelled
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.states
+            .values()
+            .filter(|state| matches!(state, ChunkRequestState::Pending { .. }))
+            .count()
+    }
+
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+}
+
+/// Names the world of the server the client is connected to.
+#[derive(Resource)]
+pub struct WorldIdentity(pub String);
+
+/// Root of the chunk caches of all servers, missing if chunk caching is disabled.
+#[derive(Resource, Clone)]
+pub struct ChunkCacheDirectory(pub PathBuf);
+
 #[derive(Eq, Hash, Clone, PartialEq)]
 pub enum MeshType {
     Solid,
@@ -71,6 +214,10 @@ impl ChunkEntityMap {
         self.map.remove(&chunk_position)
     }
 
+    pub fn drain(&mut self) -> impl Iterator<Item = Entity> + '_ {
+        self.map.drain().flat_map(|(_position, entities)| entities)
+    }
+
     pub fn extract_outside_distance(
         &mut self,
         origin: &IVec3,
@@ -109,3 +256,84 @@ impl RenderMaterials {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_requests_are_retried_until_they_fail() {
+        let mut requests = RequestedChunks::default();
+        let position = IVec3::new(1, 2, 3);
+
+        assert_eq!(
+            requests.start([position, position], Duration::ZERO),
+            vec![position]
+        );
+        assert!(requests
+            .start([position], Duration::from_secs(1))
+            .is_empty());
+        assert!(requests.retry_timed_out(Duration::from_secs(1)).is_empty());
+
+        let mut now = Duration::ZERO;
+        for _ in 1..MAX_CHUNK_REQUEST_ATTEMPTS {
+            now += CHUNK_REQUEST_TIMEOUT;
+            assert_eq!(requests.retry_timed_out(now), vec![position]);
+        }
+        now += CHUNK_REQUEST_TIMEOUT;
+        assert!(requests.retry_timed_out(now).is_empty());
+        assert_eq!(requests.pending_count(), 0);
+
+        // Failed chunks are requested again, received ones are not.
+        assert_eq!(requests.start([position], now), vec![position]);
+        requests.mark_received(position);
+        assert!(requests.start([position], now).is_empty());
+        assert_eq!(requests.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_restarted_chunk_requests_are_pending_again() {
+        let mut requests = RequestedChunks::default();
+        let received = IVec3::new(1, 0, 0);
+        let pending = IVec3::new(2, 0, 0);
+        requests.start([received, pending], Duration::ZERO);
+        requests.mark_received(received);
+
+        let now = CHUNK_REQUEST_TIMEOUT;
+        assert_eq!(
+            requests.restart([received, pending, IVec3::ZERO], now),
+            vec![received, pending, IVec3::ZERO]
+        );
+        assert_eq!(requests.pending_count(), 3);
+        // Restarted requests count as sent at `now`, with fresh attempts.
+        assert!(requests.retry_timed_out(now).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_requests_out_of_range_are_cancelled() {
+        let mut requests = RequestedChunks::default();
+        requests.start(
+            [IVec3::ZERO, IVec3::new(5, 0, 0), IVec3::new(0, 0, -7)],
+            Duration::ZERO,
+        );
+        requests.mark_received(IVec3::new(9, 0, 0));
+        requests.mark_received(IVec3::new(1, 0, 0));
+
+        let cancelled = requests.cancel_outside_distance(&IVec3::ZERO, &IVec3::splat(6));
+        assert_eq!(cancelled, vec![IVec3::new(0, 0, -7)]);
+        assert_eq!(requests.pending_count(), 2);
+
+        // Out of range chunks are forgotten whatever their state, the others stay.
+        assert_eq!(
+            requests.start(
+                [
+                    IVec3::new(0, 0, -7),
+                    IVec3::new(9, 0, 0),
+                    IVec3::new(1, 0, 0)
+                ],
+                Duration::ZERO
+            ),
+            vec![IVec3::new(0, 0, -7), IVec3::new(9, 0, 0)]
+        );
+    }
+}
@@ -6,10 +6,8 @@ use terrain_resources::{
     ChunkMeshes, FutureChunkMesh, MeshTask, MeshType, MesherTasks, RenderMaterials,
 };
 
-use crate::prelude::*;
+use crate::{prelude::*, terrain::cache::ChunkCache};
 
-const RENDER_DISTANCE: IVec3 = IVec3::new(4, 4, 4);
-const CLEANUP_DISTANCE: IVec3 = IVec3::new(6, 6, 6);
 const MIN_SPAWN_AREA_DISTANCE: IVec3 = IVec3::new(1, 1, 1);
 
 pub fn prepare_mesher_materials_system(
@@ -43,18 +41,50 @@ pub fn generate_simple_ground_system(
 pub fn generate_world_system(
     chunk_manager: Res<ChunkManager>,
     spawn_area: Res<terrain_resources::SpawnRegion>,
+    view_distance: Res<terrain_resources::ViewDistance>,
     mut batch_events: MessageWriter<terrain_events::RequestChunkBatch>,
 ) {
     let origin = spawn_area.origin_chunk_position;
-    let positions = chunk_manager.sorted_new_chunk_positions(origin, RENDER_DISTANCE);
+    let positions = chunk_manager.sorted_new_chunk_positions(origin, view_distance.render);
 
     batch_events.write(terrain_events::RequestChunkBatch { positions });
 }
 
+/// Opens the cache for the world the server named, or drops the previous one if
+/// the server sent no world identity.
+pub fn open_chunk_cache_system(
+    mut commands: Commands,
+    directory: Option<Res<terrain_resources::ChunkCacheDirectory>>,
+    world_identity: Option<Res<terrain_resources::WorldIdentity>>,
+    connection: Res<networking_resources::ServerConnection>,
+) {
+    let (Some(directory), Some(world_identity)) = (directory, world_identity) else {
+        commands.remove_resource::<ChunkCache>();
+        return;
+    };
+
+    match ChunkCache::open(&directory.0, &connection.address, &world_identity.0) {
+        Ok(cache) => {
+            println!(
+                "Found {} cached chunks for world '{}'",
+                cache.chunk_count(),
+                world_identity.0
+            );
+            commands.insert_resource(cache);
+        }
+        Err(err) => {
+            eprintln!("Failed to open the chunk cache, downloading all chunks: {err}");
+            commands.remove_resource::<ChunkCache>();
+        }
+    }
+}
+
 pub fn handle_chunk_request_chunk_batch_event_system(
     mut client: ResMut<RenetClient>,
     mut batch_events: MessageReader<terrain_events::RequestChunkBatch>,
     mut all_requests: ResMut<terrain_resources::RequestedChunks>,
+    chunk_cache: Option<Res<ChunkCache>>,
+    time: Res<Time>,
 ) {
     if batch_events.is_empty() {
         return;
@@ -67,26 +97,76 @@ pub fn handle_chunk_request_chunk_batch_event_system(
         });
     }
 
-    let old_positions = &all_requests.previous_chunks;
-    let diff: HashSet<&IVec3> = new_positions.difference(old_positions).collect();
-    let diff: Vec<IVec3> = diff.into_iter().copied().collect();
-
-    let batched_positions = diff.chunks(32);
+    let positions = all_requests.start(new_positions, time.elapsed());
+    send_chunk_requests(&mut client, &positions, chunk_cache.as_deref());
+}
 
-    batched_positions.enumerate().for_each(|(index, batch)| {
-        let request_positions = batch.to_vec();
+pub fn retry_timed_out_chunk_requests_system(
+    mut client: ResMut<RenetClient>,
+    mut all_requests: ResMut<terrain_resources::RequestedChunks>,
+    chunk_cache: Option<Res<ChunkCache>>,
+    time: Res<Time>,
+) {
+    let positions = all_requests.retry_timed_out(time.elapsed());
+    if !positions.is_empty() {
         info!(
-            "Sending chunk batch request for {:?}",
-            request_positions.len()
+            "Chunk requests timed out, requesting {} of {} pending chunks again",
+            positions.len(),
+            all_requests.pending_count()
         );
-        let message = bincode::serialize(&NetworkingMessage::ChunkBatchRequest(request_positions));
+        send_chunk_requests(&mut client, &positions, chunk_cache.as_deref());
+    }
+}
+
+pub fn cancel_out_of_range_chunk_requests_system(
+    mut client: ResMut<RenetClient>,
+    mut all_requests: ResMut<terrain_resources::RequestedChunks>,
+    mut cleanup_events: MessageReader<terrain_events::CleanupChunksAroundOrigin>,
+    view_distance: Res<terrain_resources::ViewDistance>,
+) {
+    let Some(event) = cleanup_events.read().last() else {
+        return;
+    };
+
+    let cancelled =
+        all_requests.cancel_outside_distance(&event.center_chunk_position, &view_distance.cleanup);
+    for batch in cancelled.chunks(MAX_CHUNK_BATCH_REQUEST_SIZE) {
+        info!("Cancelling {} chunk requests out of range", batch.len());
+        let message = bincode::serialize(&NetworkingMessage::ChunkRequestCancel(batch.to_vec()));
+        client.send_message(DefaultChannel::ReliableUnordered, message.unwrap());
+    }
+}
+
+/// Requests chunks in batches, with the hashes of cached chunks so that the server
+/// can skip those that did not change. Every request goes through
+/// `RequestedChunks` first, so that it is tracked and retried.
+pub fn send_chunk_requests(
+    client: &mut RenetClient,
+    positions: &[IVec3],
+    chunk_cache: Option<&ChunkCache>,
+) {
+    let mut cached = Vec::new();
+    let mut uncached = Vec::new();
+    for position in positions {
+        match chunk_cache.and_then(|cache| cache.hash(position)) {
+            Some(hash) => cached.push((*position, hash)),
+            None => uncached.push(*position),
+        }
+    }
+
+    for (index, batch) in uncached.chunks(MAX_CHUNK_BATCH_REQUEST_SIZE).enumerate() {
+        info!("Sending chunk batch request for {:?}", batch.len());
+        let message = bincode::serialize(&NetworkingMessage::ChunkBatchRequest(batch.to_vec()));
         info!("requesting chunks #{}", index);
         client.send_message(DefaultChannel::ReliableUnordered, message.unwrap());
-    });
+    }
 
-    diff.iter().for_each(|position| {
-        all_requests.previous_chunks.insert(*position);
-    })
+    for batch in cached.chunks(MAX_CHUNK_BATCH_REQUEST_SIZE) {
+        info!("Sending cached chunk batch request for {:?}", batch.len());
+        let message =
+            bincode::serialize(&NetworkingMessage::CachedChunkBatchRequest(batch.to_vec()));
+        client.send_message(DefaultChannel::ReliableUnordered, message.unwrap());
+    }
 }
 
 pub fn handle_chunk_mesh_update_events_system(
@@ -119,12 +199,13 @@ pub fn handle_chunk_rerequests_system(
     chunk_manager: Res<ChunkManager>,
     mut terrain_events: MessageReader<terrain_events::RerequestChunks>,
     mut batch_events: MessageWriter<terrain_events::RequestChunkBatch>,
+    view_distance: Res<terrain_resources::ViewDistance>,
 ) {
     for event in terrain_events.read() {
         info!("Sending chunk requests for chunks");
 
         let origin = event.center_chunk_position;
-        let positions = chunk_manager.sorted_new_chunk_positions(origin, RENDER_DISTANCE);
+        let positions = chunk_manager.sorted_new_chunk_positions(origin, view_distance.render);
         batch_events.write(terrain_events::RequestChunkBatch { positions });
     }
 }
@@ -207,12 +288,13 @@ pub fn cleanup_chunk_entities_system(
     mut commands: Commands,
     mut chunk_entities: ResMut<terrain_resources::ChunkEntityMap>,
     mut cleanup_events: MessageReader<terrain_events::CleanupChunksAroundOrigin>,
+    view_distance: Res<terrain_resources::ViewDistance>,
 ) {
     let last_event = cleanup_events.read().last();
 
     if let Some(event) = last_event {
         chunk_entities
-            .extract_outside_distance(&event.center_chunk_position, &CLEANUP_DISTANCE)
+            .extract_outside_distance(&event.center_chunk_position, &view_distance.cleanup)
             .iter()
             .for_each(|(_position, entities)| {
                 entities
@@ -222,6 +304,23 @@ pub fn cleanup_chunk_entities_system(
     }
 }
 
+pub fn reset_terrain_system(
+    mut commands: Commands,
+    mut chunk_manager: ResMut<ChunkManager>,
+    mut chunk_entities: ResMut<terrain_resources::ChunkEntityMap>,
+    mut requested_chunks: ResMut<terrain_resources::RequestedChunks>,
+    mut mesher_tasks: ResMut<MesherTasks>,
+    mut spawn_area_loaded: ResMut<terrain_resources::SpawnRegionLoaded>,
+) {
+    *chunk_manager = ChunkManager::new();
+    chunk_entities
+        .drain()
+        .for_each(|entity| commands.entity(entity).despawn());
+    requested_chunks.clear();
+    mesher_tasks.task_list.clear();
+    spawn_area_loaded.0 = false;
+}
+
 pub fn check_if_spawn_area_is_loaded_system(
     chunk_manager: Res<ChunkManager>,
     spawn_area: Res<terrain_resources::SpawnRegion>,
@@ -316,13 +415,15 @@ fn obtain_texture_handle(asset_server: &Res<AssetServer>) -> Handle<Image> {
 pub fn handle_terrain_regeneration_events_system(
     mut client: ResMut<RenetClient>,
     mut world_regenerate_events: MessageReader<terrain_events::WorldRegenerateEvent>,
-    chunk_manager: ResMut<ChunkManager>,
+    chunk_manager: Res<ChunkManager>,
+    mut all_requests: ResMut<terrain_resources::RequestedChunks>,
+    chunk_cache: Option<Res<ChunkCache>>,
+    time: Res<Time>,
 ) {
     for _ in world_regenerate_events.read() {
         info!("Rerequesting all chunks from server");
-        let all_chunk_positions = chunk_manager.get_all_chunk_positions();
-        let message =
-            bincode::serialize(&NetworkingMessage::ChunkBatchRequest(all_chunk_positions));
-        client.send_message(DefaultChannel::ReliableUnordered, message.unwrap());
+        let positions =
+            all_requests.restart(chunk_manager.get_all_chunk_positions(), time.elapsed());
+        send_chunk_requests(&mut client, &positions, chunk_cache.as_deref());
     }
 }
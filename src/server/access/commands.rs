@@ -0,0 +1,152 @@
+use std::net::IpAddr;
+
+use serde::Serialize;
+
+use crate::prelude::*;
+
+use chat_resources::ChatCommandSpec;
+use player_resources::PermissionLevel;
+
+pub const ACCESS_COMMANDS: [ChatCommandSpec; 7] = [
+    ChatCommandSpec {
+        name: "op",
+        usage: "/op <user>",
+        description: "Grant operator permissions",
+        permission: PermissionLevel::Operator,
+    },
+    ChatCommandSpec {
+        name: "deop",
+        usage: "/deop <user>",
+        description: "Revoke operator permissions",
+        permission: PermissionLevel::Operator,
+    },
+    ChatCommandSpec {
+        name: "ban",
+        usage: "/ban <user> [reason]",
+        description: "Ban a player",
+        permission: PermissionLevel::Operator,
+    },
+    ChatCommandSpec {
+        name: "pardon",
+        usage: "/pardon <user>",
+        description: "Unban a player",
+        permission: PermissionLevel::Operator,
+    },
+    ChatCommandSpec {
+        name: "ban-ip",
+        usage: "/ban-ip <ip> [reason]",
+        description: "Ban an IP address",
+        permission: PermissionLevel::Operator,
+    },
+    ChatCommandSpec {
+        name: "pardon-ip",
+        usage: "/pardon-ip <ip>",
+        description: "Unban an IP address",
+        permission: PermissionLevel::Operator,
+    },
+    ChatCommandSpec {
+        name: "whitelist",
+        usage: "/whitelist <on|off|list|add <user>|remove <user>>",
+        description: "Manage the whitelist",
+        permission: PermissionLevel::Operator,
+    },
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum WhitelistCommand {
+    On,
+    Off,
+    List,
+    Add(Username),
+    Remove(Username),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AccessCommand {
+    Op(Username),
+    Deop(Username),
+    Ban {
+        username: Username,
+        reason: Option<String>,
+    },
+    Pardon(Username),
+    BanIp {
+        ip: IpAddr,
+        reason: Option<String>,
+    },
+    PardonIp(IpAddr),
+    Whitelist(WhitelistCommand),
+}
+
+impl AccessCommand {
+    /// Returns `None` if `name` is not an access command.
+    pub fn parse(name: &str, args: &[&str]) -> Option<Result<Self, String>> {
+        let spec = ACCESS_COMMANDS.iter().find(|spec| spec.name == name)?;
+        let usage = || format!("Usage: {}", spec.usage);
+        let reason = |args: &[&str]| (!args.is_empty()).then(|| args.join(" "));
+        let username = Username::new;
+        let ip = |value: &str| {
+            value
+                .parse::<IpAddr>()
+                .map_err(|_| format!("Invalid IP address '{value}'"))
+        };
+
+        let command = match (name, args) {
+            ("op", [user]) => username(*user).map(Self::Op),
+            ("deop", [user]) => username(*user).map(Self::Deop),
+            ("ban", [user, rest @ ..]) => username(*user).map(|username| Self::Ban {
+                username,
+                reason: reason(rest),
+            }),
+            ("pardon", [user]) => username(*user).map(Self::Pardon),
+            ("ban-ip", [address, rest @ ..]) => ip(*address).map(|ip| Self::BanIp {
+                ip,
+                reason: reason(rest),
+            }),
+            ("pardon-ip", [address]) => ip(*address).map(Self::PardonIp),
+            ("whitelist", ["on"]) => Ok(Self::Whitelist(WhitelistCommand::On)),
+            ("whitelist", ["off"]) => Ok(Self::Whitelist(WhitelistCommand::Off)),
+            ("whitelist", ["list"]) => Ok(Self::Whitelist(WhitelistCommand::List)),
+            ("whitelist", ["add", user]) => {
+                username(*user).map(|user| Self::Whitelist(WhitelistCommand::Add(user)))
+            }
+            ("whitelist", ["remove", user]) => {
+                username(*user).map(|user| Self::Whitelist(WhitelistCommand::Remove(user)))
+            }
+            _ => Err(usage()),
+        };
+
+        Some(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_access_commands() {
+        assert_eq!(AccessCommand::parse("list", &[]), None);
+        assert_eq!(
+            AccessCommand::parse("ban", &["Steve", "being", "mean"]),
+            Some(Ok(AccessCommand::Ban {
+                username: Username::from("Steve"),
+                reason: Some(String::from("being mean")),
+            }))
+        );
+        assert_eq!(
+            AccessCommand::parse("pardon-ip", &["::1"]),
+            Some(Ok(AccessCommand::PardonIp("::1".parse().unwrap())))
+        );
+        assert_eq!(
+            AccessCommand::parse("whitelist", &["add", "Alex"]),
+            Some(Ok(AccessCommand::Whitelist(WhitelistCommand::Add(
+                Username::from("Alex")
+            ))))
+        );
+        assert!(AccessCommand::parse("ban-ip", &["localhost"])
+            .unwrap()
+            .is_err());
+        assert!(AccessCommand::parse("op", &[]).unwrap().is_err());
+    }
+}
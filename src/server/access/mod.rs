@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use crate::prelude::*;
+
+pub mod commands;
+pub mod resources;
+pub mod systems;
+
+pub struct AccessPlugin {
+    access_lists: resources::AccessLists,
+}
+
+impl AccessPlugin {
+    pub fn load() -> Result<Self, String> {
+        Self::load_from(Path::new(""))
+    }
+
+    /// Reads the access lists from `directory` instead of the working directory.
+    pub fn load_from(directory: &Path) -> Result<Self, String> {
+        Ok(Self {
+            access_lists: resources::AccessLists::load(directory)?,
+        })
+    }
+}
+
+impl Plugin for AccessPlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building AccessPlugin");
+
+        app.init_resource::<player_resources::PlayerPermissions>();
+        let mut permissions = app
+            .world_mut()
+            .resource_mut::<player_resources::PlayerPermissions>();
+        for username in self.access_lists.operators() {
+            permissions.set(*username, player_resources::PermissionLevel::Operator);
+        }
+
+        app.insert_resource(self.access_lists.clone());
+        app.add_systems(
+            Update,
+            access_systems::handle_console_access_commands_system
+                .after(console_systems::read_console_input_system),
+        );
+
+        #[cfg(feature = "chat")]
+        {
+            app.init_resource::<chat_resources::ChatCommandRegistry>();
+            let mut registry = app
+                .world_mut()
+                .resource_mut::<chat_resources::ChatCommandRegistry>();
+            for spec in access_commands::ACCESS_COMMANDS {
+                registry.register(spec);
+            }
+
+            app.add_systems(
+                Update,
+                access_systems::handle_chat_access_commands_system
+                    .after(chat_systems::dispatch_chat_commands_system)
+                    .before(chat_systems::send_chat_command_replies_system),
+            );
+        }
+    }
+}
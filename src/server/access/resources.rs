@@ -0,0 +1,298 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, ErrorKind},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::prelude::*;
+
+pub const OPS_FILE: &str = "ops.txt";
+pub const BANNED_USERS_FILE: &str = "banned-users.txt";
+pub const BANNED_IPS_FILE: &str = "banned-ips.txt";
+pub const WHITELIST_FILE: &str = "whitelist.txt";
+
+pub const DEFAULT_BAN_REASON: &str = "Banned by an operator";
+
+const ENTRY_SEPARATOR: char = '\t';
+const COMMENT_PREFIX: char = '#';
+
+#[derive(Resource, Clone, Default)]
+pub struct AccessLists {
+    directory: PathBuf,
+    operators: HashSet<Username>,
+    banned_users: HashMap<Username, String>,
+    banned_ips: HashMap<IpAddr, String>,
+    whitelist: HashSet<Username>,
+    whitelist_enabled: bool,
+}
+
+impl AccessLists {
+    pub fn load(directory: &Path) -> Result<Self, String> {
+        let operators = read_entries(&directory.join(OPS_FILE))?
+            .unwrap_or_default()
+            .into_keys()
+            .collect();
+        let banned_users = read_entries(&directory.join(BANNED_USERS_FILE))?.unwrap_or_default();
+        let banned_ips = read_entries(&directory.join(BANNED_IPS_FILE))?.unwrap_or_default();
+        let whitelist = read_entries(&directory.join(WHITELIST_FILE))?;
+
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            operators,
+            banned_users,
+            banned_ips,
+            whitelist_enabled: whitelist.is_some(),
+            whitelist: whitelist.unwrap_or_default().into_keys().collect(),
+        })
+    }
+
+    pub fn check(&self, username: &Username, ip: Option<IpAddr>) -> Result<(), DisconnectReason> {
+        if let Some(reason) = self.banned_users.get(username) {
+            return Err(DisconnectReason::Banned(reason.clone()));
+        }
+
+        if let Some(reason) = ip.and_then(|ip| self.banned_ips.get(&ip)) {
+            return Err(DisconnectReason::IpBanned(reason.clone()));
+        }
+
+        if self.whitelist_enabled && !self.whitelist.contains(username) {
+            return Err(DisconnectReason::NotWhitelisted);
+        }
+
+        Ok(())
+    }
+
+    pub fn operators(&self) -> impl Iterator<Item = &Username> {
+        self.operators.iter()
+    }
+
+    pub fn set_operator(&mut self, username: Username, is_operator: bool) -> io::Result<bool> {
+        let changed = if is_operator {
+            self.operators.insert(username)
+        } else {
+            self.operators.remove(&username)
+        };
+        self.save_operators()?;
+        Ok(changed)
+    }
+
+    pub fn ban_user(&mut self, username: Username, reason: String) -> io::Result<()> {
+        self.banned_users.insert(username, reason);
+        self.save_banned_users()
+    }
+
+    pub fn pardon_user(&mut self, username: &Username) -> io::Result<bool> {
+        let pardoned = self.banned_users.remove(username).is_some();
+        self.save_banned_users()?;
+        Ok(pardoned)
+    }
+
+    pub fn ban_ip(&mut self, ip: IpAddr, reason: String) -> io::Result<()> {
+        self.banned_ips.insert(ip, reason);
+        self.save_banned_ips()
+    }
+
+    pub fn pardon_ip(&mut self, ip: &IpAddr) -> io::Result<bool> {
+        let pardoned = self.banned_ips.remove(ip).is_some();
+        self.save_banned_ips()?;
+        Ok(pardoned)
+    }
+
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.whitelist_enabled
+    }
+
+    pub fn whitelisted(&self) -> impl Iterator<Item = &Username> {
+        self.whitelist.iter()
+    }
+
+    pub fn set_whitelist_enabled(&mut self, enabled: bool) -> io::Result<()> {
+        self.whitelist_enabled = enabled;
+        if enabled {
+            self.save_whitelist()
+        } else {
+            match fs::remove_file(self.directory.join(WHITELIST_FILE)) {
+                Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    pub fn set_whitelisted(&mut self, username: Username, whitelisted: bool) -> io::Result<bool> {
+        let changed = if whitelisted {
+            self.whitelist.insert(username)
+        } else {
+            self.whitelist.remove(&username)
+        };
+        if self.whitelist_enabled {
+            self.save_whitelist()?;
+        }
+        Ok(changed)
+    }
+
+    fn save_operators(&self) -> io::Result<()> {
+        write_entries(
+            &self.directory.join(OPS_FILE),
+            "Operators, one username per line",
+            self.operators
+                .iter()
+                .map(|username| (username.to_string(), None)),
+        )
+    }
+
+    fn save_banned_users(&self) -> io::Result<()> {
+        write_entries(
+            &self.directory.join(BANNED_USERS_FILE),
+            "Banned users: <username><TAB><reason>",
+            self.banned_users
+                .iter()
+                .map(|(username, reason)| (username.to_string(), Some(reason.as_str()))),
+        )
+    }
+
+    fn save_banned_ips(&self) -> io::Result<()> {
+        write_entries(
+            &self.directory.join(BANNED_IPS_FILE),
+            "Banned IP addresses: <ip><TAB><reason>",
+            self.banned_ips
+                .iter()
+                .map(|(ip, reason)| (ip.to_string(), Some(reason.as_str()))),
+        )
+    }
+
+    fn save_whitelist(&self) -> io::Result<()> {
+        write_entries(
+            &self.directory.join(WHITELIST_FILE),
+            "Whitelisted users, one username per line. Delete this file to disable the whitelist",
+            self.whitelist
+                .iter()
+                .map(|username| (username.to_string(), None)),
+        )
+    }
+}
+
+fn read_entries<K>(path: &Path) -> Result<Option<HashMap<K, String>>, String>
+where
+    K: FromStr + Eq + std::hash::Hash,
+    K::Err: std::fmt::Display,
+{
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("Failed to read '{}': {}", path.display(), err)),
+    };
+
+    let mut entries = HashMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+            continue;
+        }
+
+        let (key, reason) = line.split_once(ENTRY_SEPARATOR).unwrap_or((line, ""));
+        let reason = match reason.trim() {
+            "" => String::from(DEFAULT_BAN_REASON),
+            reason => String::from(reason),
+        };
+
+        match key.trim().parse::<K>() {
+            Ok(key) => {
+                entries.insert(key, reason);
+            }
+            Err(err) => warn!(
+                "Skipping invalid entry in '{}' line {}: {}",
+                path.display(),
+                index + 1,
+                err
+            ),
+        }
+    }
+
+    Ok(Some(entries))
+}
+
+fn write_entries<'a>(
+    path: &Path,
+    header: &str,
+    entries: impl Iterator<Item = (String, Option<&'a str>)>,
+) -> io::Result<()> {
+    let mut lines: Vec<String> = entries
+        .map(|(key, reason)| match reason {
+            Some(reason) => format!("{key}{ENTRY_SEPARATOR}{reason}"),
+            None => key,
+        })
+        .collect();
+    lines.sort();
+
+    let mut content = format!("{COMMENT_PREFIX} {header}\n");
+    for line in lines {
+        content.push_str(&line);
+        content.push('\n');
+    }
+
+    fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_directory(name: &str) -> TempDir {
+        TempDir::create(&format!("access_{name}")).unwrap()
+    }
+
+    #[test]
+    fn test_lists_are_saved_and_loaded() {
+        let directory = test_directory("roundtrip");
+        let ip: IpAddr = "10.0.0.7".parse().unwrap();
+
+        let mut lists = AccessLists::load(directory.path()).unwrap();
+        lists.set_operator(Username::from("Alex"), true).unwrap();
+        lists
+            .ban_user(Username::from("Steve"), String::from("griefing"))
+            .unwrap();
+        lists.ban_ip(ip, String::from("spam")).unwrap();
+        lists.set_whitelist_enabled(true).unwrap();
+        lists.set_whitelisted(Username::from("Alex"), true).unwrap();
+
+        let lists = AccessLists::load(directory.path()).unwrap();
+        assert_eq!(
+            lists.operators().collect::<Vec<_>>(),
+            vec![&Username::from("Alex")]
+        );
+        assert!(lists.is_whitelist_enabled());
+        assert_eq!(
+            lists.check(&Username::from("Steve"), None),
+            Err(DisconnectReason::Banned(String::from("griefing")))
+        );
+        assert_eq!(
+            lists.check(&Username::from("Alex"), Some(ip)),
+            Err(DisconnectReason::IpBanned(String::from("spam")))
+        );
+        assert!(lists.check(&Username::from("Alex"), None).is_ok());
+        assert_eq!(
+            lists.check(&Username::from("Herobrine"), None),
+            Err(DisconnectReason::NotWhitelisted)
+        );
+    }
+
+    #[test]
+    fn test_whitelist_is_optional() {
+        let directory = test_directory("whitelist");
+
+        let mut lists = AccessLists::load(directory.path()).unwrap();
+        assert!(!lists.is_whitelist_enabled());
+        assert!(lists.check(&Username::from("Steve"), None).is_ok());
+
+        lists.set_whitelist_enabled(true).unwrap();
+        assert!(directory.join(WHITELIST_FILE).is_file());
+        assert!(lists.check(&Username::from("Steve"), None).is_err());
+
+        lists.set_whitelist_enabled(false).unwrap();
+        assert!(!directory.join(WHITELIST_FILE).exists());
+        assert!(lists.check(&Username::from("Steve"), None).is_ok());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{networking::resources::PendingDisconnects, prelude::*};
+use access_commands::{AccessCommand, WhitelistCommand};
+use access_resources::{AccessLists, DEFAULT_BAN_REASON};
+use player_resources::{PermissionLevel, PlayerPermissions};
+
+#[derive(SystemParam)]
+pub struct AccessControl<'w> {
+    access_lists: ResMut<'w, AccessLists>,
+    permissions: ResMut<'w, PlayerPermissions>,
+    server: networking_systems::GameServer<'w>,
+    pending_disconnects: ResMut<'w, PendingDisconnects>,
+    client_usernames: Res<'w, ClientUsernames>,
+    transport: Res<'w, NetcodeServerTransport>,
+}
+
+impl AccessControl<'_> {
+    pub fn execute(&mut self, command: &AccessCommand) -> Result<String, String> {
+        let io_error = |err: std::io::Error| format!("Failed to save access list: {err}");
+
+        match command {
+            AccessCommand::Op(username) => {
+                self.access_lists
+                    .set_operator(*username, true)
+                    .map_err(io_error)?;
+                self.permissions.set(*username, PermissionLevel::Operator);
+                Ok(format!("Made {username} an operator"))
+            }
+            AccessCommand::Deop(username) => {
+                self.access_lists
+                    .set_operator(*username, false)
+                    .map_err(io_error)?;
+                self.permissions.set(*username, PermissionLevel::Player);
+                Ok(format!("{username} is no longer an operator"))
+            }
+            AccessCommand::Ban { username, reason } => {
+                let reason = reason
+                    .clone()
+                    .unwrap_or_else(|| String::from(DEFAULT_BAN_REASON));
+                self.access_lists
+                    .ban_user(*username, reason.clone())
+                    .map_err(io_error)?;
+
+                if let Some(client_id) = self
+                    .server
+                    .connections()
+                    .accepted_client_id(&self.client_usernames, username)
+                {
+                    self.kick(client_id, DisconnectReason::Banned(reason.clone()));
+                }
+
+                Ok(format!("Banned {username}: {reason}"))
+            }
+            AccessCommand::Pardon(username) => {
+                match self.access_lists.pardon_user(username).map_err(io_error)? {
+                    true => Ok(format!("Unbanned {username}")),
+                    false => Err(format!("{username} is not banned")),
+                }
+            }
+            AccessCommand::BanIp { ip, reason } => {
+                let reason = reason
+                    .clone()
+                    .unwrap_or_else(|| String::from(DEFAULT_BAN_REASON));
+                self.access_lists
+                    .ban_ip(*ip, reason.clone())
+                    .map_err(io_error)?;
+
+                let banned_clients: Vec<ClientId> = self
+                    .server
+                    .connections()
+                    .accepted_clients()
+                    .filter(|client_id| {
+                        self.transport
+                            .client_addr(**client_id)
+                            .is_some_and(|address| address.ip() == *ip)
+                    })
+                    .copied()
+                    .collect();
+                for client_id in banned_clients {
+                    self.kick(client_id, DisconnectReason::IpBanned(reason.clone()));
+                }
+
+                Ok(format!("Banned IP address {ip}: {reason}"))
+            }
+            AccessCommand::PardonIp(ip) => {
+                match self.access_lists.pardon_ip(ip).map_err(io_error)? {
+                    true => Ok(format!("Unbanned IP address {ip}")),
+                    false => Err(format!("IP address {ip} is not banned")),
+                }
+            }
+            AccessCommand::Whitelist(WhitelistCommand::On) => {
+                self.access_lists
+                    .set_whitelist_enabled(true)
+                    .map_err(io_error)?;
+                Ok(String::from("Whitelist enabled"))
+            }
+            AccessCommand::Whitelist(WhitelistCommand::Off) => {
+                self.access_lists
+                    .set_whitelist_enabled(false)
+                    .map_err(io_error)?;
+                Ok(String::from("Whitelist disabled"))
+            }
+            AccessCommand::Whitelist(WhitelistCommand::List) => {
+                let mut usernames: Vec<String> = self
+                    .access_lists
+                    .whitelisted()
+                    .map(Username::to_string)
+                    .collect();
+                usernames.sort();
+
+                let state = match self.access_lists.is_whitelist_enabled() {
+                    true => "enabled",
+                    false => "disabled",
+                };
+                Ok(format!(
+                    "Whitelist ({state}, {} entries): {}",
+                    usernames.len(),
+                    usernames.join(", ")
+                ))
+            }
+            AccessCommand::Whitelist(WhitelistCommand::Add(username)) => {
+                self.access_lists
+                    .set_whitelisted(*username, true)
+                    .map_err(io_error)?;
+                Ok(format!("Added {username} to the whitelist"))
+            }
+            AccessCommand::Whitelist(WhitelistCommand::Remove(username)) => {
+                match self
+                    .access_lists
+                    .set_whitelisted(*username, false)
+                    .map_err(io_error)?
+                {
+                    true => Ok(format!("Removed {username} from the whitelist")),
+                    false => Err(format!("{username} is not whitelisted")),
+                }
+            }
+        }
+    }
+
+    fn kick(&mut self, client_id: ClientId, reason: DisconnectReason) {
+        networking_systems::kick_client(
+            &mut self.server,
+            &mut self.pending_disconnects,
+            client_id,
+            reason,
+        );
+    }
+}
+
+pub fn handle_console_access_commands_system(
+    mut command_events: MessageReader<console_events::ConsoleCommandEvent>,
+    mut access_control: AccessControl,
+) {
+    for event in command_events.read() {
+        let console_commands::ConsoleCommand::Access(command) = &event.0 else {
+            continue;
+        };
+
+        match access_control.execute(command) {
+            Ok(message) => println!("{message}"),
+            Err(message) => eprintln!("{message}"),
+        }
+    }
+}
+
+#[cfg(feature = "chat")]
+pub fn handle_chat_access_commands_system(
+    mut command_events: MessageReader<chat_events::ChatCommandEvent>,
+    mut reply_events: MessageWriter<chat_events::ChatCommandReplyEvent>,
+    mut access_control: AccessControl,
+) {
+    for event in command_events.read() {
+        let args: Vec<&str> = event.args.iter().map(String::as_str).collect();
+        let Some(command) = AccessCommand::parse(&event.name, &args) else {
+            continue;
+        };
+
+        let message = match command {
+            Ok(command) => {
+                info!("{} issued access command {:?}", event.sender, command);
+                access_control
+                    .execute(&command)
+                    .unwrap_or_else(|message| message)
+            }
+            Err(usage) => usage,
+        };
+
+        reply_events.write(chat_events::ChatCommandReplyEvent::from_server(
+            event.client_id,
+            message,
+        ));
+    }
+}
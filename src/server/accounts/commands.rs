@@ -0,0 +1,59 @@
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountCommand {
+    Register {
+        username: Username,
+        password: String,
+    },
+    ResetPassword {
+        username: Username,
+        password: String,
+    },
+    Unregister(Username),
+}
+
+impl AccountCommand {
+    /// Returns `None` if `name` is not an account command.
+    pub fn parse(name: &str, args: &[&str]) -> Option<Result<Self, String>> {
+        let command = match (name, args) {
+            ("register", [user, password]) => Username::new(user).map(|username| Self::Register {
+                username,
+                password: String::from(*password),
+            }),
+            ("register", _) => Err(String::from("Usage: register <user> <password>")),
+            ("passwd", [user, password]) => {
+                Username::new(user).map(|username| Self::ResetPassword {
+                    username,
+                    password: String::from(*password),
+                })
+            }
+            ("passwd", _) => Err(String::from("Usage: passwd <user> <password>")),
+            ("unregister", [user]) => Username::new(user).map(Self::Unregister),
+            ("unregister", _) => Err(String::from("Usage: unregister <user>")),
+            _ => return None,
+        };
+
+        Some(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account_commands() {
+        assert_eq!(AccountCommand::parse("ban", &["Steve"]), None);
+        assert_eq!(
+            AccountCommand::parse("passwd", &["Steve", "hunter22"]),
+            Some(Ok(AccountCommand::ResetPassword {
+                username: Username::from("Steve"),
+                password: String::from("hunter22"),
+            }))
+        );
+        assert!(AccountCommand::parse("register", &["Steve"])
+            .unwrap()
+            .is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use crate::prelude::*;
+
+pub mod commands;
+pub mod resources;
+pub mod systems;
+
+pub struct AccountsPlugin {
+    accounts: resources::Accounts,
+}
+
+impl AccountsPlugin {
+    pub fn load(registration_required: bool) -> Result<Self, String> {
+        Self::load_from(Path::new(""), registration_required)
+    }
+
+    /// Reads `accounts.txt` from `directory` instead of the working directory.
+    pub fn load_from(directory: &Path, registration_required: bool) -> Result<Self, String> {
+        Ok(Self {
+            accounts: resources::Accounts::load(directory, registration_required)?,
+        })
+    }
+}
+
+impl Plugin for AccountsPlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building AccountsPlugin");
+
+        app.insert_resource(self.accounts.clone());
+        app.init_resource::<resources::PendingLogins>();
+        app.init_resource::<resources::LoginAttempts>();
+        app.add_systems(
+            FixedUpdate,
+            accounts_systems::receive_login_system
+                .after(networking_systems::handle_events_system)
+                .before(networking_systems::accept_players_system),
+        );
+        app.add_systems(
+            Update,
+            accounts_systems::handle_console_account_commands_system
+                .after(console_systems::read_console_input_system),
+        );
+    }
+}
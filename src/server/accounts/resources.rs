@@ -0,0 +1,378 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use bevy::tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task};
+
+use crate::prelude::*;
+
+pub const ACCOUNTS_FILE: &str = "accounts.txt";
+pub const MIN_PASSWORD_LENGTH: usize = 6;
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
+pub const MAX_FAILED_LOGINS: usize = 5;
+pub const FAILED_LOGIN_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+const ENTRY_SEPARATOR: char = '\t';
+const COMMENT_PREFIX: char = '#';
+
+#[derive(Resource, Clone, Default)]
+pub struct Accounts {
+    path: PathBuf,
+    password_hashes: HashMap<Username, String>,
+    registration_required: bool,
+}
+
+impl Accounts {
+    pub fn load(directory: &Path, registration_required: bool) -> Result<Self, String> {
+        let path = directory.join(ACCOUNTS_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(format!("Failed to read '{}': {}", path.display(), err)),
+        };
+
+        let mut password_hashes = HashMap::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+
+            let entry = line
+                .split_once(ENTRY_SEPARATOR)
+                .ok_or_else(|| String::from("missing password hash"))
+                .and_then(|(username, hash)| {
+                    PasswordHash::new(hash).map_err(|err| err.to_string())?;
+                    Ok((Username::new(username)?, String::from(hash)))
+                });
+
+            match entry {
+                Ok((username, hash)) => {
+                    password_hashes.insert(username, hash);
+                }
+                Err(err) => warn!(
+                    "Skipping invalid entry in '{}' line {}: {}",
+                    path.display(),
+                    index + 1,
+                    err
+                ),
+            }
+        }
+
+        Ok(Self {
+            path,
+            password_hashes,
+            registration_required,
+        })
+    }
+
+    pub fn is_registered(&self, username: &Username) -> bool {
+        self.password_hashes.contains_key(username)
+    }
+
+    /// Whether usernames without an account are turned away.
+    pub fn is_registration_required(&self) -> bool {
+        self.registration_required
+    }
+
+    /// Checks `password` on the async compute pool, Argon2 is slow on purpose and
+    /// would stall the tick.
+    pub fn verify(&self, username: &Username, password: String) -> Task<bool> {
+        let hash = self.password_hashes.get(username).cloned();
+        AsyncComputeTaskPool::get().spawn(async move {
+            hash.is_some_and(|hash| {
+                PasswordHash::new(&hash).is_ok_and(|hash| {
+                    Argon2::default()
+                        .verify_password(password.as_bytes(), &hash)
+                        .is_ok()
+                })
+            })
+        })
+    }
+
+    pub fn register(&mut self, username: Username, password: &str) -> Result<(), String> {
+        if self.is_registered(&username) {
+            return Err(format!("{username} already has an account"));
+        }
+        self.store(username, password)
+    }
+
+    pub fn reset_password(&mut self, username: Username, password: &str) -> Result<(), String> {
+        if !self.is_registered(&username) {
+            return Err(format!("{username} has no account"));
+        }
+        self.store(username, password)
+    }
+
+    pub fn unregister(&mut self, username: &Username) -> Result<bool, String> {
+        let removed = self.password_hashes.remove(username).is_some();
+        self.save().map_err(save_error)?;
+        Ok(removed)
+    }
+
+    fn store(&mut self, username: Username, password: &str) -> Result<(), String> {
+        let hash = hash_password(password)?;
+        self.password_hashes.insert(username, hash);
+        self.save().map_err(save_error)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let mut lines: Vec<String> = self
+            .password_hashes
+            .iter()
+            .map(|(username, hash)| format!("{username}{ENTRY_SEPARATOR}{hash}"))
+            .collect();
+        lines.sort();
+
+        let mut content = format!("{COMMENT_PREFIX} Accounts: <username><TAB><password hash>\n");
+        for line in lines {
+            content.push_str(&line);
+            content.push('\n');
+        }
+
+        fs::write(&self.path, content)
+    }
+}
+
+fn save_error(err: io::Error) -> String {
+    format!("Failed to save accounts: {err}")
+}
+
+fn hash_password(password: &str) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "Passwords must be at least {MIN_PASSWORD_LENGTH} characters long"
+        ));
+    }
+    if password.len() > MAX_PASSWORD_BYTES {
+        return Err(format!(
+            "Passwords must not be longer than {MAX_PASSWORD_BYTES} bytes"
+        ));
+    }
+
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+        .map_err(|err| format!("Failed to generate salt: {err}"))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| format!("Failed to hash password: {err}"))
+}
+
+struct PendingLogin {
+    username: Username,
+    connected_at: Duration,
+    verification: Option<Task<bool>>,
+}
+
+/// Clients that connected with a registered username and have not logged in yet,
+/// either still waiting for their password or having it checked.
+#[derive(Resource, Default)]
+pub struct PendingLogins {
+    pending: HashMap<ClientId, PendingLogin>,
+}
+
+impl PendingLogins {
+    pub fn insert(&mut self, client_id: ClientId, username: Username, now: Duration) {
+        self.pending.insert(
+            client_id,
+            PendingLogin {
+                username,
+                connected_at: now,
+                verification: None,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, client_id: &ClientId) -> Option<Username> {
+        self.pending
+            .remove(client_id)
+            .map(|pending_login| pending_login.username)
+    }
+
+    /// Clients that have not sent their password yet.
+    pub fn awaiting_password(&self) -> Vec<ClientId> {
+        self.pending
+            .iter()
+            .filter(|(_, pending_login)| pending_login.verification.is_none())
+            .map(|(client_id, _)| *client_id)
+            .collect()
+    }
+
+    pub fn username(&self, client_id: &ClientId) -> Option<Username> {
+        self.pending
+            .get(client_id)
+            .map(|pending_login| pending_login.username)
+    }
+
+    pub fn start_verification(&mut self, client_id: &ClientId, verification: Task<bool>) {
+        if let Some(pending_login) = self.pending.get_mut(client_id) {
+            pending_login.verification = Some(verification);
+        }
+    }
+
+    /// Removes and returns the clients whose password check finished, with whether
+    /// the password was right.
+    pub fn take_verified(&mut self) -> Vec<(ClientId, Username, bool)> {
+        let mut verified = Vec::new();
+        for (client_id, pending_login) in &mut self.pending {
+            let Some(verification) = &mut pending_login.verification else {
+                continue;
+            };
+            if let Some(valid) = block_on(future::poll_once(verification)) {
+                verified.push((*client_id, pending_login.username, valid));
+            }
+        }
+
+        for (client_id, _, _) in &verified {
+            self.pending.remove(client_id);
+        }
+        verified
+    }
+
+    /// Removes and returns the clients that did not log in within `LOGIN_TIMEOUT`.
+    pub fn take_expired(&mut self, now: Duration) -> Vec<ClientId> {
+        let expired: Vec<ClientId> = self
+            .pending
+            .iter()
+            .filter(|(_, pending_login)| {
+                now.saturating_sub(pending_login.connected_at) >= LOGIN_TIMEOUT
+            })
+            .map(|(client_id, _)| *client_id)
+            .collect();
+
+        for client_id in &expired {
+            self.pending.remove(client_id);
+        }
+        expired
+    }
+}
+
+/// Failed logins per IP address. Once an address failed `MAX_FAILED_LOGINS` times
+/// within `FAILED_LOGIN_WINDOW` its logins are refused without checking the password,
+/// which slows down guessing and keeps it from tying up the task pool.
+#[derive(Resource, Default)]
+pub struct LoginAttempts {
+    failures: HashMap<IpAddr, Vec<Duration>>,
+}
+
+impl LoginAttempts {
+    pub fn record_failure(&mut self, ip: IpAddr, now: Duration) {
+        self.failures.retain(|_, failures| {
+            failures.retain(|failed_at| now.saturating_sub(*failed_at) < FAILED_LOGIN_WINDOW);
+            !failures.is_empty()
+        });
+        self.failures.entry(ip).or_default().push(now);
+    }
+
+    pub fn is_limited(&self, ip: &IpAddr, now: Duration) -> bool {
+        self.failures.get(ip).is_some_and(|failures| {
+            failures
+                .iter()
+                .filter(|failed_at| now.saturating_sub(**failed_at) < FAILED_LOGIN_WINDOW)
+                .count()
+                >= MAX_FAILED_LOGINS
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::tasks::TaskPool;
+    use std::net::Ipv4Addr;
+
+    fn test_directory(name: &str) -> TempDir {
+        TempDir::create(&format!("accounts_{name}")).unwrap()
+    }
+
+    fn verify(accounts: &Accounts, username: &Username, password: &str) -> bool {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        block_on(accounts.verify(username, String::from(password)))
+    }
+
+    #[test]
+    fn test_accounts_are_saved_and_verified() {
+        let directory = test_directory("roundtrip");
+        let steve = Username::from("Steve");
+
+        let mut accounts = Accounts::load(directory.path(), false).unwrap();
+        assert!(accounts.register(steve, "short").is_err());
+        accounts.register(steve, "hunter22").unwrap();
+        assert!(accounts.register(steve, "hunter22").is_err());
+        assert!(accounts
+            .reset_password(Username::from("Alex"), "hunter22")
+            .is_err());
+
+        let mut accounts = Accounts::load(directory.path(), false).unwrap();
+        assert!(accounts.is_registered(&steve));
+        assert!(verify(&accounts, &steve, "hunter22"));
+        assert!(!verify(&accounts, &steve, "hunter23"));
+        assert!(!verify(&accounts, &Username::from("Alex"), "hunter22"));
+
+        accounts.reset_password(steve, "correct horse").unwrap();
+        assert!(!verify(&accounts, &steve, "hunter22"));
+        assert!(verify(&accounts, &steve, "correct horse"));
+
+        let content = fs::read_to_string(directory.join(ACCOUNTS_FILE)).unwrap();
+        assert!(!content.contains("correct horse"));
+    }
+
+    #[test]
+    fn test_pending_logins_expire() {
+        let mut pending_logins = PendingLogins::default();
+        pending_logins.insert(1, Username::from("Steve"), Duration::ZERO);
+        pending_logins.insert(2, Username::from("Alex"), Duration::from_secs(10));
+
+        assert!(pending_logins
+            .take_expired(Duration::from_secs(20))
+            .is_empty());
+        assert_eq!(pending_logins.take_expired(LOGIN_TIMEOUT), vec![1]);
+        assert_eq!(pending_logins.remove(&2), Some(Username::from("Alex")));
+        assert_eq!(pending_logins.remove(&1), None);
+    }
+
+    #[test]
+    fn test_finished_verifications_are_taken() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let mut pending_logins = PendingLogins::default();
+        pending_logins.insert(1, Username::from("Steve"), Duration::ZERO);
+        pending_logins.insert(2, Username::from("Alex"), Duration::ZERO);
+        assert_eq!(pending_logins.awaiting_password().len(), 2);
+
+        pending_logins.start_verification(&1, AsyncComputeTaskPool::get().spawn(async { true }));
+        assert_eq!(pending_logins.awaiting_password(), vec![2]);
+
+        let mut verified = Vec::new();
+        while verified.is_empty() {
+            verified = pending_logins.take_verified();
+        }
+        assert_eq!(verified, vec![(1, Username::from("Steve"), true)]);
+        assert_eq!(pending_logins.username(&1), None);
+        assert_eq!(pending_logins.username(&2), Some(Username::from("Alex")));
+    }
+
+    #[test]
+    fn test_failed_logins_are_limited_per_ip() {
+        let mut login_attempts = LoginAttempts::default();
+        let ip = IpAddr::from(Ipv4Addr::new(203, 0, 113, 7));
+        let other_ip = IpAddr::from(Ipv4Addr::new(203, 0, 113, 8));
+
+        for _ in 0..MAX_FAILED_LOGINS - 1 {
+            login_attempts.record_failure(ip, Duration::ZERO);
+        }
+        assert!(!login_attempts.is_limited(&ip, Duration::ZERO));
+
+        login_attempts.record_failure(ip, Duration::ZERO);
+        assert!(login_attempts.is_limited(&ip, Duration::ZERO));
+        assert!(!login_attempts.is_limited(&other_ip, Duration::ZERO));
+        assert!(!login_attempts.is_limited(&ip, FAILED_LOGIN_WINDOW));
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::time::Time;
+
+use crate::{networking::resources::PendingDisconnects, prelude::*};
+use accounts_commands::AccountCommand;
+use accounts_resources::{Accounts, LoginAttempts, PendingLogins};
+
+#[allow(clippy::too_many_arguments)]
+pub fn receive_login_system(
+    mut server: networking_systems::GameServer,
+    mut pending_logins: ResMut<PendingLogins>,
+    mut login_attempts: ResMut<LoginAttempts>,
+    mut pending_disconnects: ResMut<PendingDisconnects>,
+    mut authenticated_events: MessageWriter<networking_events::PlayerAuthenticatedEvent>,
+    accounts: Res<Accounts>,
+    transport: Res<NetcodeServerTransport>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    let client_ip =
+        |client_id: ClientId| transport.client_addr(client_id).map(|address| address.ip());
+
+    for client_id in pending_logins.awaiting_password() {
+        let Some(message) = server.receive_message(client_id, DefaultChannel::ReliableOrdered)
+        else {
+            continue;
+        };
+        let Some(username) = pending_logins.username(&client_id) else {
+            continue;
+        };
+
+        let reason = match networking_systems::decode_client_message(&message) {
+            Ok(NetworkingMessage::Login { .. })
+                if client_ip(client_id).is_some_and(|ip| login_attempts.is_limited(&ip, now)) =>
+            {
+                println!("Client {client_id} is logging in as {username} too often");
+                DisconnectReason::TooManyLoginAttempts
+            }
+            Ok(NetworkingMessage::Login { password }) => {
+                pending_logins.start_verification(&client_id, accounts.verify(&username, password));
+                continue;
+            }
+            _ => DisconnectReason::ProtocolViolation,
+        };
+        pending_logins.remove(&client_id);
+        networking_systems::kick_client(&mut server, &mut pending_disconnects, client_id, reason);
+    }
+
+    for (client_id, username, valid) in pending_logins.take_verified() {
+        if valid {
+            println!("{username} logged in");
+            authenticated_events.write(networking_events::PlayerAuthenticatedEvent {
+                client_id,
+                username,
+            });
+            continue;
+        }
+
+        println!("Client {client_id} failed to log in as {username}");
+        if let Some(ip) = client_ip(client_id) {
+            login_attempts.record_failure(ip, now);
+        }
+        networking_systems::kick_client(
+            &mut server,
+            &mut pending_disconnects,
+            client_id,
+            DisconnectReason::WrongPassword,
+        );
+    }
+
+    for client_id in pending_logins.take_expired(now) {
+        networking_systems::kick_client(
+            &mut server,
+            &mut pending_disconnects,
+            client_id,
+            DisconnectReason::LoginTimedOut,
+        );
+    }
+}
+
+pub fn handle_console_account_commands_system(
+    mut command_events: MessageReader<console_events::ConsoleCommandEvent>,
+    mut accounts: ResMut<Accounts>,
+) {
+    for event in command_events.read() {
+        let console_commands::ConsoleCommand::Account(command) = &event.0 else {
+            continue;
+        };
+
+        let result = match command {
+            AccountCommand::Register { username, password } => accounts
+                .register(*username, password)
+                .map(|_| format!("Registered {username}")),
+            AccountCommand::ResetPassword { username, password } => accounts
+                .reset_password(*username, password)
+                .map(|_| format!("Reset the password of {username}")),
+            AccountCommand::Unregister(username) => {
+                accounts
+                    .unregister(username)
+                    .and_then(|removed| match removed {
+                        true => Ok(format!("Deleted the account of {username}")),
+                        false => Err(format!("{username} has no account")),
+                    })
+            }
+        };
+
+        match result {
+            Ok(message) => println!("{message}"),
+            Err(message) => eprintln!("{message}"),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{networking::resources::ActiveConnections, prelude::*};
+
+use chat_events::{ChatCommandEvent, ChatCommandReplyEvent};
+use chat_resources::{ChatCommandRegistry, ChatCommandSpec};
+use player_resources::PermissionLevel;
+
+pub const COMMAND_PREFIX: char = '/';
+
+pub const HELP: ChatCommandSpec = ChatCommandSpec {
+    name: "help",
+    usage: "/help",
+    description: "List the commands you can use",
+    permission: PermissionLevel::Player,
+};
+
+pub const LIST: ChatCommandSpec = ChatCommandSpec {
+    name: "list",
+    usage: "/list",
+    description: "List connected players",
+    permission: PermissionLevel::Player,
+};
+
+pub const MSG: ChatCommandSpec = ChatCommandSpec {
+    name: "msg",
+    usage: "/msg <user> <message>",
+    description: "Send a private message",
+    permission: PermissionLevel::Player,
+};
+
+pub const ME: ChatCommandSpec = ChatCommandSpec {
+    name: "me",
+    usage: "/me <action>",
+    description: "Describe an action",
+    permission: PermissionLevel::Player,
+};
+
+pub const SPAWN: ChatCommandSpec = ChatCommandSpec {
+    name: "spawn",
+    usage: "/spawn",
+    description: "Teleport to the spawn point",
+    permission: PermissionLevel::Player,
+};
+
+pub const SEED: ChatCommandSpec = ChatCommandSpec {
+    name: "seed",
+    usage: "/seed",
+    description: "Show the world seed",
+    permission: PermissionLevel::Operator,
+};
+
+pub fn register_builtin_commands(registry: &mut ChatCommandRegistry) {
+    for spec in [HELP, LIST, MSG, ME, SPAWN, SEED] {
+        registry.register(spec);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ChatCommandInput {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl ChatCommandInput {
+    pub fn parse(message: &str) -> Option<Self> {
+        let mut parts = message
+            .trim()
+            .strip_prefix(COMMAND_PREFIX)?
+            .split_whitespace();
+        let name = parts.next()?.to_lowercase();
+        let args = parts.map(String::from).collect();
+
+        Some(Self { name, args })
+    }
+}
+
+fn read_command<'a>(
+    command_events: &'a mut MessageReader<ChatCommandEvent>,
+    spec: &'a ChatCommandSpec,
+) -> impl Iterator<Item = &'a ChatCommandEvent> {
+    command_events
+        .read()
+        .filter(move |event| event.name == spec.name)
+}
+
+fn usage(event: &ChatCommandEvent, spec: &ChatCommandSpec) -> ChatCommandReplyEvent {
+    ChatCommandReplyEvent::from_server(event.client_id, format!("Usage: {}", spec.usage))
+}
+
+pub fn help_command_system(
+    mut command_events: MessageReader<ChatCommandEvent>,
+    mut reply_events: MessageWriter<ChatCommandReplyEvent>,
+    registry: Res<ChatCommandRegistry>,
+    permissions: Res<player_resources::PlayerPermissions>,
+) {
+    for event in read_command(&mut command_events, &HELP) {
+        let lines: Vec<String> = registry
+            .available_to(permissions.level(&event.sender))
+            .map(|spec| format!("{} - {}", spec.usage, spec.description))
+            .collect();
+
+        reply_events.write(ChatCommandReplyEvent::from_server(
+            event.client_id,
+            lines.join("\n"),
+        ));
+    }
+}
+
+pub fn list_command_system(
+    mut command_events: MessageReader<ChatCommandEvent>,
+    mut reply_events: MessageWriter<ChatCommandReplyEvent>,
+    active_connections: Res<ActiveConnections>,
+    client_usernames: Res<ClientUsernames>,
+) {
+    for event in read_command(&mut command_events, &LIST) {
+        let usernames: Vec<String> = active_connections
+            .accepted_usernames(&client_usernames)
+            .iter()
+            .map(Username::to_string)
+            .collect();
+
+        reply_events.write(ChatCommandReplyEvent::from_server(
+            event.client_id,
+            format!("{} online: {}", usernames.len(), usernames.join(", ")),
+        ));
+    }
+}
+
+pub fn msg_command_system(
+    mut command_events: MessageReader<ChatCommandEvent>,
+    mut reply_events: MessageWriter<ChatCommandReplyEvent>,
+    active_connections: Res<ActiveConnections>,
+    client_usernames: Res<ClientUsernames>,
+) {
+    for event in read_command(&mut command_events, &MSG) {
+        let [target, ..] = &event.args[..] else {
+            reply_events.write(usage(event, &MSG));
+            continue;
+        };
+        let message = event.args[1..].join(" ");
+        if message.is_empty() {
+            reply_events.write(usage(event, &MSG));
+            continue;
+        }
+
+        let target_client_id = Username::new(target)
+            .ok()
+            .and_then(|target| active_connections.accepted_client_id(&client_usernames, &target));
+        let Some(target_client_id) = target_client_id else {
+            reply_events.write(ChatCommandReplyEvent::from_server(
+                event.client_id,
+                format!("Player '{target}' is not online"),
+            ));
+            continue;
+        };
+
+        reply_events.write(ChatCommandReplyEvent {
+            client_id: target_client_id,
+            sender: ChatMessageSender::Player(event.sender),
+            message: format!("(whisper) {message}"),
+        });
+        reply_events.write(ChatCommandReplyEvent::from_server(
+            event.client_id,
+            format!("To {target}: {message}"),
+        ));
+    }
+}
+
+pub fn me_command_system(
+    mut command_events: MessageReader<ChatCommandEvent>,
+    mut reply_events: MessageWriter<ChatCommandReplyEvent>,
+    mut chat_message_events: MessageWriter<chat_events::PlayerChatMessageSendEvent>,
+) {
+    for event in read_command(&mut command_events, &ME) {
+        if event.args.is_empty() {
+            reply_events.write(usage(event, &ME));
+            continue;
+        }
+
+        chat_message_events.write(chat_events::PlayerChatMessageSendEvent {
+            sender: ChatMessageSender::Server,
+            message: format!("* {} {}", event.sender, event.args.join(" ")),
+        });
+    }
+}
+
+pub fn spawn_command_system(
+    mut command_events: MessageReader<ChatCommandEvent>,
+    mut reply_events: MessageWriter<ChatCommandReplyEvent>,
+    mut server: networking_systems::GameServer,
+    mut player_states: ResMut<player_resources::PlayerStates>,
+    spawn_position: networking_systems::SpawnPosition,
+) {
+    for event in read_command(&mut command_events, &SPAWN) {
+        let position = spawn_position.get();
+
+        if let Some(player_state) = player_states.players.get_mut(&event.sender) {
+            player_state.position = position;
+        }
+
+        server.send(
+            event.client_id,
+            DefaultChannel::ReliableOrdered,
+            &NetworkingMessage::PlayerTeleport(position),
+        );
+        reply_events.write(ChatCommandReplyEvent::from_server(
+            event.client_id,
+            String::from("Teleported to spawn"),
+        ));
+    }
+}
+
+pub fn seed_command_system(
+    mut command_events: MessageReader<ChatCommandEvent>,
+    mut reply_events: MessageWriter<ChatCommandReplyEvent>,
+    generator: Res<terrain_resources::Generator>,
+) {
+    for event in read_command(&mut command_events, &SEED) {
+        reply_events.write(ChatCommandReplyEvent::from_server(
+            event.client_id,
+            format!("Seed: [{}]", generator.noise.seed),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chat_command() {
+        assert_eq!(
+            ChatCommandInput::parse("/msg Steve  hello there"),
+            Some(ChatCommandInput {
+                name: String::from("msg"),
+                args: vec![
+                    String::from("Steve"),
+                    String::from("hello"),
+                    String::from("there")
+                ],
+            })
+        );
+        assert_eq!(
+            ChatCommandInput::parse(" /HELP"),
+            Some(ChatCommandInput {
+                name: String::from("help"),
+                args: vec![],
+            })
+        );
+        assert_eq!(ChatCommandInput::parse("/"), None);
+        assert_eq!(ChatCommandInput::parse("hello /help"), None);
+    }
+}
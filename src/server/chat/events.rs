@@ -10,3 +10,28 @@ pub struct PlayerChatMessageSendEvent {
 pub struct SyncPlayerChatMessagesEvent {
     pub client_id: ClientId,
 }
+
+#[derive(Message, Debug)]
+pub struct ChatCommandEvent {
+    pub client_id: ClientId,
+    pub sender: Username,
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Message, Debug)]
+pub struct ChatCommandReplyEvent {
+    pub client_id: ClientId,
+    pub sender: ChatMessageSender,
+    pub message: String,
+}
+
+impl ChatCommandReplyEvent {
+    pub fn from_server(client_id: ClientId, message: String) -> Self {
+        Self {
+            client_id,
+            sender: ChatMessageSender::Server,
+            message,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+pub mod commands;
 pub mod events;
 pub mod resources;
 pub mod systems;
@@ -10,6 +11,14 @@ impl Plugin for ChatPlugin {
     fn build(&self, app: &mut App) {
         info!("Building ChatPlugin");
         app.insert_resource(resources::ChatHistory::new());
+
+        app.init_resource::<resources::ChatCommandRegistry>();
+        chat_commands::register_builtin_commands(
+            &mut app
+                .world_mut()
+                .resource_mut::<resources::ChatCommandRegistry>(),
+        );
+
         app.add_systems(
             Update,
             (
@@ -17,7 +26,25 @@ impl Plugin for ChatPlugin {
                 chat_systems::sync_single_player_chat_messages_system,
             ),
         );
+        app.add_systems(
+            Update,
+            (
+                chat_systems::dispatch_chat_commands_system,
+                (
+                    chat_commands::help_command_system,
+                    chat_commands::list_command_system,
+                    chat_commands::msg_command_system,
+                    chat_commands::me_command_system,
+                    chat_commands::spawn_command_system,
+                    chat_commands::seed_command_system,
+                ),
+                chat_systems::send_chat_command_replies_system,
+            )
+                .chain(),
+        );
         app.add_message::<chat_events::PlayerChatMessageSendEvent>();
         app.add_message::<chat_events::SyncPlayerChatMessagesEvent>();
+        app.add_message::<chat_events::ChatCommandEvent>();
+        app.add_message::<chat_events::ChatCommandReplyEvent>();
     }
 }
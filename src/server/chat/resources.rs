@@ -1,16 +1,28 @@
 use crate::prelude::*;
 
+use player_resources::PermissionLevel;
+
 #[derive(Resource, Debug)]
 pub struct ChatHistory {
     pub messages: Vec<ChatMessage>,
+    next_message_id: usize,
 }
 
 impl ChatHistory {
     pub fn new() -> Self {
         Self {
             messages: Vec::new(),
+            next_message_id: 0,
         }
     }
+
+    /// Ids are shared with command replies, which are sent to a single player and
+    /// not kept in the history.
+    pub fn next_message_id(&mut self) -> usize {
+        let message_id = self.next_message_id;
+        self.next_message_id += 1;
+        message_id
+    }
 }
 
 impl Default for ChatHistory {
@@ -18,3 +30,37 @@ impl Default for ChatHistory {
         Self::new()
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChatCommandSpec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub description: &'static str,
+    pub permission: PermissionLevel,
+}
+
+#[derive(Resource, Default)]
+pub struct ChatCommandRegistry {
+    commands: Vec<ChatCommandSpec>,
+}
+
+impl ChatCommandRegistry {
+    pub fn register(&mut self, spec: ChatCommandSpec) {
+        assert!(
+            self.get(spec.name).is_none(),
+            "Chat command '/{}' is already registered",
+            spec.name
+        );
+        self.commands.push(spec);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ChatCommandSpec> {
+        self.commands.iter().find(|spec| spec.name == name)
+    }
+
+    pub fn available_to(&self, level: PermissionLevel) -> impl Iterator<Item = &ChatCommandSpec> {
+        self.commands
+            .iter()
+            .filter(move |spec| spec.permission <= level)
+    }
+}
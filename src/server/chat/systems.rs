@@ -3,17 +3,23 @@ use std::time::UNIX_EPOCH;
 use crate::prelude::*;
 
 pub fn sync_single_player_chat_messages_system(
-    mut server: ResMut<RenetServer>,
+    mut server: networking_systems::GameServer,
     mut player_send_messages: MessageReader<chat_events::PlayerChatMessageSendEvent>,
     mut chat_messages: ResMut<chat_resources::ChatHistory>,
 ) {
     for event in player_send_messages.read() {
-        let message = event.message.clone();
+        if matches!(event.sender, ChatMessageSender::Player(_))
+            && chat_commands::ChatCommandInput::parse(&event.message).is_some()
+        {
+            continue;
+        }
+
+        let mut message = event.message.clone();
+        truncate_to_byte_limit(&mut message, MAX_CHAT_MESSAGE_BYTES);
         let sender = event.sender.clone();
 
         info!("Broadcasting message from sender {sender}");
-        let message_count = chat_messages.messages.len();
-        let message_id = message_count;
+        let message_id = chat_messages.next_message_id();
 
         let chat_message = ChatMessage {
             sender,
@@ -26,15 +32,12 @@ pub fn sync_single_player_chat_messages_system(
 
         let response_message = NetworkingMessage::SingleChatMessageSync(chat_message);
 
-        server.broadcast_message(
-            DefaultChannel::ReliableOrdered,
-            bincode::serialize(&response_message).unwrap(),
-        );
+        server.broadcast(DefaultChannel::ReliableOrdered, &response_message);
     }
 }
 
 pub fn sync_player_chat_messages_event(
-    mut server: ResMut<RenetServer>,
+    mut server: networking_systems::GameServer,
     mut events: MessageReader<chat_events::SyncPlayerChatMessagesEvent>,
     chat_messages: ResMut<chat_resources::ChatHistory>,
 ) {
@@ -42,10 +45,86 @@ pub fn sync_player_chat_messages_event(
         let client_id = event.client_id;
         info!("Synchronizing messages with client {}", client_id);
 
-        let history = chat_messages.messages.clone();
-        let response_message =
-            bincode::serialize(&NetworkingMessage::ChatMessageSync(history)).unwrap();
-        server.send_message(client_id, DefaultChannel::ReliableOrdered, response_message);
+        let skipped = chat_messages
+            .messages
+            .len()
+            .saturating_sub(MAX_CHAT_HISTORY_SYNC);
+        let history = chat_messages.messages[skipped..].to_vec();
+        server.send(
+            client_id,
+            DefaultChannel::ReliableOrdered,
+            &NetworkingMessage::ChatMessageSync(history),
+        );
+    }
+}
+
+pub fn dispatch_chat_commands_system(
+    mut player_send_messages: MessageReader<chat_events::PlayerChatMessageSendEvent>,
+    mut command_events: MessageWriter<chat_events::ChatCommandEvent>,
+    mut reply_events: MessageWriter<chat_events::ChatCommandReplyEvent>,
+    registry: Res<chat_resources::ChatCommandRegistry>,
+    permissions: Res<player_resources::PlayerPermissions>,
+    client_usernames: Res<ClientUsernames>,
+) {
+    for event in player_send_messages.read() {
+        let ChatMessageSender::Player(username) = event.sender else {
+            continue;
+        };
+        let Some(input) = chat_commands::ChatCommandInput::parse(&event.message) else {
+            continue;
+        };
+        let Some(client_id) = client_usernames.get_client_id(&username).copied() else {
+            continue;
+        };
+
+        info!("Player {username} issued command /{}", input.name);
+
+        match registry.get(&input.name) {
+            None => {
+                reply_events.write(chat_events::ChatCommandReplyEvent::from_server(
+                    client_id,
+                    format!(
+                        "Unknown command '/{}'. Type /help for a list of commands.",
+                        input.name
+                    ),
+                ));
+            }
+            Some(spec) if permissions.level(&username) < spec.permission => {
+                reply_events.write(chat_events::ChatCommandReplyEvent::from_server(
+                    client_id,
+                    format!("You don't have permission to use /{}", spec.name),
+                ));
+            }
+            Some(_) => {
+                command_events.write(chat_events::ChatCommandEvent {
+                    client_id,
+                    sender: username,
+                    name: input.name,
+                    args: input.args,
+                });
+            }
+        }
+    }
+}
+
+pub fn send_chat_command_replies_system(
+    mut server: networking_systems::GameServer,
+    mut reply_events: MessageReader<chat_events::ChatCommandReplyEvent>,
+    mut chat_messages: ResMut<chat_resources::ChatHistory>,
+) {
+    for event in reply_events.read() {
+        let chat_message = ChatMessage {
+            sender: event.sender.clone(),
+            message_id: chat_messages.next_message_id(),
+            message: event.message.clone(),
+            timestamp: get_current_time_in_ms(),
+        };
+
+        server.send(
+            event.client_id,
+            DefaultChannel::ReliableOrdered,
+            &NetworkingMessage::SingleChatMessageSync(chat_message),
+        );
     }
 }
 
@@ -66,3 +145,97 @@ fn get_current_time_in_ms() -> i64 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use player_resources::PermissionLevel;
+
+    fn setup_app() -> App {
+        let mut app = App::new();
+        app.add_message::<chat_events::PlayerChatMessageSendEvent>();
+        app.add_message::<chat_events::ChatCommandEvent>();
+        app.add_message::<chat_events::ChatCommandReplyEvent>();
+        app.add_systems(Update, dispatch_chat_commands_system);
+
+        let mut registry = chat_resources::ChatCommandRegistry::default();
+        chat_commands::register_builtin_commands(&mut registry);
+        app.insert_resource(registry);
+        app.insert_resource(player_resources::PlayerPermissions::default());
+
+        let mut client_usernames = ClientUsernames::default();
+        client_usernames.insert(1, Username::from("Steve"));
+        app.insert_resource(client_usernames);
+
+        app
+    }
+
+    fn send_chat_message(app: &mut App, message: &str) {
+        app.world_mut()
+            .write_message(chat_events::PlayerChatMessageSendEvent {
+                sender: ChatMessageSender::Player(Username::from("Steve")),
+                message: String::from(message),
+            });
+        app.update();
+    }
+
+    fn drain<E: Message>(app: &mut App) -> Vec<E> {
+        app.world_mut()
+            .resource_mut::<Messages<E>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn test_command_is_dispatched() {
+        let mut app = setup_app();
+        send_chat_message(&mut app, "/msg Alex hi");
+
+        let commands = drain::<chat_events::ChatCommandEvent>(&mut app);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].client_id, 1);
+        assert_eq!(commands[0].name, "msg");
+        assert_eq!(commands[0].args, vec!["Alex", "hi"]);
+        assert!(drain::<chat_events::ChatCommandReplyEvent>(&mut app).is_empty());
+    }
+
+    #[test]
+    fn test_plain_messages_are_ignored() {
+        let mut app = setup_app();
+        send_chat_message(&mut app, "hello /seed");
+
+        assert!(drain::<chat_events::ChatCommandEvent>(&mut app).is_empty());
+        assert!(drain::<chat_events::ChatCommandReplyEvent>(&mut app).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_command_replies_to_sender() {
+        let mut app = setup_app();
+        send_chat_message(&mut app, "/dance");
+
+        let replies = drain::<chat_events::ChatCommandReplyEvent>(&mut app);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].client_id, 1);
+        assert!(replies[0].message.contains("Unknown command"));
+    }
+
+    #[test]
+    fn test_command_permission_is_checked() {
+        let mut app = setup_app();
+        send_chat_message(&mut app, "/seed");
+
+        assert!(drain::<chat_events::ChatCommandEvent>(&mut app).is_empty());
+        let replies = drain::<chat_events::ChatCommandReplyEvent>(&mut app);
+        assert_eq!(replies.len(), 1);
+        assert!(replies[0].message.contains("permission"));
+
+        app.world_mut()
+            .resource_mut::<player_resources::PlayerPermissions>()
+            .set(Username::from("Steve"), PermissionLevel::Operator);
+        send_chat_message(&mut app, "/seed");
+
+        let commands = drain::<chat_events::ChatCommandEvent>(&mut app);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].name, "seed");
+    }
+}
@@ -0,0 +1,479 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::Args;
+use serde::Serialize;
+
+use crate::prelude::*;
+
+pub const CONFIG_FILE: &str = "server.toml";
+pub const PRIVATE_KEY_FILE: &str = "private.key";
+pub const DEFAULT_MOTD: &str = "A rsmc server";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub address: SocketAddr,
+    /// Address clients connect to, written into connect tokens. Needed when `address`
+    /// is not reachable by clients, like `0.0.0.0` or behind NAT. Defaults to `address`.
+    pub public_address: Option<SocketAddr>,
+    pub max_clients: usize,
+    pub tick_rate: f64,
+    pub save_interval_seconds: u64,
+    pub backup_interval_seconds: u64,
+    pub spawn_point: IVec3,
+    pub player_sync_rate: f32,
+    pub player_view_radius: f32,
+    /// Only accept clients with a connect token signed by the private key.
+    pub secure: bool,
+    pub private_key_file: PathBuf,
+    /// Turn away usernames without an account in `accounts.txt`. Needs `secure`, since
+    /// passwords travel unencrypted otherwise.
+    pub require_accounts: bool,
+    /// Shown by status queries.
+    pub motd: String,
+    /// UDP port for status queries on the server's IP address, `0` disables them.
+    pub status_port: u16,
+    /// Broadcast the server status so that clients on the same network can find it.
+    pub lan_discovery: bool,
+    pub lan_discovery_address: SocketAddr,
+    /// Serve Prometheus metrics over HTTP.
+    pub metrics: bool,
+    pub metrics_address: SocketAddr,
+    /// Simulate a bad connection between the server and every client. Clients appear
+    /// to connect from loopback addresses while it is enabled, so IP bans do not apply.
+    pub link_conditioner: LinkConditionerSettings,
+    /// Deflate chunk batches when that makes them smaller.
+    pub chunk_compression: bool,
+    /// Chunk bytes sent to every client per second, `0` for no limit.
+    pub chunk_bytes_per_second: u64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        let sync_config = player_resources::PlayerSyncConfig::default();
+        let chunk_streaming = terrain_resources::ChunkStreamingConfig::default();
+
+        Self {
+            address: crate::networking::DEFAULT_SERVER_ADDR
+                .parse()
+                .expect("Hardcoded server address should be valid"),
+            public_address: None,
+            max_clients: MAX_PLAYERS,
+            tick_rate: crate::tick::DEFAULT_TICK_RATE,
+            save_interval_seconds: terrain_resources::DEFAULT_SAVE_INTERVAL_SECONDS,
+            backup_interval_seconds: terrain_resources::DEFAULT_BACKUP_INTERVAL_SECONDS,
+            spawn_point: DEFAULT_SPAWN_POINT,
+            player_sync_rate: sync_config.tick_rate,
+            player_view_radius: sync_config.view_radius,
+            secure: false,
+            private_key_file: PathBuf::from(PRIVATE_KEY_FILE),
+            require_accounts: false,
+            motd: String::from(DEFAULT_MOTD),
+            status_port: DEFAULT_STATUS_PORT,
+            lan_discovery: false,
+            lan_discovery_address: (Ipv4Addr::BROADCAST, DEFAULT_DISCOVERY_PORT).into(),
+            metrics: false,
+            metrics_address: crate::metrics::DEFAULT_METRICS_ADDR
+                .parse()
+                .expect("Hardcoded metrics address should be valid"),
+            link_conditioner: LinkConditionerSettings::default(),
+            chunk_compression: chunk_streaming.compression,
+            chunk_bytes_per_second: chunk_streaming.bytes_per_second,
+        }
+    }
+}
+
+impl ServerSettings {
+    pub fn load(args: &ServerSettingsArgs) -> Result<Self, String> {
+        let mut settings: Self = load_or_create_config(&args.config)?;
+        args.apply(&mut settings);
+        settings
+            .validate()
+            .map_err(|err| format!("Invalid server configuration: {err}"))?;
+        Ok(settings)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_clients == 0 || self.max_clients > MAX_PLAYERS {
+            return Err(format!(
+                "max_clients must be between 1 and {MAX_PLAYERS}, got {}",
+                self.max_clients
+            ));
+        }
+        if !self.tick_rate.is_finite() || self.tick_rate <= 0.0 {
+            return Err(format!(
+                "tick_rate must be a positive number, got {}",
+                self.tick_rate
+            ));
+        }
+        if self.save_interval_seconds == 0 || self.backup_interval_seconds == 0 {
+            return Err(String::from(
+                "save_interval_seconds and backup_interval_seconds must be at least 1",
+            ));
+        }
+        if !self.player_sync_rate.is_finite() || self.player_sync_rate <= 0.0 {
+            return Err(format!(
+                "player_sync_rate must be a positive number, got {}",
+                self.player_sync_rate
+            ));
+        }
+        if !self.player_view_radius.is_finite() || self.player_view_radius <= 0.0 {
+            return Err(format!(
+                "player_view_radius must be a positive number, got {}",
+                self.player_view_radius
+            ));
+        }
+        if self.motd.len() > MAX_MOTD_BYTES {
+            return Err(format!(
+                "motd must not be longer than {MAX_MOTD_BYTES} bytes"
+            ));
+        }
+        if let Some(public_address) = self.public_address {
+            if public_address.ip().is_unspecified() || public_address.port() == 0 {
+                return Err(format!(
+                    "public_address must be an address clients can connect to, got {public_address}"
+                ));
+            }
+        }
+        if self.require_accounts && !self.secure {
+            return Err(String::from(
+                "require_accounts needs secure, passwords would be sent unencrypted otherwise",
+            ));
+        }
+        if self.status_port != 0 && self.status_port == self.address.port() {
+            return Err(String::from(
+                "status_port must differ from the port in address",
+            ));
+        }
+        self.link_conditioner
+            .validate()
+            .map_err(|err| format!("link_conditioner.{err}"))?;
+        Ok(())
+    }
+
+    pub fn public_address(&self) -> SocketAddr {
+        self.public_address.unwrap_or(self.address)
+    }
+
+    pub fn save_interval(&self) -> Duration {
+        Duration::from_secs(self.save_interval_seconds)
+    }
+
+    pub fn backup_interval(&self) -> Duration {
+        Duration::from_secs(self.backup_interval_seconds)
+    }
+
+    /// Returns the private key in secure mode, creating it on first use.
+    pub fn private_key(&self) -> Result<Option<PrivateKey>, String> {
+        if !self.secure {
+            return Ok(None);
+        }
+        load_or_create_private_key(&self.private_key_file).map(Some)
+    }
+
+    pub fn status_address(&self) -> Option<SocketAddr> {
+        match self.status_port {
+            0 => None,
+            port => Some(SocketAddr::new(self.address.ip(), port)),
+        }
+    }
+
+    pub fn player_sync_config(&self) -> player_resources::PlayerSyncConfig {
+        player_resources::PlayerSyncConfig {
+            tick_rate: self.player_sync_rate,
+            view_radius: self.player_view_radius,
+        }
+    }
+
+    pub fn chunk_streaming_config(&self) -> terrain_resources::ChunkStreamingConfig {
+        terrain_resources::ChunkStreamingConfig {
+            compression: self.chunk_compression,
+            bytes_per_second: self.chunk_bytes_per_second,
+        }
+    }
+}
+
+/// Command line overrides for the values in `server.toml`.
+#[derive(Args, Debug)]
+pub struct ServerSettingsArgs {
+    /// Path to the server configuration file, created with defaults if missing
+    #[arg(long, global = true, default_value = CONFIG_FILE)]
+    config: PathBuf,
+
+    /// Address to listen on
+    #[arg(long, global = true)]
+    address: Option<SocketAddr>,
+
+    /// Address clients connect to, written into connect tokens
+    #[arg(long, global = true)]
+    public_address: Option<SocketAddr>,
+
+    /// Maximum number of connected clients
+    #[arg(long, global = true)]
+    max_clients: Option<usize>,
+
+    /// Server ticks per second
+    #[arg(long, global = true)]
+    tick_rate: Option<f64>,
+
+    /// Seconds between world saves
+    #[arg(long, global = true)]
+    save_interval: Option<u64>,
+
+    /// Seconds between world backups
+    #[arg(long, global = true)]
+    backup_interval: Option<u64>,
+
+    /// Spawn point as 'x,y,z'
+    #[arg(long, global = true, value_parser = parse_ivec3, allow_hyphen_values = true)]
+    spawn_point: Option<IVec3>,
+
+    /// Player position updates sent per second
+    #[arg(long, global = true)]
+    player_sync_rate: Option<f32>,
+
+    /// Distance within which players receive each other's positions
+    #[arg(long, global = true)]
+    player_view_radius: Option<f32>,
+
+    /// Require connect tokens signed with the private key
+    #[arg(long, global = true)]
+    secure: Option<bool>,
+
+    /// Path to the hex encoded private key, generated if missing
+    #[arg(long, global = true)]
+    private_key_file: Option<PathBuf>,
+
+    /// Only let registered accounts join
+    #[arg(long, global = true)]
+    require_accounts: Option<bool>,
+
+    /// Message shown by status queries
+    #[arg(long, global = true)]
+    motd: Option<String>,
+
+    /// UDP port answering status queries, 0 to disable
+    #[arg(long, global = true)]
+    status_port: Option<u16>,
+
+    /// Announce the server on the LAN
+    #[arg(long, global = true)]
+    lan_discovery: Option<bool>,
+
+    /// Broadcast or multicast address LAN announcements are sent to, clients listen on a
+    /// multicast group with `discover --group`
+    #[arg(long, global = true)]
+    lan_discovery_address: Option<SocketAddr>,
+
+    /// Serve Prometheus metrics over HTTP
+    #[arg(long, global = true)]
+    metrics: Option<bool>,
+
+    /// Address of the metrics endpoint
+    #[arg(long, global = true)]
+    metrics_address: Option<SocketAddr>,
+
+    /// Simulate latency, jitter, packet loss and bandwidth caps for all clients
+    #[arg(long, global = true)]
+    link_conditioner: Option<bool>,
+
+    /// Simulated one way latency in milliseconds
+    #[arg(long, global = true)]
+    simulated_latency: Option<u64>,
+
+    /// Simulated jitter in milliseconds
+    #[arg(long, global = true)]
+    simulated_jitter: Option<u64>,
+
+    /// Share of packets to drop, between 0 and 1
+    #[arg(long, global = true)]
+    simulated_packet_loss: Option<f64>,
+
+    /// Simulated bandwidth per client and direction in kbit/s, 0 for no limit
+    #[arg(long, global = true)]
+    simulated_bandwidth: Option<u64>,
+
+    /// Compress chunk batches sent to clients
+    #[arg(long, global = true)]
+    chunk_compression: Option<bool>,
+
+    /// Chunk bytes sent to every client per second, 0 for no limit
+    #[arg(long, global = true)]
+    chunk_bytes_per_second: Option<u64>,
+}
+
+impl ServerSettingsArgs {
+    fn apply(&self, settings: &mut ServerSettings) {
+        if let Some(address) = self.address {
+            settings.address = address;
+        }
+        if let Some(public_address) = self.public_address {
+            settings.public_address = Some(public_address);
+        }
+        if let Some(max_clients) = self.max_clients {
+            settings.max_clients = max_clients;
+        }
+        if let Some(tick_rate) = self.tick_rate {
+            settings.tick_rate = tick_rate;
+        }
+        if let Some(save_interval) = self.save_interval {
+            settings.save_interval_seconds = save_interval;
+        }
+        if let Some(backup_interval) = self.backup_interval {
+            settings.backup_interval_seconds = backup_interval;
+        }
+        if let Some(spawn_point) = self.spawn_point {
+            settings.spawn_point = spawn_point;
+        }
+        if let Some(player_sync_rate) = self.player_sync_rate {
+            settings.player_sync_rate = player_sync_rate;
+        }
+        if let Some(player_view_radius) = self.player_view_radius {
+            settings.player_view_radius = player_view_radius;
+        }
+        if let Some(secure) = self.secure {
+            settings.secure = secure;
+        }
+        if let Some(private_key_file) = &self.private_key_file {
+            settings.private_key_file = private_key_file.clone();
+        }
+        if let Some(require_accounts) = self.require_accounts {
+            settings.require_accounts = require_accounts;
+        }
+        if let Some(motd) = &self.motd {
+            settings.motd = motd.clone();
+        }
+        if let Some(status_port) = self.status_port {
+            settings.status_port = status_port;
+        }
+        if let Some(lan_discovery) = self.lan_discovery {
+            settings.lan_discovery = lan_discovery;
+        }
+        if let Some(lan_discovery_address) = self.lan_discovery_address {
+            settings.lan_discovery_address = lan_discovery_address;
+        }
+        if let Some(metrics) = self.metrics {
+            settings.metrics = metrics;
+        }
+        if let Some(metrics_address) = self.metrics_address {
+            settings.metrics_address = metrics_address;
+        }
+        if let Some(link_conditioner) = self.link_conditioner {
+            settings.link_conditioner.enabled = link_conditioner;
+        }
+        if let Some(latency_ms) = self.simulated_latency {
+            settings.link_conditioner.latency_ms = latency_ms;
+        }
+        if let Some(jitter_ms) = self.simulated_jitter {
+            settings.link_conditioner.jitter_ms = jitter_ms;
+        }
+        if let Some(packet_loss) = self.simulated_packet_loss {
+            settings.link_conditioner.packet_loss = packet_loss;
+        }
+        if let Some(bandwidth_kbps) = self.simulated_bandwidth {
+            settings.link_conditioner.bandwidth_kbps = bandwidth_kbps;
+        }
+        if let Some(chunk_compression) = self.chunk_compression {
+            settings.chunk_compression = chunk_compression;
+        }
+        if let Some(chunk_bytes_per_second) = self.chunk_bytes_per_second {
+            settings.chunk_bytes_per_second = chunk_bytes_per_second;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        settings: ServerSettingsArgs,
+    }
+
+    #[test]
+    fn test_default_settings_are_valid() {
+        assert!(ServerSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_cli_overrides_settings() {
+        let cli =
+            TestCli::parse_from(["server", "--max-clients", "8", "--spawn-point", "-10,60,5"]);
+        let mut settings = ServerSettings::default();
+        cli.settings.apply(&mut settings);
+
+        assert_eq!(settings.max_clients, 8);
+        assert_eq!(settings.spawn_point, IVec3::new(-10, 60, 5));
+        assert_eq!(settings.tick_rate, crate::tick::DEFAULT_TICK_RATE);
+    }
+
+    #[test]
+    fn test_public_address_defaults_to_address() {
+        let mut settings = ServerSettings {
+            address: (Ipv4Addr::UNSPECIFIED, 5000).into(),
+            ..Default::default()
+        };
+        assert_eq!(settings.public_address(), settings.address);
+
+        let cli = TestCli::parse_from(["server", "--public-address", "203.0.113.7:5000"]);
+        cli.settings.apply(&mut settings);
+        assert!(settings.validate().is_ok());
+        assert_eq!(
+            settings.public_address(),
+            "203.0.113.7:5000".parse::<SocketAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        let invalid = [
+            ServerSettings {
+                max_clients: MAX_PLAYERS + 1,
+                ..Default::default()
+            },
+            ServerSettings {
+                tick_rate: 0.0,
+                ..Default::default()
+            },
+            ServerSettings {
+                save_interval_seconds: 0,
+                ..Default::default()
+            },
+            ServerSettings {
+                player_view_radius: f32::NAN,
+                ..Default::default()
+            },
+            ServerSettings {
+                require_accounts: true,
+                ..Default::default()
+            },
+            ServerSettings {
+                status_port: 5000,
+                ..Default::default()
+            },
+            ServerSettings {
+                public_address: Some((Ipv4Addr::UNSPECIFIED, 5000).into()),
+                ..Default::default()
+            },
+            ServerSettings {
+                link_conditioner: LinkConditionerSettings {
+                    packet_loss: 2.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+
+        for settings in invalid {
+            assert!(settings.validate().is_err(), "{settings:?}");
+        }
+    }
+}
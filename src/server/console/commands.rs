@@ -0,0 +1,178 @@
+use serde::Serialize;
+
+use crate::prelude::*;
+
+use access_commands::AccessCommand;
+use accounts_commands::AccountCommand;
+
+pub const CONSOLE_HELP: &str = "\
+Available commands:
+  save                    Save the world
+  backup                  Create a world backup
+  say <message>           Broadcast a chat message
+  kick <user> [reason]    Disconnect a player
+  list                    List connected players
+  tp <user> <x> <y> <z>   Teleport a player
+  tps                     Show server tick timings
+  op <user>               Grant operator permissions
+  deop <user>             Revoke operator permissions
+  ban <user> [reason]     Ban a player
+  pardon <user>           Unban a player
+  ban-ip <ip> [reason]    Ban an IP address
+  pardon-ip <ip>          Unban an IP address
+  whitelist <on|off|list|add <user>|remove <user>>
+                          Manage the whitelist
+  register <user> <password>
+                          Create an account for a username
+  passwd <user> <password>
+                          Reset the password of an account
+  unregister <user>       Delete an account
+  stop                    Save the world and shut down
+  help                    Show this message";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    Save,
+    Backup,
+    Say(String),
+    Kick {
+        username: Username,
+        reason: Option<String>,
+    },
+    List,
+    Teleport {
+        username: Username,
+        position: Vec3,
+    },
+    Tps,
+    Access(AccessCommand),
+    /// Never recorded, it carries passwords.
+    #[serde(skip)]
+    Account(AccountCommand),
+    Stop,
+    Help,
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, rest) = line
+            .split_once(char::is_whitespace)
+            .map(|(name, rest)| (name, rest.trim()))
+            .unwrap_or((line, ""));
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        match name {
+            "save" => Ok(Self::Save),
+            "backup" => Ok(Self::Backup),
+            "say" if !rest.is_empty() => Ok(Self::Say(String::from(rest))),
+            "say" => Err(String::from("Usage: say <message>")),
+            "kick" => {
+                let mut parts = rest.splitn(2, char::is_whitespace);
+                let username = parts
+                    .next()
+                    .filter(|username| !username.is_empty())
+                    .ok_or_else(|| String::from("Usage: kick <user> [reason]"))?;
+                let reason = parts
+                    .next()
+                    .map(str::trim)
+                    .filter(|reason| !reason.is_empty())
+                    .map(String::from);
+
+                Ok(Self::Kick {
+                    username: Username::new(username)?,
+                    reason,
+                })
+            }
+            "list" => Ok(Self::List),
+            "tp" => {
+                let [username, x, y, z] = args[..] else {
+                    return Err(String::from("Usage: tp <user> <x> <y> <z>"));
+                };
+                let coordinate = |value: &str| {
+                    value
+                        .parse::<f32>()
+                        .map_err(|_| format!("Invalid coordinate '{value}'"))
+                };
+
+                Ok(Self::Teleport {
+                    username: Username::new(username)?,
+                    position: Vec3::new(coordinate(x)?, coordinate(y)?, coordinate(z)?),
+                })
+            }
+            "tps" => Ok(Self::Tps),
+            "stop" => Ok(Self::Stop),
+            "help" => Ok(Self::Help),
+            _ => match AccessCommand::parse(name, &args) {
+                Some(command) => command.map(Self::Access),
+                None => match AccountCommand::parse(name, &args) {
+                    Some(command) => command.map(Self::Account),
+                    None => Err(format!(
+                        "Unknown command '{name}'. Type 'help' for a list of commands."
+                    )),
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_commands() {
+        assert_eq!(ConsoleCommand::parse("save"), Ok(ConsoleCommand::Save));
+        assert_eq!(ConsoleCommand::parse(" list "), Ok(ConsoleCommand::List));
+        assert_eq!(
+            ConsoleCommand::parse("say Hello  World"),
+            Ok(ConsoleCommand::Say(String::from("Hello  World")))
+        );
+        assert!(ConsoleCommand::parse("say").is_err());
+        assert!(ConsoleCommand::parse("dance").is_err());
+        assert_eq!(
+            ConsoleCommand::parse("op Steve"),
+            Ok(ConsoleCommand::Access(AccessCommand::Op(Username::from(
+                "Steve"
+            ))))
+        );
+        assert_eq!(
+            ConsoleCommand::parse("unregister Steve"),
+            Ok(ConsoleCommand::Account(AccountCommand::Unregister(
+                Username::from("Steve")
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parse_kick() {
+        assert_eq!(
+            ConsoleCommand::parse("kick Steve"),
+            Ok(ConsoleCommand::Kick {
+                username: Username::from("Steve"),
+                reason: None,
+            })
+        );
+        assert_eq!(
+            ConsoleCommand::parse("kick Steve griefing spawn"),
+            Ok(ConsoleCommand::Kick {
+                username: Username::from("Steve"),
+                reason: Some(String::from("griefing spawn")),
+            })
+        );
+        assert!(ConsoleCommand::parse("kick").is_err());
+    }
+
+    #[test]
+    fn test_parse_teleport() {
+        assert_eq!(
+            ConsoleCommand::parse("tp Steve 1 2.5 -3"),
+            Ok(ConsoleCommand::Teleport {
+                username: Username::from("Steve"),
+                position: Vec3::new(1.0, 2.5, -3.0),
+            })
+        );
+        assert!(ConsoleCommand::parse("tp Steve 1 2").is_err());
+        assert!(ConsoleCommand::parse("tp Steve a b c").is_err());
+    }
+}
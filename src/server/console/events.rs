@@ -0,0 +1,4 @@
+use crate::prelude::*;
+
+#[derive(Message)]
+pub struct ConsoleCommandEvent(pub console_commands::ConsoleCommand);
@@ -0,0 +1,24 @@
+use crate::prelude::*;
+
+pub mod commands;
+pub mod events;
+pub mod resources;
+pub mod systems;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building ConsolePlugin");
+        app.insert_resource(resources::ConsoleInput::from_stdin());
+        app.add_message::<console_events::ConsoleCommandEvent>();
+        app.add_systems(
+            Update,
+            (
+                console_systems::read_console_input_system,
+                console_systems::handle_console_commands_system,
+            )
+                .chain(),
+        );
+    }
+}
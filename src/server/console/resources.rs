@@ -0,0 +1,47 @@
+use std::{
+    io::{self, BufRead},
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+};
+
+use crate::prelude::*;
+
+#[derive(Resource)]
+pub struct ConsoleInput {
+    receiver: Mutex<Receiver<String>>,
+}
+
+impl ConsoleInput {
+    pub fn from_stdin() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::Builder::new()
+            .name(String::from("console"))
+            .spawn(move || {
+                for line in io::stdin().lock().lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("Console thread should be spawnable");
+
+        Self {
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    pub fn drain_lines(&self) -> Vec<String> {
+        self.receiver
+            .lock()
+            .expect("Console receiver should not be poisoned")
+            .try_iter()
+            .collect()
+    }
+}
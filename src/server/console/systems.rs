@@ -0,0 +1,133 @@
+use bevy::app::AppExit;
+
+use crate::{networking::resources::PendingDisconnects, prelude::*};
+use console_commands::{ConsoleCommand, CONSOLE_HELP};
+
+pub fn read_console_input_system(
+    console_input: Res<console_resources::ConsoleInput>,
+    mut command_events: MessageWriter<console_events::ConsoleCommandEvent>,
+) {
+    for line in console_input.drain_lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match ConsoleCommand::parse(&line) {
+            Ok(command) => {
+                command_events.write(console_events::ConsoleCommandEvent(command));
+            }
+            Err(error) => println!("{error}"),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_console_commands_system(
+    mut command_events: MessageReader<console_events::ConsoleCommandEvent>,
+    mut server: networking_systems::GameServer,
+    mut player_states: ResMut<player_resources::PlayerStates>,
+    mut pending_disconnects: ResMut<PendingDisconnects>,
+    client_usernames: Res<ClientUsernames>,
+    chunk_manager: Res<ChunkManager>,
+    generator: Res<terrain_resources::Generator>,
+    world_name: Res<terrain_resources::AutoSaveName>,
+    mut save_timer: ResMut<terrain_resources::WorldSaveTimer>,
+    mut backup_timer: ResMut<terrain_resources::WorldBackupTimer>,
+    mut exit_events: MessageWriter<AppExit>,
+    #[cfg(feature = "chat")] mut chat_message_events: MessageWriter<
+        chat_events::PlayerChatMessageSendEvent,
+    >,
+) {
+    for event in command_events.read() {
+        match &event.0 {
+            ConsoleCommand::Save => {
+                match terrain_persistence::save_world(&world_name.0, &chunk_manager, &generator) {
+                    Ok(_) => save_timer.reset(),
+                    Err(err) => eprintln!("Error saving world: {}", err),
+                }
+            }
+            ConsoleCommand::Backup => {
+                match terrain_persistence::backup_world(&world_name.0, &chunk_manager, &generator) {
+                    Ok(_) => backup_timer.reset(),
+                    Err(err) => eprintln!("Error backing up world: {}", err),
+                }
+            }
+            ConsoleCommand::Say(message) => {
+                println!("[{SERVER_USERNAME}] {message}");
+
+                #[cfg(feature = "chat")]
+                chat_message_events.write(chat_events::PlayerChatMessageSendEvent {
+                    sender: ChatMessageSender::Server,
+                    message: message.clone(),
+                });
+            }
+            ConsoleCommand::Kick { username, reason } => {
+                let Some(client_id) = server
+                    .connections()
+                    .accepted_client_id(&client_usernames, username)
+                else {
+                    println!("Player '{username}' is not online");
+                    continue;
+                };
+
+                let reason = reason
+                    .clone()
+                    .unwrap_or_else(|| String::from("Kicked by an operator"));
+
+                networking_systems::kick_client(
+                    &mut server,
+                    &mut pending_disconnects,
+                    client_id,
+                    DisconnectReason::Kicked(reason.clone()),
+                );
+                println!("Kicked {username}: {reason}");
+
+                #[cfg(feature = "chat")]
+                chat_message_events.write(chat_events::PlayerChatMessageSendEvent {
+                    sender: ChatMessageSender::Server,
+                    message: format!("{username} was kicked: {reason}"),
+                });
+            }
+            ConsoleCommand::List => {
+                let usernames: Vec<String> = server
+                    .connections()
+                    .accepted_usernames(&client_usernames)
+                    .iter()
+                    .map(Username::to_string)
+                    .collect();
+
+                println!(
+                    "{} player(s) online: {}",
+                    usernames.len(),
+                    usernames.join(", ")
+                );
+            }
+            ConsoleCommand::Teleport { username, position } => {
+                let Some(client_id) = server
+                    .connections()
+                    .accepted_client_id(&client_usernames, username)
+                else {
+                    println!("Player '{username}' is not online");
+                    continue;
+                };
+
+                if let Some(player_state) = player_states.players.get_mut(username) {
+                    player_state.position = *position;
+                }
+
+                server.send(
+                    client_id,
+                    DefaultChannel::ReliableOrdered,
+                    &NetworkingMessage::PlayerTeleport(*position),
+                );
+                println!("Teleported {username} to {position}");
+            }
+            ConsoleCommand::Tps | ConsoleCommand::Access(_) | ConsoleCommand::Account(_) => {}
+            ConsoleCommand::Stop => {
+                println!("Stopping server...");
+                exit_events.write(AppExit::Success);
+            }
+            ConsoleCommand::Help => println!("{CONSOLE_HELP}"),
+        }
+    }
+}
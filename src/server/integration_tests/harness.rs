@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use bevy::time::TimeUpdateStrategy;
+use bevy_renet::netcode::{ClientAuthentication, NetcodeClientTransport};
+
+use crate::{
+    access::AccessPlugin,
+    accounts::AccountsPlugin,
+    networking::NetworkingPlugin,
+    player::PlayerPlugin,
+    prelude::*,
+    recording::RecordingPlugin,
+    terrain::persistence::WorldSave,
+    terrain::TerrainPlugin,
+    tick::{TickPlugin, DEFAULT_TICK_RATE},
+};
+use recording_resources::{hash_world, SessionEvent, SessionRecorder, WorldChecksum};
+
+/// Simulated time that passes with every step, one tick at the default tick rate.
+pub const STEP: Duration = Duration::from_nanos(1_000_000_000 / DEFAULT_TICK_RATE as u64);
+/// Upper bound for `Harness::run_until`, 20 simulated seconds.
+pub const MAX_STEPS: usize = 600;
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+pub const RECORDING_FILE: &str = "session.rsmcr";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientHandle(usize);
+
+/// A client that speaks the game protocol without any of the client's plugins and
+/// keeps every message it receives. The client binary cannot be linked into the
+/// server's tests, its plugins are driven headless by the client's own integration
+/// tests instead.
+pub struct TestClient {
+    pub username: Username,
+    client: RenetClient,
+    transport: NetcodeClientTransport,
+    pub received: Vec<NetworkingMessage>,
+}
+
+impl TestClient {
+    fn connect(server_addr: SocketAddr, username: Username) -> Self {
+        let authentication = ClientAuthentication::Unsecure {
+            server_addr,
+            client_id: rand::random::<u64>(),
+            user_data: Some(username.to_netcode_user_data()),
+            protocol_id: PROTOCOL_ID,
+        };
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let current_time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        Self {
+            username,
+            client: RenetClient::new(connection_config()),
+            transport: NetcodeClientTransport::new(current_time, authentication, socket).unwrap(),
+            received: Vec::new(),
+        }
+    }
+
+    fn update(&mut self) {
+        self.client.update(STEP);
+        // Errors only mean that the connection is gone, which tests check with
+        // `is_disconnected`. Messages that arrived before are still drained.
+        let _ = self.transport.update(STEP, &mut self.client);
+
+        for channel in [
+            DefaultChannel::ReliableOrdered,
+            DefaultChannel::ReliableUnordered,
+            DefaultChannel::Unreliable,
+        ] {
+            while let Some(bytes) = self.client.receive_message(channel) {
+                let message = deserialize_message(&bytes).expect("Server messages should be valid");
+                // Kept decompressed, so that scenarios do not depend on compression.
+                self.received.push(match message {
+                    NetworkingMessage::CompressedChunkBatchResponse(bytes) => {
+                        NetworkingMessage::ChunkBatchResponse(
+                            decompress_chunks(&bytes).expect("Compressed chunks should be valid"),
+                        )
+                    }
+                    message => message,
+                });
+            }
+        }
+
+        let _ = self.transport.send_packets(&mut self.client);
+    }
+
+    /// Sends `message` on the channel the client uses for its kind.
+    pub fn send(&mut self, message: NetworkingMessage) {
+        let channel = match message {
+            NetworkingMessage::PlayerUpdate { .. } => DefaultChannel::Unreliable,
+            NetworkingMessage::ChunkBatchRequest(_)
+            | NetworkingMessage::CachedChunkBatchRequest(_)
+            | NetworkingMessage::ChunkRequestCancel(_) => DefaultChannel::ReliableUnordered,
+            _ => DefaultChannel::ReliableOrdered,
+        };
+        self.client
+            .send_message(channel, bincode::serialize(&message).unwrap());
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.client.is_disconnected()
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.received
+            .iter()
+            .any(|message| matches!(message, NetworkingMessage::PlayerAccept(_)))
+    }
+
+    pub fn kick_reason(&self) -> Option<&DisconnectReason> {
+        self.received.iter().find_map(|message| match message {
+            NetworkingMessage::Kick { reason } => Some(reason),
+            _ => None,
+        })
+    }
+}
+
+/// Runs the server plugins and any number of `TestClient`s in one process, talking
+/// over loopback. Time advances by `STEP` with every step instead of wall clock time.
+pub struct Harness {
+    pub server: App,
+    address: SocketAddr,
+    directory: PathBuf,
+    clients: Vec<TestClient>,
+}
+
+impl Harness {
+    /// Starts a server whose access lists and accounts live in a fresh temporary
+    /// directory named after the test and unique to this run.
+    pub fn start(name: &str) -> Self {
+        Self::start_with(name, |_| {})
+    }
+
+    /// Like `start`, but lets the test write files like `banned-users.txt` into the
+    /// server directory before the plugins read them.
+    pub fn start_with(name: &str, prepare: impl FnOnce(&Path)) -> Self {
+        Self::build(name, prepare, false)
+    }
+
+    /// Like `start`, but records the session to `RECORDING_FILE` in the server directory.
+    pub fn start_recorded(name: &str) -> Self {
+        Self::build(name, |_| {}, true)
+    }
+
+    fn build(name: &str, prepare: impl FnOnce(&Path), record: bool) -> Self {
+        let directory = unique_temp_dir(name);
+        fs::create_dir_all(&directory).unwrap();
+        prepare(&directory);
+
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = socket.local_addr().unwrap();
+        let mut server = App::new();
+        server.add_plugins(MinimalPlugins);
+        server.insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+        server.add_message::<console_events::ConsoleCommandEvent>();
+
+        server.add_plugins(TickPlugin::new(DEFAULT_TICK_RATE).unwrap());
+        server.add_plugins(
+            TerrainPlugin::from_world_save(WorldSave {
+                name: String::from(name),
+                generator: terrain_resources::Generator::with_seed(0),
+                chunks: Vec::new(),
+            })
+            .with_autosave_intervals(AUTOSAVE_INTERVAL, AUTOSAVE_INTERVAL),
+        );
+        server.add_plugins(PlayerPlugin::new(
+            DEFAULT_SPAWN_POINT,
+            player_resources::PlayerSyncConfig::default(),
+        ));
+        server.add_plugins(NetworkingPlugin::new(address, MAX_PLAYERS, None).with_socket(socket));
+        #[cfg(feature = "chat")]
+        server.add_plugins(crate::chat::ChatPlugin);
+        server.add_plugins(AccessPlugin::load_from(&directory).unwrap());
+        server.add_plugins(AccountsPlugin::load_from(&directory, false).unwrap());
+        if record {
+            server.add_plugins(RecordingPlugin::create(&directory.join(RECORDING_FILE)).unwrap());
+        }
+        // Tests write console commands as messages, stdin is not read.
+        server.add_systems(Update, console_systems::handle_console_commands_system);
+        server.finish();
+        server.cleanup();
+
+        Self {
+            server,
+            address,
+            directory,
+            clients: Vec::new(),
+        }
+    }
+
+    /// A path inside the server directory, which is removed with the harness.
+    pub fn temp_path(&self, name: &str) -> PathBuf {
+        self.directory.join(name)
+    }
+
+    pub fn connect(&mut self, username: &str) -> ClientHandle {
+        self.clients
+            .push(TestClient::connect(self.address, Username::from(username)));
+        ClientHandle(self.clients.len() - 1)
+    }
+
+    /// Connects a client and waits until the server accepted it.
+    pub fn join(&mut self, username: &str) -> ClientHandle {
+        let handle = self.connect(username);
+        assert!(
+            self.run_until(|harness| harness.client(handle).is_accepted()),
+            "{username} should be accepted"
+        );
+        handle
+    }
+
+    pub fn client(&self, handle: ClientHandle) -> &TestClient {
+        &self.clients[handle.0]
+    }
+
+    pub fn client_mut(&mut self, handle: ClientHandle) -> &mut TestClient {
+        &mut self.clients[handle.0]
+    }
+
+    pub fn step(&mut self) {
+        self.server.update();
+        for client in &mut self.clients {
+            client.update();
+        }
+    }
+
+    /// Ends the recording the way a shutdown does, without saving the world.
+    pub fn finish_recording(&mut self) {
+        let world = self.server.world_mut();
+        let checksum = WorldChecksum::compute(
+            world.resource::<ChunkManager>(),
+            world.resource::<terrain_resources::PastBlockUpdates>(),
+            world.resource::<player_resources::PlayerStates>(),
+        );
+        let world_hash = hash_world(world.resource::<ChunkManager>());
+
+        let mut recorder = world.resource_mut::<SessionRecorder>();
+        recorder.record(SessionEvent::Finished {
+            checksum,
+            world_hash,
+        });
+        recorder.flush();
+    }
+
+    /// Steps until `condition` holds, returns false if it did not within `MAX_STEPS`.
+    pub fn run_until(&mut self, mut condition: impl FnMut(&Harness) -> bool) -> bool {
+        for _ in 0..MAX_STEPS {
+            if condition(self) {
+                return true;
+            }
+            self.step();
+        }
+        condition(self)
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+/// A directory no other test running in parallel uses.
+fn unique_temp_dir(name: &str) -> PathBuf {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "rsmc_integration_{name}_{}_{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ))
+}
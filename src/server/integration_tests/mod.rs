@@ -0,0 +1,638 @@
+//! Scenarios that run the server plugins against clients in the same process.
+
+mod harness;
+
+use std::{collections::HashSet, fs};
+
+use crate::{networking::resources::MAX_CLIENT_MESSAGE_ERRORS, prelude::*, recording::replay};
+use harness::Harness;
+
+#[test]
+fn test_players_join_and_see_each_other() {
+    let mut harness = Harness::start("join");
+    let steve = harness.join("Steve");
+    let alex = harness.join("Alex");
+
+    let alex_username = harness.client(alex).username;
+    assert!(harness.run_until(|harness| {
+        harness.client(steve).received.iter().any(|message| {
+            matches!(message, NetworkingMessage::PlayerJoin(username) if *username == alex_username)
+        })
+    }));
+
+    let usernames = harness.server.world().resource::<ClientUsernames>();
+    assert!(usernames.get_client_id(&Username::from("Steve")).is_some());
+    assert!(usernames.get_client_id(&Username::from("Alex")).is_some());
+}
+
+#[test]
+fn test_banned_player_is_rejected() {
+    let mut harness = Harness::start_with("banned", |directory| {
+        fs::write(
+            directory.join(access_resources::BANNED_USERS_FILE),
+            "Griefer\tBroke spawn\n",
+        )
+        .unwrap();
+    });
+    let griefer = harness.connect("Griefer");
+
+    assert!(harness.run_until(|harness| harness.client(griefer).is_disconnected()));
+    assert_eq!(
+        harness.client(griefer).kick_reason(),
+        Some(&DisconnectReason::Banned(String::from("Broke spawn")))
+    );
+    assert!(!harness.client(griefer).is_accepted());
+}
+
+#[test]
+fn test_duplicate_username_is_rejected() {
+    let mut harness = Harness::start("duplicate");
+    let first = harness.join("Steve");
+    let second = harness.connect("Steve");
+
+    assert!(harness.run_until(|harness| harness.client(second).is_disconnected()));
+    assert_eq!(
+        harness.client(second).kick_reason(),
+        Some(&DisconnectReason::UsernameTaken)
+    );
+    assert!(!harness.client(first).is_disconnected());
+}
+
+#[test]
+fn test_broadcasts_skip_clients_logging_in() {
+    let mut harness = Harness::start("logging_in");
+    harness
+        .server
+        .world_mut()
+        .resource_mut::<accounts_resources::Accounts>()
+        .register(Username::from("Alex"), "correct horse")
+        .unwrap();
+    let steve = harness.join("Steve");
+    let alex = harness.connect("Alex");
+    assert!(harness.run_until(|harness| {
+        harness
+            .client(alex)
+            .received
+            .iter()
+            .any(|message| matches!(message, NetworkingMessage::LoginRequired))
+    }));
+
+    harness.join("Carl");
+    let carl_username = Username::from("Carl");
+    assert!(harness.run_until(|harness| {
+        harness.client(steve).received.iter().any(|message| {
+            matches!(message, NetworkingMessage::PlayerJoin(username) if *username == carl_username)
+        })
+    }));
+
+    assert!(!harness.client(alex).is_disconnected());
+    assert!(harness
+        .client(alex)
+        .received
+        .iter()
+        .all(|message| matches!(message, NetworkingMessage::LoginRequired)));
+}
+
+#[test]
+fn test_failed_logins_are_limited() {
+    let mut harness = Harness::start("logins");
+    let mut accounts = harness
+        .server
+        .world_mut()
+        .resource_mut::<accounts_resources::Accounts>();
+    accounts
+        .register(Username::from("Steve"), "correct horse")
+        .unwrap();
+    accounts
+        .register(Username::from("Alex"), "correct horse")
+        .unwrap();
+
+    let log_in = |harness: &mut Harness, username: &str, password: &str| {
+        let client = harness.connect(username);
+        assert!(harness.run_until(|harness| {
+            harness
+                .client(client)
+                .received
+                .iter()
+                .any(|message| matches!(message, NetworkingMessage::LoginRequired))
+        }));
+        harness.client_mut(client).send(NetworkingMessage::Login {
+            password: String::from(password),
+        });
+        assert!(harness.run_until(|harness| {
+            harness.client(client).is_accepted() || harness.client(client).is_disconnected()
+        }));
+        client
+    };
+
+    let steve = log_in(&mut harness, "Steve", "correct horse");
+    assert!(harness.client(steve).is_accepted());
+
+    for _ in 0..accounts_resources::MAX_FAILED_LOGINS {
+        let alex = log_in(&mut harness, "Alex", "wrong horse");
+        assert_eq!(
+            harness.client(alex).kick_reason(),
+            Some(&DisconnectReason::WrongPassword)
+        );
+    }
+
+    // Refused without checking, the right password included.
+    let alex = log_in(&mut harness, "Alex", "correct horse");
+    assert_eq!(
+        harness.client(alex).kick_reason(),
+        Some(&DisconnectReason::TooManyLoginAttempts)
+    );
+    assert!(!harness.client(alex).is_accepted());
+}
+
+#[test]
+fn test_requested_chunks_are_sent_in_batches() {
+    let mut harness = Harness::start("chunks");
+    let steve = harness.join("Steve");
+
+    let requested: HashSet<IVec3> = (0..MAX_CHUNK_BATCH_RESPONSE_SIZE as i32 + 2)
+        .map(|x| IVec3::new(x, 0, 0))
+        .collect();
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(
+            requested.iter().copied().collect(),
+        ));
+
+    let received_chunks = |harness: &Harness| -> Vec<IVec3> {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .filter_map(|message| match message {
+                NetworkingMessage::ChunkBatchResponse(chunks) => Some(chunks),
+                _ => None,
+            })
+            .flatten()
+            .map(|chunk| chunk.position)
+            .collect()
+    };
+    assert!(harness.run_until(|harness| received_chunks(harness).len() >= requested.len()));
+
+    let received = received_chunks(&harness);
+    assert_eq!(received.len(), requested.len());
+    assert_eq!(received.into_iter().collect::<HashSet<_>>(), requested);
+
+    let batches = harness
+        .client(steve)
+        .received
+        .iter()
+        .filter(|message| matches!(message, NetworkingMessage::ChunkBatchResponse(_)))
+        .count();
+    assert_eq!(batches, 2);
+}
+
+#[test]
+fn test_chunk_batches_are_compressed() {
+    let mut harness = Harness::start("compressed_chunks");
+    let steve = harness.join("Steve");
+
+    // A column through the surface, where chunks are not just runs of one block.
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(
+            (0..4).map(|y| IVec3::new(0, y, 0)).collect(),
+        ));
+    assert!(harness.run_until(|harness| {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .any(|message| matches!(message, NetworkingMessage::ChunkBatchResponse(_)))
+    }));
+
+    let stats = harness
+        .server
+        .world()
+        .resource::<terrain_resources::TerrainStats>();
+    assert!(stats.chunk_bytes_sent > 0);
+    assert!(stats.compression_ratio() < 1.0);
+}
+
+#[test]
+fn test_chunk_budget_limits_chunks_per_tick() {
+    let mut harness = Harness::start("chunk_budget");
+    harness
+        .server
+        .insert_resource(terrain_resources::ChunkStreamingConfig {
+            compression: false,
+            bytes_per_second: 1,
+        });
+    let steve = harness.join("Steve");
+
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(
+            (0..3).map(|x| IVec3::new(x, 0, 0)).collect(),
+        ));
+    for _ in 0..20 {
+        harness.step();
+    }
+
+    // The first chunk is always sent, it leaves the client in debt for hours.
+    let received: usize = harness
+        .client(steve)
+        .received
+        .iter()
+        .filter_map(|message| match message {
+            NetworkingMessage::ChunkBatchResponse(chunks) => Some(chunks.len()),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(received, 1);
+
+    let queued: Vec<usize> = harness
+        .server
+        .world()
+        .resource::<terrain_resources::ClientChunkRequests>()
+        .queue_lengths()
+        .map(|(_, len)| len)
+        .collect();
+    assert_eq!(queued, vec![2]);
+}
+
+#[test]
+fn test_chunk_budget_carries_over_to_later_requests() {
+    let mut harness = Harness::start("chunk_budget_debt");
+    harness
+        .server
+        .insert_resource(terrain_resources::ChunkStreamingConfig {
+            compression: false,
+            bytes_per_second: 1,
+        });
+    let steve = harness.join("Steve");
+    let received_chunks = |harness: &Harness| -> usize {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .filter_map(|message| match message {
+                NetworkingMessage::ChunkBatchResponse(chunks) => Some(chunks.len()),
+                _ => None,
+            })
+            .sum()
+    };
+
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(vec![IVec3::ZERO]));
+    assert!(harness.run_until(|harness| received_chunks(harness) == 1));
+
+    // The queue is empty now, the debt from the first chunk still holds back the next.
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(vec![IVec3::X]));
+    for _ in 0..20 {
+        harness.step();
+    }
+    assert_eq!(received_chunks(&harness), 1);
+
+    let queued: Vec<usize> = harness
+        .server
+        .world()
+        .resource::<terrain_resources::ClientChunkRequests>()
+        .queue_lengths()
+        .map(|(_, len)| len)
+        .collect();
+    assert_eq!(queued, vec![1]);
+}
+
+#[test]
+fn test_cancelled_chunk_requests_leave_the_queue() {
+    let mut harness = Harness::start("chunk_cancel");
+    harness
+        .server
+        .insert_resource(terrain_resources::ChunkStreamingConfig {
+            compression: false,
+            bytes_per_second: 1,
+        });
+    let steve = harness.join("Steve");
+
+    let positions: Vec<IVec3> = (0..3).map(|x| IVec3::new(x, 0, 0)).collect();
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(positions.clone()));
+    let queued = |harness: &Harness| -> usize {
+        harness
+            .server
+            .world()
+            .resource::<terrain_resources::ClientChunkRequests>()
+            .queue_lengths()
+            .map(|(_, len)| len)
+            .sum()
+    };
+    assert!(harness.run_until(|harness| queued(harness) == 2));
+
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkRequestCancel(positions));
+    assert!(harness.run_until(|harness| queued(harness) == 0));
+}
+
+#[test]
+fn test_cached_chunks_are_answered_as_unchanged() {
+    let mut harness = Harness::start("cached_chunks");
+    let steve = harness.join("Steve");
+    assert!(harness
+        .client(steve)
+        .received
+        .iter()
+        .any(|message| matches!(message, NetworkingMessage::WorldIdentity(_))));
+
+    let positions = [IVec3::new(0, 0, 0), IVec3::new(1, 0, 0)];
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(positions.to_vec()));
+
+    let received_chunks = |harness: &Harness| -> Vec<Chunk> {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .filter_map(|message| match message {
+                NetworkingMessage::ChunkBatchResponse(chunks) => Some(chunks.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    };
+    assert!(harness.run_until(|harness| received_chunks(harness).len() >= positions.len()));
+    let chunks = received_chunks(&harness);
+
+    // The first chunk is cached as received, the second one went stale.
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::CachedChunkBatchRequest(vec![
+            (chunks[0].position, chunks[0].content_hash()),
+            (chunks[1].position, chunks[1].content_hash().wrapping_add(1)),
+        ]));
+
+    let unchanged = |harness: &Harness| -> Vec<IVec3> {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .filter_map(|message| match message {
+                NetworkingMessage::ChunkBatchUnchanged(positions) => Some(positions.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    };
+    assert!(harness.run_until(|harness| {
+        !unchanged(harness).is_empty() && received_chunks(harness).len() > positions.len()
+    }));
+
+    assert_eq!(unchanged(&harness), vec![chunks[0].position]);
+    assert_eq!(
+        received_chunks(&harness)[positions.len()..]
+            .iter()
+            .map(|chunk| chunk.position)
+            .collect::<Vec<_>>(),
+        vec![chunks[1].position]
+    );
+}
+
+#[test]
+fn test_block_updates_reach_other_and_late_players() {
+    let mut harness = Harness::start("blocks");
+    let steve = harness.join("Steve");
+    let alex = harness.join("Alex");
+
+    let position = IVec3::new(3, 40, -7);
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::BlockUpdate {
+            position,
+            block: BlockId::Stone,
+        });
+
+    let has_update = |harness: &Harness, client| {
+        harness
+            .client(client)
+            .received
+            .iter()
+            .any(|message| match message {
+                NetworkingMessage::BlockUpdate {
+                    position: update_position,
+                    block,
+                } => *update_position == position && *block == BlockId::Stone,
+                _ => false,
+            })
+    };
+    assert!(harness.run_until(|harness| has_update(harness, alex)));
+    assert!(!has_update(&harness, steve));
+
+    let notch = harness.join("Notch");
+    assert!(harness.run_until(|harness| has_update(harness, notch)));
+}
+
+#[test]
+fn test_block_updates_out_of_reach_are_rejected() {
+    let mut harness = Harness::start("blocks_out_of_reach");
+    let steve = harness.join("Steve");
+    let alex = harness.join("Alex");
+
+    // More than enough to get kicked if they counted as invalid messages.
+    let position = IVec3::new(0, 40, 100_000);
+    for _ in 0..=MAX_CLIENT_MESSAGE_ERRORS {
+        harness
+            .client_mut(steve)
+            .send(NetworkingMessage::BlockUpdate {
+                position,
+                block: BlockId::Stone,
+            });
+    }
+    for _ in 0..10 {
+        harness.step();
+    }
+    assert!(!harness.client(steve).is_disconnected());
+
+    assert!(!harness
+        .client(alex)
+        .received
+        .iter()
+        .any(|message| matches!(message, NetworkingMessage::BlockUpdate { .. })));
+    let chunk_position = ChunkManager::world_position_to_chunk_position(position);
+    assert_eq!(
+        harness
+            .server
+            .world()
+            .resource::<ChunkManager>()
+            .pending_update_count(&chunk_position),
+        0
+    );
+}
+
+#[test]
+fn test_block_updates_out_of_reach_are_corrected() {
+    let mut harness = Harness::start("blocks_corrected");
+    let steve = harness.join("Steve");
+
+    let position = DEFAULT_SPAWN_POINT + IVec3::new(4 * MAX_BLOCK_UPDATE_DISTANCE as i32, 0, 0);
+    let chunk_position = ChunkManager::world_position_to_chunk_position(position);
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(vec![chunk_position]));
+    assert!(harness.run_until(|harness| {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .any(|message| matches!(message, NetworkingMessage::ChunkBatchResponse(_)))
+    }));
+
+    let block = harness
+        .server
+        .world()
+        .resource::<ChunkManager>()
+        .get_block(position)
+        .unwrap();
+    let placed = match block {
+        BlockId::Stone => BlockId::Air,
+        _ => BlockId::Stone,
+    };
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::BlockUpdate {
+            position,
+            block: placed,
+        });
+
+    assert!(harness.run_until(|harness| {
+        harness.client(steve).received.iter().any(|message| {
+            matches!(message, NetworkingMessage::BlockUpdate { position: updated, block: corrected } if *updated == position && *corrected == block)
+        })
+    }));
+    let server_block = harness
+        .server
+        .world()
+        .resource::<ChunkManager>()
+        .get_block(position);
+    assert_eq!(server_block, Some(block));
+}
+
+#[cfg(feature = "chat")]
+#[test]
+fn test_chat_messages_are_synced() {
+    let mut harness = Harness::start("chat");
+    let steve = harness.join("Steve");
+    let alex = harness.join("Alex");
+
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChatMessageSend(String::from("hello")));
+
+    let is_hello = |message: &ChatMessage| {
+        message.message == "hello"
+            && matches!(message.sender, ChatMessageSender::Player(username) if username.as_str() == "Steve")
+    };
+    assert!(harness.run_until(|harness| {
+        harness.client(alex).received.iter().any(|message| {
+            matches!(message, NetworkingMessage::SingleChatMessageSync(message) if is_hello(message))
+        })
+    }));
+
+    // Players joining later get the history instead.
+    let notch = harness.join("Notch");
+    assert!(harness.run_until(|harness| {
+        harness.client(notch).received.iter().any(|message| {
+            matches!(message, NetworkingMessage::ChatMessageSync(messages) if messages.iter().any(is_hello))
+        })
+    }));
+}
+
+#[cfg(feature = "chat")]
+#[test]
+fn test_command_replies_do_not_reuse_message_ids() {
+    let mut harness = Harness::start("chat_ids");
+    let steve = harness.join("Steve");
+
+    for message in ["/dance", "hello", "/dance", "again"] {
+        harness
+            .client_mut(steve)
+            .send(NetworkingMessage::ChatMessageSend(String::from(message)));
+    }
+
+    let message_ids = |harness: &Harness| -> Vec<usize> {
+        harness
+            .client(steve)
+            .received
+            .iter()
+            .filter_map(|message| match message {
+                NetworkingMessage::SingleChatMessageSync(message) => Some(message.message_id),
+                _ => None,
+            })
+            .collect()
+    };
+    assert!(harness.run_until(|harness| message_ids(harness).len() >= 4));
+
+    let message_ids = message_ids(&harness);
+    let unique: HashSet<usize> = message_ids.iter().copied().collect();
+    assert_eq!(unique.len(), message_ids.len(), "{message_ids:?}");
+}
+
+#[test]
+fn test_recorded_session_replays_without_divergence() {
+    let mut harness = Harness::start_recorded("replay");
+    let steve = harness.join("Steve");
+    let alex = harness.join("Alex");
+
+    // A column through the surface, where chunks are not just runs of one block.
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::ChunkBatchRequest(
+            (0..4).map(|y| IVec3::new(0, y, 0)).collect(),
+        ));
+    harness
+        .client_mut(steve)
+        .send(NetworkingMessage::BlockUpdate {
+            position: IVec3::new(3, 40, -7),
+            block: BlockId::Stone,
+        });
+    harness
+        .client_mut(alex)
+        .send(NetworkingMessage::PlayerUpdate {
+            sequence: 1,
+            state: PlayerState {
+                position: Vec3::new(4.0, 44.0, 2.0),
+                rotation: Quat::IDENTITY,
+            }
+            .into(),
+        });
+    for _ in 0..recording_resources::CHECKPOINT_INTERVAL_TICKS {
+        harness.step();
+    }
+    harness
+        .server
+        .world_mut()
+        .write_message(console_events::ConsoleCommandEvent(
+            console_commands::ConsoleCommand::Teleport {
+                username: Username::from("Alex"),
+                position: Vec3::new(10.0, 50.0, 10.0),
+            },
+        ));
+    for _ in 0..recording_resources::CHECKPOINT_INTERVAL_TICKS {
+        harness.step();
+    }
+    harness.finish_recording();
+
+    let recording =
+        recording_resources::SessionRecording::read(&harness.temp_path(harness::RECORDING_FILE))
+            .unwrap();
+    assert!(recording
+        .events
+        .iter()
+        .any(|event| matches!(event.event, recording_resources::SessionEvent::Console(_))));
+    let report = replay::run(&recording, &harness.temp_path("replay_server")).unwrap();
+
+    assert!(report.inbound_messages >= 3);
+    assert!(report.checkpoints >= 2);
+    assert!(!report.diverged(), "{report}");
+    assert_eq!(report.final_differences, Some(Vec::new()));
+}
@@ -1,11 +1,25 @@
+pub mod access;
+pub mod accounts;
 pub mod chat;
+pub mod config;
+pub mod console;
+pub mod metrics;
 pub mod networking;
 pub mod player;
 pub mod prelude;
+pub mod recording;
+pub mod status;
 pub mod terrain;
+pub mod tick;
 
+#[cfg(test)]
+mod integration_tests;
+
+#[cfg(not(feature = "egui_layer"))]
+use bevy::app::ScheduleRunnerPlugin;
 use bevy::app::TerminalCtrlCHandlerPlugin;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[cfg(feature = "egui_layer")]
 use bevy::DefaultPlugins;
@@ -22,16 +36,80 @@ use crate::prelude::*;
 #[command(long_about = None)]
 struct Cli {
     #[command(subcommand)]
-    world_commands: terrain_commands::WorldCommands,
+    command: Command,
+
+    #[command(flatten)]
+    settings: config::ServerSettingsArgs,
+
+    /// Record every message to and from players to this file, for `replay`
+    #[arg(long, global = true)]
+    record: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(flatten)]
+    World(terrain_commands::WorldCommands),
+    #[command(
+        about = "Issue a connect token for a username, signed with the server's private key"
+    )]
+    IssueToken(networking::commands::IssueTokenArgs),
+    #[command(
+        about = "Replay a recorded session in a headless server and report where it diverges"
+    )]
+    Replay(recording::commands::ReplayArgs),
 }
 
 fn main() {
+    let args = Cli::parse();
+    let settings = match config::ServerSettings::load(&args.settings) {
+        Ok(settings) => settings,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return;
+        }
+    };
+
+    let world_commands = match args.command {
+        Command::World(world_commands) => world_commands,
+        Command::IssueToken(issue_token_args) => {
+            if let Err(error) = networking::commands::issue_token(issue_token_args, &settings) {
+                eprintln!("Error: {}", error);
+            }
+            return;
+        }
+        Command::Replay(replay_args) => {
+            if let Err(error) = recording::commands::replay_session(replay_args) {
+                eprintln!("Error: {}", error);
+            }
+            return;
+        }
+    };
+
+    let private_key = match settings.private_key() {
+        Ok(private_key) => private_key,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return;
+        }
+    };
+
+    let tick_plugin = match tick::TickPlugin::new(settings.tick_rate) {
+        Ok(tick_plugin) => tick_plugin,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return;
+        }
+    };
+
     let mut app = App::new();
     app.add_plugins(TerminalCtrlCHandlerPlugin);
 
     #[cfg(not(feature = "egui_layer"))]
     {
-        app.add_plugins(MinimalPlugins);
+        app.add_plugins(
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(tick_plugin.tick_interval())),
+        );
         app.add_plugins(LogPlugin::default());
     }
 
@@ -43,21 +121,94 @@ fn main() {
         app.add_systems(Startup, gui::setup_camera_system);
     }
 
-    let args = Cli::parse();
-    match terrain::TerrainPlugin::from_command(args.world_commands) {
-        Ok(terrain_plugin) => app.add_plugins(terrain_plugin),
+    app.add_plugins(tick_plugin);
+
+    match terrain::TerrainPlugin::from_command(world_commands) {
+        Ok(terrain_plugin) => app.add_plugins(
+            terrain_plugin
+                .with_autosave_intervals(settings.save_interval(), settings.backup_interval())
+                .with_chunk_streaming(settings.chunk_streaming_config()),
+        ),
         Err(error) => {
             eprintln!("Error: {}", error);
             return;
         }
     };
 
-    app.add_plugins(player::PlayerPlugin);
-    app.add_plugins(networking::NetworkingPlugin);
+    app.add_plugins(player::PlayerPlugin::new(
+        settings.spawn_point,
+        settings.player_sync_config(),
+    ));
+    let networking_plugin =
+        networking::NetworkingPlugin::new(settings.address, settings.max_clients, private_key)
+            .with_public_address(settings.public_address());
+    app.add_plugins(match settings.link_conditioner.enabled {
+        true => networking_plugin.with_link_conditioner(settings.link_conditioner.clone()),
+        false => networking_plugin,
+    });
+    app.add_plugins(console::ConsolePlugin);
 
     #[cfg(feature = "chat")]
     app.add_plugins(chat::ChatPlugin);
 
+    match access::AccessPlugin::load() {
+        Ok(access_plugin) => app.add_plugins(access_plugin),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return;
+        }
+    };
+
+    match accounts::AccountsPlugin::load(settings.require_accounts) {
+        Ok(accounts_plugin) => app.add_plugins(accounts_plugin),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return;
+        }
+    };
+
+    let status_plugin = status::StatusPlugin::new(settings.motd.clone(), settings.max_clients);
+    let status_plugin = match settings.status_address() {
+        Some(status_address) => status_plugin.with_status_queries(status_address),
+        None => Ok(status_plugin),
+    }
+    .and_then(|plugin| match settings.lan_discovery {
+        true => {
+            plugin.with_lan_announcements(settings.lan_discovery_address, settings.address.port())
+        }
+        false => Ok(plugin),
+    });
+    match status_plugin {
+        Ok(status_plugin) => app.add_plugins(status_plugin),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            return;
+        }
+    };
+
+    if settings.metrics {
+        match metrics::MetricsPlugin::bind(settings.metrics_address) {
+            Ok(metrics_plugin) => app.add_plugins(metrics_plugin),
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return;
+            }
+        };
+    }
+
+    if let Some(path) = &args.record {
+        match recording::RecordingPlugin::create(path) {
+            Ok(recording_plugin) => {
+                println!("Recording the session to '{}'", path.display());
+                app.add_plugins(recording_plugin);
+            }
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return;
+            }
+        };
+    }
+
     println!("Server is starting!");
     app.run();
 }
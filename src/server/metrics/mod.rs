@@ -0,0 +1,45 @@
+use std::net::{SocketAddr, TcpListener};
+
+use crate::prelude::*;
+
+pub mod resources;
+pub mod systems;
+
+pub const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9464";
+
+/// Serves Prometheus text format metrics over HTTP on `GET /metrics`. Scrapes are
+/// answered on their own thread from a snapshot that the server refreshes, so that
+/// slow scrapers never hold up a tick.
+pub struct MetricsPlugin {
+    listener: TcpListener,
+}
+
+impl MetricsPlugin {
+    pub fn bind(address: SocketAddr) -> Result<Self, String> {
+        let listener = TcpListener::bind(address)
+            .map_err(|err| format!("Failed to bind metrics endpoint on {address}: {err}"))?;
+        println!("Serving metrics on http://{address}/metrics");
+
+        Ok(Self { listener })
+    }
+}
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building MetricsPlugin");
+
+        let listener = self
+            .listener
+            .try_clone()
+            .expect("Metrics listener should be clonable");
+        let snapshot = resources::MetricsSnapshot::default();
+        let served_snapshot = snapshot.clone();
+        std::thread::Builder::new()
+            .name(String::from("metrics"))
+            .spawn(move || metrics_systems::serve_metrics(listener, served_snapshot))
+            .expect("Metrics thread should start");
+
+        app.insert_resource(snapshot);
+        app.add_systems(Update, metrics_systems::update_metrics_snapshot_system);
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::prelude::*;
+
+pub const METRICS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The last rendered metrics, shared with the thread that answers scrapes.
+#[derive(Resource, Clone, Default)]
+pub struct MetricsSnapshot {
+    text: Arc<Mutex<String>>,
+    rendered_at: Option<Duration>,
+}
+
+impl MetricsSnapshot {
+    /// Returns whether the snapshot is older than `METRICS_REFRESH_INTERVAL`.
+    pub fn is_due(&self, now: Duration) -> bool {
+        self.rendered_at
+            .is_none_or(|rendered_at| now.saturating_sub(rendered_at) >= METRICS_REFRESH_INTERVAL)
+    }
+
+    pub fn update(&mut self, text: String, now: Duration) {
+        *self
+            .text
+            .lock()
+            .expect("Metrics lock should not be poisoned") = text;
+        self.rendered_at = Some(now);
+    }
+
+    pub fn text(&self) -> String {
+        self.text
+            .lock()
+            .expect("Metrics lock should not be poisoned")
+            .clone()
+    }
+}
+
+/// Builds a response body in the Prometheus text exposition format.
+#[derive(Default)]
+pub struct MetricsWriter {
+    text: String,
+}
+
+impl MetricsWriter {
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.header(name, help, "gauge");
+        self.sample(name, None, value);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: f64) {
+        self.header(name, help, "counter");
+        self.sample(name, None, value);
+    }
+
+    /// A gauge with one sample per value of `label`.
+    pub fn labeled_gauge(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        samples: &[(String, f64)],
+    ) {
+        self.labeled(name, help, "gauge", label, samples);
+    }
+
+    /// A counter with one sample per value of `label`.
+    pub fn labeled_counter(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        samples: &[(String, f64)],
+    ) {
+        self.labeled(name, help, "counter", label, samples);
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+
+    fn labeled(
+        &mut self,
+        name: &str,
+        help: &str,
+        kind: &str,
+        label: &str,
+        samples: &[(String, f64)],
+    ) {
+        self.header(name, help, kind);
+        for (label_value, value) in samples {
+            self.sample(name, Some((label, label_value)), *value);
+        }
+    }
+
+    fn header(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.text, "# HELP {name} {help}");
+        let _ = writeln!(self.text, "# TYPE {name} {kind}");
+    }
+
+    fn sample(&mut self, name: &str, label: Option<(&str, &str)>, value: f64) {
+        match label {
+            Some((label, label_value)) => {
+                let label_value = label_value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                let _ = writeln!(self.text, "{name}{{{label}=\"{label_value}\"}} {value}");
+            }
+            None => {
+                let _ = writeln!(self.text, "{name} {value}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_snapshot_is_shared_and_refreshed() {
+        let mut snapshot = MetricsSnapshot::default();
+        let served = snapshot.clone();
+        assert!(snapshot.is_due(Duration::ZERO));
+
+        snapshot.update(
+            String::from("rsmc_loaded_chunks 1\n"),
+            Duration::from_secs(5),
+        );
+        assert_eq!(served.text(), "rsmc_loaded_chunks 1\n");
+        assert!(!snapshot.is_due(Duration::from_millis(5999)));
+        assert!(snapshot.is_due(Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_metrics_text_format() {
+        let mut writer = MetricsWriter::default();
+        writer.gauge("rsmc_loaded_chunks", "Loaded chunks", 12.0);
+        writer.labeled_gauge(
+            "rsmc_queue",
+            "Queue depth",
+            "client",
+            &[(String::from("Ste\"ve"), 0.5)],
+        );
+
+        assert_eq!(
+            writer.finish(),
+            "# HELP rsmc_loaded_chunks Loaded chunks\n\
+             # TYPE rsmc_loaded_chunks gauge\n\
+             rsmc_loaded_chunks 12\n\
+             # HELP rsmc_queue Queue depth\n\
+             # TYPE rsmc_queue gauge\n\
+             rsmc_queue{client=\"Ste\\\"ve\"} 0.5\n"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+use bevy::time::{Real, Time};
+
+use crate::{
+    networking::resources::{ActiveConnections, ChannelTraffic},
+    prelude::*,
+};
+use metrics_resources::{MetricsSnapshot, MetricsWriter};
+
+/// Total time a scraper gets to send its request and read the response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+#[derive(SystemParam)]
+pub struct ServerMetrics<'w> {
+    server: Res<'w, RenetServer>,
+    active_connections: Res<'w, ActiveConnections>,
+    channel_traffic: Res<'w, ChannelTraffic>,
+    client_usernames: Res<'w, ClientUsernames>,
+    chunk_requests: Res<'w, terrain_resources::ClientChunkRequests>,
+    chunk_manager: Res<'w, ChunkManager>,
+    terrain_stats: Res<'w, terrain_resources::TerrainStats>,
+    tick_stats: Res<'w, tick_resources::TickStats>,
+}
+
+impl ServerMetrics<'_> {
+    pub fn render(&self) -> String {
+        let mut writer = MetricsWriter::default();
+
+        writer.gauge(
+            "rsmc_connected_players",
+            "Players that joined the game",
+            self.active_connections.accepted_clients().count() as f64,
+        );
+        writer.gauge(
+            "rsmc_loaded_chunks",
+            "Chunks held in memory",
+            self.chunk_manager.chunk_count() as f64,
+        );
+        writer.counter(
+            "rsmc_chunks_generated_total",
+            "Chunks generated since startup",
+            self.terrain_stats.chunks_generated as f64,
+        );
+        writer.counter(
+            "rsmc_chunk_bytes_encoded_total",
+            "Size of the chunk batches sent to clients before compression",
+            self.terrain_stats.chunk_bytes_encoded as f64,
+        );
+        writer.counter(
+            "rsmc_chunk_bytes_sent_total",
+            "Size of the chunk batches sent to clients",
+            self.terrain_stats.chunk_bytes_sent as f64,
+        );
+        writer.gauge(
+            "rsmc_chunk_compression_ratio",
+            "Sent chunk bytes per encoded chunk byte since startup",
+            self.terrain_stats.compression_ratio(),
+        );
+        writer.gauge(
+            "rsmc_world_save_duration_seconds",
+            "Duration of the last world save",
+            self.terrain_stats
+                .last_save_duration
+                .unwrap_or_default()
+                .as_secs_f64(),
+        );
+        writer.gauge(
+            "rsmc_tick_duration_seconds",
+            "Average duration of the last server ticks",
+            self.tick_stats.average().as_secs_f64(),
+        );
+        writer.gauge(
+            "rsmc_tick_duration_max_seconds",
+            "Longest duration of the last server ticks",
+            self.tick_stats.max().as_secs_f64(),
+        );
+        writer.counter(
+            "rsmc_tick_overruns_total",
+            "Ticks that took longer than the tick interval",
+            self.tick_stats.overruns() as f64,
+        );
+
+        let queue_depths: Vec<(String, f64)> = self
+            .chunk_requests
+            .queue_lengths()
+            .map(|(client_id, len)| (self.client_label(client_id), len as f64))
+            .collect();
+        writer.labeled_gauge(
+            "rsmc_chunk_request_queue_depth",
+            "Chunks queued for a client",
+            "client",
+            &queue_depths,
+        );
+
+        let network_info: Vec<(String, NetworkInfo)> = self
+            .server
+            .clients_id()
+            .into_iter()
+            .filter_map(|client_id| {
+                let info = self.server.network_info(client_id).ok()?;
+                Some((self.client_label(&client_id), info))
+            })
+            .collect();
+        writer.labeled_gauge(
+            "rsmc_network_sent_bytes_per_second",
+            "Bytes sent to a client",
+            "client",
+            &network_info
+                .iter()
+                .map(|(client, info)| (client.clone(), info.bytes_sent_per_second))
+                .collect::<Vec<_>>(),
+        );
+        writer.labeled_gauge(
+            "rsmc_network_received_bytes_per_second",
+            "Bytes received from a client",
+            "client",
+            &network_info
+                .iter()
+                .map(|(client, info)| (client.clone(), info.bytes_received_per_second))
+                .collect::<Vec<_>>(),
+        );
+
+        let channel_traffic: Vec<(&str, u64, u64)> = self.channel_traffic.totals().collect();
+        writer.labeled_counter(
+            "rsmc_network_channel_sent_bytes_total",
+            "Message bytes sent on a channel since startup",
+            "channel",
+            &channel_traffic
+                .iter()
+                .map(|(channel, sent, _)| (channel.to_string(), *sent as f64))
+                .collect::<Vec<_>>(),
+        );
+        writer.labeled_counter(
+            "rsmc_network_channel_received_bytes_total",
+            "Message bytes received on a channel since startup",
+            "channel",
+            &channel_traffic
+                .iter()
+                .map(|(channel, _, received)| (channel.to_string(), *received as f64))
+                .collect::<Vec<_>>(),
+        );
+
+        writer.finish()
+    }
+
+    fn client_label(&self, client_id: &ClientId) -> String {
+        match self.client_usernames.username_for_client_id(client_id) {
+            Some(username) => username.to_string(),
+            None => client_id.to_string(),
+        }
+    }
+}
+
+pub fn update_metrics_snapshot_system(
+    mut snapshot: ResMut<MetricsSnapshot>,
+    metrics: ServerMetrics,
+    time: Res<Time<Real>>,
+) {
+    if snapshot.is_due(time.elapsed()) {
+        snapshot.update(metrics.render(), time.elapsed());
+    }
+}
+
+/// Answers scrapes one at a time with the latest snapshot. Runs on its own thread
+/// for as long as the server is up.
+pub fn serve_metrics(listener: TcpListener, snapshot: MetricsSnapshot) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("Failed to accept metrics connection: {err}");
+                continue;
+            }
+        };
+
+        if let Err(err) = respond(stream, &snapshot) {
+            debug!("Failed to answer metrics request: {err}");
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, snapshot: &MetricsSnapshot) -> std::io::Result<()> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(ErrorKind::TimedOut.into());
+        }
+        stream.set_read_timeout(Some(remaining))?;
+        match stream.read(&mut buffer)? {
+            0 => break,
+            len => request.extend_from_slice(&buffer[..len]),
+        }
+    }
+
+    let (status, body) = if request.starts_with(b"GET /metrics ") {
+        ("200 OK", snapshot.text())
+    } else {
+        ("404 Not Found", String::from("Not found\n"))
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::{config::ServerSettings, prelude::*};
+
+#[derive(Debug, Args)]
+pub struct IssueTokenArgs {
+    #[arg(required = true)]
+    username: String,
+    #[arg(
+        short,
+        long,
+        help = "File to write the connect token to [default: <username>.token]"
+    )]
+    output: Option<PathBuf>,
+    #[arg(long, default_value_t = DEFAULT_TOKEN_EXPIRE_SECONDS, help = "Seconds until the token expires")]
+    expire_seconds: u64,
+}
+
+/// Mints a connect token for the public server address, signed with the server's private key.
+pub fn issue_token(args: IssueTokenArgs, settings: &ServerSettings) -> Result<(), String> {
+    let username = Username::new(&args.username)?;
+    let public_address = settings.public_address();
+    if public_address.ip().is_unspecified() {
+        return Err(format!(
+            "Clients cannot connect to {public_address}, set public_address to the address they use"
+        ));
+    }
+    let private_key = load_or_create_private_key(&settings.private_key_file)?;
+    let token = issue_connect_token(
+        &private_key,
+        &username,
+        vec![public_address],
+        args.expire_seconds,
+    )?;
+
+    let output = args
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("{username}.token")));
+    write_connect_token(&token, &output)?;
+
+    println!(
+        "Wrote connect token for {username} to '{}', valid for {} seconds",
+        output.display(),
+        args.expire_seconds
+    );
+    if !settings.secure {
+        println!("Note: the server only checks connect tokens when 'secure' is enabled");
+    }
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use crate::prelude::*;
+
+/// A client passed every connection check and can join as `username`.
+#[derive(Message)]
+pub struct PlayerAuthenticatedEvent {
+    pub client_id: ClientId,
+    pub username: Username,
+}
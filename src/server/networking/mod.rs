@@ -1,14 +1,66 @@
+pub mod commands;
+pub mod events;
 pub mod resources;
 pub mod systems;
 
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
 use crate::connection_config;
 
-use crate::networking::resources::{ActiveConnections, PendingDisconnects};
+use crate::networking::resources::{
+    ActiveConnections, ChannelTraffic, ClientMessageErrors, PendingDisconnects,
+    PlayerUpdateSequences,
+};
 use crate::prelude::*;
 
-const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:5000";
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:5000";
+
+pub struct NetworkingPlugin {
+    server_addr: SocketAddr,
+    public_addr: Option<SocketAddr>,
+    max_clients: usize,
+    private_key: Option<PrivateKey>,
+    link_conditioner: Option<LinkConditionerSettings>,
+    socket: Option<UdpSocket>,
+}
+
+impl NetworkingPlugin {
+    /// Without a private key, clients connect unauthenticated with any username.
+    pub fn new(
+        server_addr: SocketAddr,
+        max_clients: usize,
+        private_key: Option<PrivateKey>,
+    ) -> Self {
+        Self {
+            server_addr,
+            public_addr: None,
+            max_clients,
+            private_key,
+            link_conditioner: None,
+            socket: None,
+        }
+    }
+
+    /// Announces `public_addr` instead of the server address, connect tokens must name it.
+    pub fn with_public_address(mut self, public_addr: SocketAddr) -> Self {
+        self.public_addr = Some(public_addr);
+        self
+    }
+
+    /// Serves on an already bound socket instead of binding the server address, which
+    /// is then only announced to clients. Tests use this to keep the port they picked.
+    pub fn with_socket(mut self, socket: UdpSocket) -> Self {
+        self.socket = Some(socket);
+        self
+    }
 
-pub struct NetworkingPlugin;
+    /// Puts a proxy that adds latency, jitter, loss and bandwidth caps in front of the
+    /// server. It listens on the server address while the server moves to a loopback port.
+    pub fn with_link_conditioner(mut self, settings: LinkConditionerSettings) -> Self {
+        self.link_conditioner = Some(settings);
+        self
+    }
+}
 
 impl Plugin for NetworkingPlugin {
     fn build(&self, app: &mut App) {
@@ -37,12 +89,32 @@ impl Plugin for NetworkingPlugin {
         app.insert_resource(server);
 
         app.add_plugins(NetcodeServerPlugin);
-        app.insert_resource(Self::build_transport_resource());
+        let socket = match (&self.socket, &self.link_conditioner) {
+            (Some(socket), _) => socket
+                .try_clone()
+                .expect("Server socket should be clonable"),
+            (None, Some(settings)) => {
+                let (socket, conditioner) = self.bind_conditioned_socket(settings.clone());
+                app.insert_resource(conditioner);
+                socket
+            }
+            (None, None) => UdpSocket::bind(self.server_addr).unwrap(),
+        };
+        app.insert_resource(self.build_transport_resource(socket));
         app.insert_resource(ClientUsernames::default());
         app.insert_resource(ActiveConnections::default());
         app.insert_resource(PendingDisconnects::default());
-        app.add_systems(Update, networking_systems::receive_message_system);
-        app.add_systems(Update, networking_systems::handle_events_system);
+        app.insert_resource(ClientMessageErrors::default());
+        app.insert_resource(PlayerUpdateSequences::default());
+        app.insert_resource(ChannelTraffic::default());
+        app.add_systems(FixedUpdate, networking_systems::receive_message_system);
+        app.add_message::<events::PlayerAuthenticatedEvent>();
+        app.add_systems(FixedUpdate, networking_systems::handle_events_system);
+        app.add_systems(
+            FixedUpdate,
+            networking_systems::accept_players_system
+                .after(networking_systems::handle_events_system),
+        );
         app.add_systems(
             Last,
             (
@@ -54,17 +126,52 @@ impl Plugin for NetworkingPlugin {
 }
 
 impl NetworkingPlugin {
-    fn build_transport_resource() -> NetcodeServerTransport {
-        let server_addr = DEFAULT_SERVER_ADDR.parse().unwrap();
-        let socket = UdpSocket::bind(server_addr).unwrap();
+    fn bind_conditioned_socket(
+        &self,
+        settings: LinkConditionerSettings,
+    ) -> (UdpSocket, LinkConditioner) {
+        let internal_addr: SocketAddr = if self.server_addr.is_ipv6() {
+            (Ipv6Addr::LOCALHOST, 0).into()
+        } else {
+            (Ipv4Addr::LOCALHOST, 0).into()
+        };
+        let socket = UdpSocket::bind(internal_addr).unwrap();
+        let conditioner = LinkConditioner::spawn(
+            self.server_addr,
+            socket.local_addr().unwrap(),
+            settings.clone(),
+        )
+        .expect("Link conditioner should start");
+
+        info!(
+            "Simulating {} ms latency, {} ms jitter, {}% packet loss and {} bandwidth",
+            settings.latency_ms,
+            settings.jitter_ms,
+            settings.packet_loss * 100.0,
+            match settings.bandwidth_kbps {
+                0 => String::from("unlimited"),
+                kbps => format!("{kbps} kbit/s"),
+            }
+        );
+        warn!(
+            "With the link conditioner enabled all clients connect from loopback, IP bans are not enforced"
+        );
+        (socket, conditioner)
+    }
+
+    fn build_transport_resource(&self, socket: UdpSocket) -> NetcodeServerTransport {
+        let public_addr = self.public_addr.unwrap_or(self.server_addr);
         let server_config = ServerConfig {
             current_time: SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .unwrap(),
-            max_clients: 64,
-            protocol_id: 0,
-            public_addresses: vec![server_addr],
-            authentication: ServerAuthentication::Unsecure,
+            max_clients: self.max_clients,
+            protocol_id: PROTOCOL_ID,
+            public_addresses: vec![public_addr],
+            authentication: match self.private_key {
+                Some(private_key) => ServerAuthentication::Secure { private_key },
+                None => ServerAuthentication::Unsecure,
+            },
         };
         NetcodeServerTransport::new(server_config, socket)
             .expect("Serverconfig and socket should be valid")
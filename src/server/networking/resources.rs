@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt::Display};
 
 use renet::ClientId;
 
@@ -21,6 +21,31 @@ impl ActiveConnections {
     pub fn is_accepted(&self, client_id: &ClientId) -> bool {
         self.accepted_clients.contains(client_id)
     }
+
+    pub fn accepted_clients(&self) -> impl Iterator<Item = &ClientId> {
+        self.accepted_clients.iter()
+    }
+
+    pub fn accepted_client_id(
+        &self,
+        client_usernames: &ClientUsernames,
+        username: &Username,
+    ) -> Option<ClientId> {
+        client_usernames
+            .get_client_id(username)
+            .filter(|client_id| self.is_accepted(client_id))
+            .copied()
+    }
+
+    pub fn accepted_usernames(&self, client_usernames: &ClientUsernames) -> Vec<Username> {
+        let mut usernames: Vec<Username> = self
+            .accepted_clients()
+            .filter_map(|client_id| client_usernames.username_for_client_id(client_id))
+            .copied()
+            .collect();
+        usernames.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        usernames
+    }
 }
 
 #[derive(Default, Resource)]
@@ -40,3 +65,108 @@ impl PendingDisconnects {
         to_disconnect
     }
 }
+
+pub const MAX_CLIENT_MESSAGE_ERRORS: u32 = 10;
+
+#[derive(Debug)]
+pub enum ClientMessageError {
+    Oversized(usize),
+    Malformed(bincode::Error),
+    Unexpected {
+        kind: &'static str,
+        channel: &'static str,
+    },
+}
+
+impl Display for ClientMessageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientMessageError::Oversized(size) => {
+                write!(f, "message of {size} bytes exceeds its size limit")
+            }
+            ClientMessageError::Malformed(err) => write!(f, "malformed message: {err}"),
+            ClientMessageError::Unexpected { kind, channel } => {
+                write!(f, "unexpected {kind} message on {channel} channel")
+            }
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct ClientMessageErrors {
+    counts: HashMap<ClientId, u32>,
+}
+
+impl ClientMessageErrors {
+    /// Returns the number of errors recorded for the client so far.
+    pub fn record(&mut self, client_id: ClientId) -> u32 {
+        let count = self.counts.entry(client_id).or_default();
+        *count += 1;
+        *count
+    }
+
+    pub fn exceeds_limit(&self, client_id: &ClientId) -> bool {
+        self.counts
+            .get(client_id)
+            .is_some_and(|count| *count >= MAX_CLIENT_MESSAGE_ERRORS)
+    }
+
+    pub fn remove(&mut self, client_id: &ClientId) {
+        self.counts.remove(client_id);
+    }
+}
+
+/// Message bytes sent and received on every channel since startup.
+#[derive(Default, Resource)]
+pub struct ChannelTraffic {
+    sent: [u64; 3],
+    received: [u64; 3],
+}
+
+impl ChannelTraffic {
+    pub fn add_sent(&mut self, channel: DefaultChannel, bytes: usize) {
+        self.sent[Self::index(channel)] += bytes as u64;
+    }
+
+    pub fn add_received(&mut self, channel: DefaultChannel, bytes: usize) {
+        self.received[Self::index(channel)] += bytes as u64;
+    }
+
+    /// Sent and received bytes per channel name.
+    pub fn totals(&self) -> impl Iterator<Item = (&'static str, u64, u64)> + '_ {
+        ["unreliable", "reliable_ordered", "reliable_unordered"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| (name, self.sent[index], self.received[index]))
+    }
+
+    fn index(channel: DefaultChannel) -> usize {
+        match channel {
+            DefaultChannel::Unreliable => 0,
+            DefaultChannel::ReliableOrdered => 1,
+            DefaultChannel::ReliableUnordered => 2,
+        }
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct PlayerUpdateSequences {
+    last_sequences: HashMap<ClientId, u32>,
+}
+
+impl PlayerUpdateSequences {
+    /// Records `sequence` and returns whether it is newer than the client's last update.
+    pub fn accept(&mut self, client_id: ClientId, sequence: u32) -> bool {
+        match self.last_sequences.get(&client_id) {
+            Some(previous) if !is_newer_sequence(sequence, *previous) => false,
+            _ => {
+                self.last_sequences.insert(client_id, sequence);
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, client_id: &ClientId) {
+        self.last_sequences.remove(client_id);
+    }
+}
@@ -1,11 +1,182 @@
 use crate::{
-    networking::resources::{ActiveConnections, PendingDisconnects},
+    networking::resources::{
+        ActiveConnections, ChannelTraffic, ClientMessageError, ClientMessageErrors,
+        PendingDisconnects, PlayerUpdateSequences, MAX_CLIENT_MESSAGE_ERRORS,
+    },
     prelude::*,
 };
 
 use bevy::prelude::*;
+use std::ops::{Deref, DerefMut};
 
-fn find_ground_spawn_position(chunk_manager: &ChunkManager, base_world_position: IVec3) -> IVec3 {
+#[derive(SystemParam)]
+pub struct SpawnPosition<'w> {
+    chunk_manager: Res<'w, ChunkManager>,
+    spawn_point: Res<'w, player_resources::SpawnPoint>,
+}
+
+impl SpawnPosition<'_> {
+    pub fn get(&self) -> Vec3 {
+        find_ground_spawn_position(&self.chunk_manager, self.spawn_point.0).as_vec3()
+            + Vec3::new(0.5, 0.0, 0.5)
+    }
+}
+
+/// The renet server with typed sends, which also end up in the session recording
+/// when one is running and are counted per channel. Broadcasts only reach accepted
+/// players, never clients that are still logging in or about to be dropped. Everything
+/// else is reached through `Deref`.
+#[derive(SystemParam)]
+pub struct GameServer<'w> {
+    server: ResMut<'w, RenetServer>,
+    connections: ResMut<'w, ActiveConnections>,
+    traffic: ResMut<'w, ChannelTraffic>,
+    recorder: Option<ResMut<'w, recording_resources::SessionRecorder>>,
+}
+
+impl GameServer<'_> {
+    pub fn send(
+        &mut self,
+        client_id: ClientId,
+        channel: DefaultChannel,
+        message: &NetworkingMessage,
+    ) {
+        let bytes = serialize_message(message);
+        self.send_serialized(client_id, channel, message, bytes);
+    }
+
+    /// Like `send`, for a message that was serialized with `serialize_message` already.
+    pub fn send_serialized(
+        &mut self,
+        client_id: ClientId,
+        channel: DefaultChannel,
+        message: &NetworkingMessage,
+        bytes: Vec<u8>,
+    ) {
+        self.record_outbound(
+            recording_resources::OutboundTarget::Client(client_id),
+            message,
+        );
+        self.traffic.add_sent(channel, bytes.len());
+        self.server.send_message(client_id, channel, bytes);
+    }
+
+    pub fn broadcast(&mut self, channel: DefaultChannel, message: &NetworkingMessage) {
+        self.record_outbound(recording_resources::OutboundTarget::All, message);
+        let recipients = self.accepted_clients(None);
+        self.send_to_all(&recipients, channel, message);
+    }
+
+    pub fn broadcast_except(
+        &mut self,
+        client_id: ClientId,
+        channel: DefaultChannel,
+        message: &NetworkingMessage,
+    ) {
+        self.record_outbound(
+            recording_resources::OutboundTarget::AllExcept(client_id),
+            message,
+        );
+        let recipients = self.accepted_clients(Some(client_id));
+        self.send_to_all(&recipients, channel, message);
+    }
+
+    pub fn connections(&self) -> &ActiveConnections {
+        &self.connections
+    }
+
+    pub fn connections_mut(&mut self) -> &mut ActiveConnections {
+        &mut self.connections
+    }
+
+    fn accepted_clients(&self, except: Option<ClientId>) -> Vec<ClientId> {
+        self.server
+            .clients_id()
+            .into_iter()
+            .filter(|id| self.connections.is_accepted(id) && Some(*id) != except)
+            .collect()
+    }
+
+    fn send_to_all(
+        &mut self,
+        recipients: &[ClientId],
+        channel: DefaultChannel,
+        message: &NetworkingMessage,
+    ) {
+        let bytes = Bytes::from(serialize_message(message));
+        self.traffic
+            .add_sent(channel, bytes.len() * recipients.len());
+        for client_id in recipients {
+            self.server.send_message(*client_id, channel, bytes.clone());
+        }
+    }
+
+    /// Shadows `RenetServer::receive_message` so that received bytes are counted.
+    pub fn receive_message(
+        &mut self,
+        client_id: ClientId,
+        channel: DefaultChannel,
+    ) -> Option<Bytes> {
+        let message = self.server.receive_message(client_id, channel)?;
+        self.traffic.add_received(channel, message.len());
+        Some(message)
+    }
+
+    pub fn record_inbound(
+        &mut self,
+        client_id: ClientId,
+        channel: DefaultChannel,
+        message: &NetworkingMessage,
+    ) {
+        self.record(recording_resources::SessionEvent::Inbound {
+            client_id,
+            channel: channel.into(),
+            message: message.clone(),
+        });
+    }
+
+    pub fn record(&mut self, event: recording_resources::SessionEvent) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(event);
+        }
+    }
+
+    fn record_outbound(
+        &mut self,
+        target: recording_resources::OutboundTarget,
+        message: &NetworkingMessage,
+    ) {
+        if self.recorder.is_some() {
+            self.record(recording_resources::SessionEvent::Outbound {
+                target,
+                message: message.clone(),
+            });
+        }
+    }
+}
+
+impl Deref for GameServer<'_> {
+    type Target = RenetServer;
+
+    fn deref(&self) -> &RenetServer {
+        &self.server
+    }
+}
+
+impl DerefMut for GameServer<'_> {
+    fn deref_mut(&mut self) -> &mut RenetServer {
+        &mut self.server
+    }
+}
+
+pub fn serialize_message(message: &NetworkingMessage) -> Vec<u8> {
+    bincode::serialize(message).expect("Message should always be sendable")
+}
+
+pub fn find_ground_spawn_position(
+    chunk_manager: &ChunkManager,
+    base_world_position: IVec3,
+) -> IVec3 {
     const MAX_DELTA: i32 = 64;
 
     for dy in 0..MAX_DELTA {
@@ -34,30 +205,71 @@ fn is_standable(chunk_manager: &ChunkManager, world_position: IVec3) -> bool {
         && ground_block.is_some_and(|b| b.is_standable())
 }
 
+pub fn kick_client(
+    server: &mut GameServer,
+    pending_disconnects: &mut PendingDisconnects,
+    client_id: ClientId,
+    mut reason: DisconnectReason,
+) {
+    reason.truncate();
+    server.send(
+        client_id,
+        DefaultChannel::ReliableOrdered,
+        &NetworkingMessage::Kick { reason },
+    );
+    pending_disconnects.queue(client_id);
+}
+
+/// Tells every client why it is being dropped before the app exits. The notice and the
+/// disconnect packets are flushed right away because no further frame runs.
 pub fn disconnect_all_clients_on_exit_system(
-    mut server: ResMut<RenetServer>,
+    mut server: GameServer,
+    mut transport: ResMut<NetcodeServerTransport>,
     mut exit_events: MessageReader<AppExit>,
 ) {
     if exit_events.read().len() > 0 {
-        server.disconnect_all();
+        // Clients that are still logging in are dropped as well, so this is not a
+        // broadcast.
+        for client_id in server.clients_id() {
+            server.send(
+                client_id,
+                DefaultChannel::ReliableOrdered,
+                &NetworkingMessage::Kick {
+                    reason: DisconnectReason::ServerShutdown,
+                },
+            );
+        }
+        transport.send_packets(&mut server);
+        transport.disconnect_all(&mut server);
     }
 }
 
+pub fn decode_client_message(bytes: &[u8]) -> Result<NetworkingMessage, ClientMessageError> {
+    deserialize_message(bytes).map_err(|err| match *err {
+        bincode::ErrorKind::SizeLimit => ClientMessageError::Oversized(bytes.len()),
+        _ => ClientMessageError::Malformed(err),
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn receive_message_system(
-    mut server: ResMut<RenetServer>,
+    mut server: GameServer,
     mut player_states: ResMut<player_resources::PlayerStates>,
     mut past_block_updates: ResMut<terrain_resources::PastBlockUpdates>,
     mut chunk_manager: ResMut<ChunkManager>,
     client_usernames: Res<ClientUsernames>,
     mut request_queue: ResMut<terrain_resources::ClientChunkRequests>,
-    accepted_clients: Res<ActiveConnections>,
+    mut message_errors: ResMut<ClientMessageErrors>,
+    mut update_sequences: ResMut<PlayerUpdateSequences>,
+    mut pending_disconnects: ResMut<PendingDisconnects>,
+    transport: Res<NetcodeServerTransport>,
     #[cfg(feature = "chat")] mut chat_message_events: MessageWriter<
         chat_events::PlayerChatMessageSendEvent,
     >,
 ) {
     for client_id in server.clients_id() {
-        if !accepted_clients.is_accepted(&client_id) {
+        if !server.connections().is_accepted(&client_id) || message_errors.exceeds_limit(&client_id)
+        {
             continue;
         }
 
@@ -65,9 +277,18 @@ pub fn receive_message_system(
             .username_for_client_id(&client_id)
             .cloned()
             .expect("All clients should be associated with a username");
+        let mut errors = Vec::new();
+
         while let Some(message) = server.receive_message(client_id, DefaultChannel::ReliableOrdered)
         {
-            let message = bincode::deserialize(&message).unwrap();
+            let message = match decode_client_message(&message) {
+                Ok(message) => message,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+            server.record_inbound(client_id, DefaultChannel::ReliableOrdered, &message);
 
             match message {
                 NetworkingMessage::BlockUpdate { position, block } => {
@@ -75,16 +296,31 @@ pub fn receive_message_system(
                         "Received block update from client {} {} {:?}",
                         client_id, position, block
                     );
+                    let in_reach = player_states.players.get(&username).is_some_and(|player| {
+                        player.position.distance(position.as_vec3()) <= MAX_BLOCK_UPDATE_DISTANCE
+                    });
+                    // The position the server knows can lag behind the client, so this is
+                    // not treated as a protocol error. The client gets the block back.
+                    if !in_reach {
+                        debug!("Dropping block update at {position} out of reach of {username}");
+                        if let Some(block) = chunk_manager.get_block(position) {
+                            server.send(
+                                client_id,
+                                DefaultChannel::ReliableOrdered,
+                                &NetworkingMessage::BlockUpdate { position, block },
+                            );
+                        }
+                        continue;
+                    }
                     chunk_manager.update_block(position, block);
                     past_block_updates
                         .updates
                         .push(terrain_events::BlockUpdateEvent { position, block });
 
-                    server.broadcast_message_except(
+                    server.broadcast_except(
                         client_id,
                         DefaultChannel::ReliableOrdered,
-                        bincode::serialize(&NetworkingMessage::BlockUpdate { position, block })
-                            .unwrap(),
+                        &NetworkingMessage::BlockUpdate { position, block },
                     );
                 }
                 #[cfg(feature = "chat")]
@@ -95,8 +331,11 @@ pub fn receive_message_system(
                         message,
                     });
                 }
-                _ => {
-                    warn!("Received unknown message type. (ReliabelOrdered)");
+                message => {
+                    errors.push(ClientMessageError::Unexpected {
+                        kind: message.kind(),
+                        channel: "ReliableOrdered",
+                    });
                 }
             }
         }
@@ -104,31 +343,105 @@ pub fn receive_message_system(
         while let Some(message) =
             server.receive_message(client_id, DefaultChannel::ReliableUnordered)
         {
-            let message = bincode::deserialize(&message).unwrap();
+            let message = match decode_client_message(&message) {
+                Ok(message) => message,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+            server.record_inbound(client_id, DefaultChannel::ReliableUnordered, &message);
             debug!("Received message: {:?}", message);
 
             match message {
-                NetworkingMessage::PlayerUpdate(player) => {
-                    debug!(
-                        "Received player update from client {} {}",
-                        client_id, player.position
-                    );
-                    let username = client_usernames
-                        .username_for_client_id(&client_id)
-                        .expect("All clients should have associated username");
-                    player_states.players.insert(*username, player);
-                }
                 NetworkingMessage::ChunkBatchRequest(positions) => {
                     info!(
                         "Received chunk batch request at {:?} from client {}",
                         positions, client_id
                     );
 
-                    request_queue.enqueue_bulk(client_id, &mut positions.into());
+                    request_queue.enqueue_bulk(
+                        client_id,
+                        positions
+                            .into_iter()
+                            .map(terrain_resources::ChunkRequest::new),
+                    );
+                }
+                NetworkingMessage::CachedChunkBatchRequest(chunks) => {
+                    info!(
+                        "Received cached chunk batch request for {} chunks from client {}",
+                        chunks.len(),
+                        client_id
+                    );
+
+                    request_queue.enqueue_bulk(
+                        client_id,
+                        chunks.into_iter().map(|(position, hash)| {
+                            terrain_resources::ChunkRequest::cached(position, hash)
+                        }),
+                    );
+                }
+                NetworkingMessage::ChunkRequestCancel(positions) => {
+                    let cancelled = request_queue.cancel(&client_id, &positions);
+                    debug!(
+                        "Client {} cancelled {} chunk requests, {} were still queued",
+                        client_id,
+                        positions.len(),
+                        cancelled
+                    );
                 }
-                _ => {
-                    warn!("Received unknown message type. (ReliableUnordered)");
+                message => {
+                    errors.push(ClientMessageError::Unexpected {
+                        kind: message.kind(),
+                        channel: "ReliableUnordered",
+                    });
+                }
+            }
+        }
+
+        while let Some(message) = server.receive_message(client_id, DefaultChannel::Unreliable) {
+            let message = decode_client_message(&message);
+            if let Ok(message) = &message {
+                server.record_inbound(client_id, DefaultChannel::Unreliable, message);
+            }
+
+            match message {
+                Ok(NetworkingMessage::PlayerUpdate { sequence, state }) => {
+                    if !update_sequences.accept(client_id, sequence) {
+                        continue;
+                    }
+
+                    let player = PlayerState::from(state);
+                    debug!(
+                        "Received player update from client {} {}",
+                        client_id, player.position
+                    );
+                    player_states.players.insert(username, player);
                 }
+                Ok(message) => errors.push(ClientMessageError::Unexpected {
+                    kind: message.kind(),
+                    channel: "Unreliable",
+                }),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        let address = transport.client_addr(client_id).map_or_else(
+            || String::from("unknown address"),
+            |address| address.to_string(),
+        );
+        for error in errors {
+            warn!("Invalid message from {username} ({address}): {error}");
+
+            if message_errors.record(client_id) >= MAX_CLIENT_MESSAGE_ERRORS {
+                println!("Kicking {username} ({address}) after too many invalid messages");
+                kick_client(
+                    &mut server,
+                    &mut pending_disconnects,
+                    client_id,
+                    DisconnectReason::ProtocolViolation,
+                );
+                break;
             }
         }
     }
@@ -136,102 +449,84 @@ pub fn receive_message_system(
 
 #[allow(clippy::too_many_arguments)]
 pub fn handle_events_system(
-    mut server: ResMut<RenetServer>,
+    mut server: GameServer,
     mut server_events: MessageReader<ServerEvent>,
-    mut player_states: ResMut<player_resources::PlayerStates>,
-    past_block_updates: Res<terrain_resources::PastBlockUpdates>,
+    mut authenticated_events: MessageWriter<networking_events::PlayerAuthenticatedEvent>,
     mut request_queue: ResMut<terrain_resources::ClientChunkRequests>,
-    mut client_usernames: ResMut<ClientUsernames>,
-    mut active_connections: ResMut<ActiveConnections>,
+    client_usernames: Res<ClientUsernames>,
     mut pending_disconnects: ResMut<PendingDisconnects>,
     #[cfg(feature = "chat")] mut chat_message_events: MessageWriter<
         chat_events::PlayerChatMessageSendEvent,
     >,
-    #[cfg(feature = "chat")] mut chat_sync_events: MessageWriter<
-        chat_events::SyncPlayerChatMessagesEvent,
-    >,
     transport: Res<NetcodeServerTransport>,
-    chunk_manager: Res<ChunkManager>,
+    access_lists: Res<access_resources::AccessLists>,
+    accounts: Res<accounts_resources::Accounts>,
+    mut pending_logins: ResMut<accounts_resources::PendingLogins>,
+    mut message_errors: ResMut<ClientMessageErrors>,
+    mut update_sequences: ResMut<PlayerUpdateSequences>,
+    time: Res<Time>,
 ) {
     for event in server_events.read() {
         match event {
             ServerEvent::ClientConnected { client_id } => {
-                let user_data = transport.user_data(*client_id).unwrap();
-                let username = Username::from_user_data(&user_data);
+                // Local clients, which replays connect, carry no user data and are
+                // named up front instead.
+                let Some(username) = transport
+                    .user_data(*client_id)
+                    .map(|user_data| Username::from_user_data(&user_data))
+                    .or_else(|| client_usernames.username_for_client_id(client_id).copied())
+                else {
+                    warn!("Client {client_id} connected without a username");
+                    server.disconnect(*client_id);
+                    continue;
+                };
+
+                let ip = transport
+                    .client_addr(*client_id)
+                    .map(|address| address.ip());
+                if let Err(reason) = access_lists.check(&username, ip) {
+                    kick_client(&mut server, &mut pending_disconnects, *client_id, reason);
+                    server.connections_mut().reject(client_id);
+                    println!("Client {client_id} with Username '{username}' denied access");
+                    continue;
+                }
 
-                if let Some(existing_client_id) = client_usernames.get_client_id(&username) {
-                    if active_connections.is_accepted(existing_client_id) {
-                        server.send_message(
-                            *client_id,
-                            DefaultChannel::ReliableOrdered,
-                            bincode::serialize(&NetworkingMessage::PlayerReject(String::from(
-                                "Another Client is already connected with that Username. Wait 15 seconds before trying again.",
-                            )))
-                            .expect("Message should always be sendable"),
-                        );
-                        active_connections.reject(client_id);
-                        pending_disconnects.queue(*client_id);
-                        println!("Client {client_id} with Username '{username}' rejected");
-                        continue;
-                    }
+                if accounts.is_registered(&username) {
+                    pending_logins.insert(*client_id, username, time.elapsed());
+                    server.send(
+                        *client_id,
+                        DefaultChannel::ReliableOrdered,
+                        &NetworkingMessage::LoginRequired,
+                    );
+                    continue;
                 }
 
-                active_connections.accept(*client_id);
-
-                let player_state =
-                    player_states
-                        .players
-                        .entry(username)
-                        .or_insert_with(|| PlayerState {
-                            position: find_ground_spawn_position(
-                                &chunk_manager,
-                                DEFAULT_SPAWN_POINT,
-                            )
-                            .as_vec3()
-                                + Vec3::new(0.5, 0.0, 0.5),
-                            rotation: Quat::IDENTITY,
-                        });
-
-                client_usernames.insert(*client_id, username);
-                server.send_message(
-                    *client_id,
-                    DefaultChannel::ReliableOrdered,
-                    bincode::serialize(&NetworkingMessage::PlayerAccept(*player_state))
-                        .expect("Message should always be sendable"),
-                );
-                println!("{username} connected");
+                if accounts.is_registration_required() {
+                    kick_client(
+                        &mut server,
+                        &mut pending_disconnects,
+                        *client_id,
+                        DisconnectReason::AccountRequired,
+                    );
+                    println!("Client {client_id} with unregistered Username '{username}' rejected");
+                    continue;
+                }
 
-                #[cfg(feature = "chat")]
-                chat_sync_events.write(chat_events::SyncPlayerChatMessagesEvent {
+                authenticated_events.write(networking_events::PlayerAuthenticatedEvent {
                     client_id: *client_id,
+                    username,
                 });
-
-                #[cfg(feature = "chat")]
-                chat_message_events.write(chat_events::PlayerChatMessageSendEvent {
-                    sender: ChatMessageSender::Server,
-                    message: format!("{username} joined the game"),
-                });
-
-                let message = bincode::serialize(&NetworkingMessage::PlayerJoin(username)).unwrap();
-                server.broadcast_message_except(
-                    *client_id,
-                    DefaultChannel::ReliableOrdered,
-                    message,
-                );
-
-                for update in past_block_updates.updates.iter() {
-                    let message = bincode::serialize(&NetworkingMessage::BlockUpdate {
-                        position: update.position,
-                        block: update.block,
-                    })
-                    .unwrap();
-                    server.send_message(*client_id, DefaultChannel::ReliableOrdered, message);
-                }
             }
             ServerEvent::ClientDisconnected { client_id, .. } => {
                 request_queue.remove(client_id);
-                if active_connections.is_accepted(client_id) {
-                    active_connections.reject(client_id);
+                pending_logins.remove(client_id);
+                message_errors.remove(client_id);
+                update_sequences.remove(client_id);
+                if server.connections().is_accepted(client_id) {
+                    server.connections_mut().reject(client_id);
+                    server.record(recording_resources::SessionEvent::Disconnected {
+                        client_id: *client_id,
+                    });
 
                     let username = client_usernames
                         .username_for_client_id(client_id)
@@ -247,10 +542,10 @@ pub fn handle_events_system(
                     });
 
                     if let Some(username) = client_usernames.username_for_client_id(client_id) {
-                        let message =
-                            bincode::serialize(&NetworkingMessage::PlayerLeave(*username))
-                                .expect("Messages should be serializable");
-                        server.broadcast_message(DefaultChannel::ReliableOrdered, message);
+                        server.broadcast(
+                            DefaultChannel::ReliableOrdered,
+                            &NetworkingMessage::PlayerLeave(*username),
+                        );
                     }
                 }
             }
@@ -258,6 +553,99 @@ pub fn handle_events_system(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn accept_players_system(
+    mut server: GameServer,
+    mut authenticated_events: MessageReader<networking_events::PlayerAuthenticatedEvent>,
+    mut player_states: ResMut<player_resources::PlayerStates>,
+    past_block_updates: Res<terrain_resources::PastBlockUpdates>,
+    mut client_usernames: ResMut<ClientUsernames>,
+    mut pending_disconnects: ResMut<PendingDisconnects>,
+    #[cfg(feature = "chat")] mut chat_message_events: MessageWriter<
+        chat_events::PlayerChatMessageSendEvent,
+    >,
+    #[cfg(feature = "chat")] mut chat_sync_events: MessageWriter<
+        chat_events::SyncPlayerChatMessagesEvent,
+    >,
+    spawn_position: SpawnPosition,
+    world_name: Res<terrain_resources::AutoSaveName>,
+    generator: Res<terrain_resources::Generator>,
+) {
+    for event in authenticated_events.read() {
+        let client_id = &event.client_id;
+        let username = event.username;
+
+        if let Some(existing_client_id) = client_usernames.get_client_id(&username) {
+            if server.connections().is_accepted(existing_client_id) {
+                kick_client(
+                    &mut server,
+                    &mut pending_disconnects,
+                    *client_id,
+                    DisconnectReason::UsernameTaken,
+                );
+                server.connections_mut().reject(client_id);
+                println!("Client {client_id} with Username '{username}' rejected");
+                continue;
+            }
+        }
+
+        server.connections_mut().accept(*client_id);
+
+        let player_state = player_states
+            .players
+            .entry(username)
+            .or_insert_with(|| PlayerState {
+                position: spawn_position.get(),
+                rotation: Quat::IDENTITY,
+            });
+
+        client_usernames.insert(*client_id, username);
+        server.record(recording_resources::SessionEvent::Connected {
+            client_id: *client_id,
+            username,
+        });
+        server.send(
+            *client_id,
+            DefaultChannel::ReliableOrdered,
+            &NetworkingMessage::WorldIdentity(world_name.world_identity(&generator)),
+        );
+        server.send(
+            *client_id,
+            DefaultChannel::ReliableOrdered,
+            &NetworkingMessage::PlayerAccept(*player_state),
+        );
+        println!("{username} connected");
+
+        #[cfg(feature = "chat")]
+        chat_sync_events.write(chat_events::SyncPlayerChatMessagesEvent {
+            client_id: *client_id,
+        });
+
+        #[cfg(feature = "chat")]
+        chat_message_events.write(chat_events::PlayerChatMessageSendEvent {
+            sender: ChatMessageSender::Server,
+            message: format!("{username} joined the game"),
+        });
+
+        server.broadcast_except(
+            *client_id,
+            DefaultChannel::ReliableOrdered,
+            &NetworkingMessage::PlayerJoin(username),
+        );
+
+        for update in past_block_updates.updates.iter() {
+            server.send(
+                *client_id,
+                DefaultChannel::ReliableOrdered,
+                &NetworkingMessage::BlockUpdate {
+                    position: update.position,
+                    block: update.block,
+                },
+            );
+        }
+    }
+}
+
 pub fn process_pending_disconnects_system(
     mut server: ResMut<RenetServer>,
     mut pending_disconnects: ResMut<PendingDisconnects>,
@@ -316,3 +704,48 @@ pub mod server_visualizer {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_client_message() {
+        let message =
+            bincode::serialize(&NetworkingMessage::ChatMessageSend(String::from("hi"))).unwrap();
+        assert!(matches!(
+            decode_client_message(&message),
+            Ok(NetworkingMessage::ChatMessageSend(_))
+        ));
+
+        assert!(matches!(
+            decode_client_message(&[255, 255, 255, 255]),
+            Err(ClientMessageError::Malformed(_))
+        ));
+        let message = bincode::serialize(&NetworkingMessage::ChatMessageSend(
+            "a".repeat(MAX_CHAT_MESSAGE_BYTES + 1),
+        ))
+        .unwrap();
+        assert!(matches!(
+            decode_client_message(&message),
+            Err(ClientMessageError::Oversized(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_message_errors_limit() {
+        let mut message_errors = ClientMessageErrors::default();
+
+        for _ in 1..MAX_CLIENT_MESSAGE_ERRORS {
+            message_errors.record(1);
+        }
+        assert!(!message_errors.exceeds_limit(&1));
+
+        assert_eq!(message_errors.record(1), MAX_CLIENT_MESSAGE_ERRORS);
+        assert!(message_errors.exceeds_limit(&1));
+        assert!(!message_errors.exceeds_limit(&2));
+
+        message_errors.remove(&1);
+        assert!(!message_errors.exceeds_limit(&1));
+    }
+}
@@ -3,11 +3,30 @@ use crate::prelude::*;
 pub mod resources;
 pub mod systems;
 
-pub struct PlayerPlugin;
+pub struct PlayerPlugin {
+    spawn_point: IVec3,
+    sync_config: player_resources::PlayerSyncConfig,
+}
+
+impl PlayerPlugin {
+    pub fn new(spawn_point: IVec3, sync_config: player_resources::PlayerSyncConfig) -> Self {
+        Self {
+            spawn_point,
+            sync_config,
+        }
+    }
+}
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(player_resources::PlayerStates::new());
-        app.add_systems(Update, player_systems::broadcast_player_attributes_system);
+        app.init_resource::<player_resources::PlayerPermissions>();
+        app.insert_resource(player_resources::SpawnPoint(self.spawn_point));
+        app.insert_resource(self.sync_config.clone());
+        app.init_resource::<player_resources::PlayerSyncState>();
+        app.add_systems(
+            FixedUpdate,
+            player_systems::broadcast_player_attributes_system,
+        );
     }
 }
@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
 use crate::prelude::*;
 
 #[derive(Resource)]
@@ -18,3 +22,86 @@ impl Default for PlayerStates {
         Self::new()
     }
 }
+
+/// Players spawn on the nearest standable ground to this position.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SpawnPoint(pub IVec3);
+
+impl Default for SpawnPoint {
+    fn default() -> Self {
+        Self(DEFAULT_SPAWN_POINT)
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerSyncConfig {
+    pub tick_rate: f32,
+    pub view_radius: f32,
+}
+
+impl PlayerSyncConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.tick_rate)
+    }
+}
+
+impl Default for PlayerSyncConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: 20.0,
+            view_radius: 160.0,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PlayerSyncState {
+    since_last_sync: Duration,
+}
+
+impl PlayerSyncState {
+    /// Returns whether a sync is due. Time past the interval counts towards the next
+    /// sync, so that the rate does not drift below the configured one. After a stall
+    /// the missed syncs are skipped instead of sent in a burst.
+    pub fn tick(&mut self, delta: Duration, interval: Duration) -> bool {
+        self.since_last_sync += delta;
+        if self.since_last_sync < interval {
+            return false;
+        }
+
+        self.since_last_sync -= interval;
+        if self.since_last_sync >= interval {
+            self.since_last_sync = Duration::ZERO;
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PermissionLevel {
+    #[default]
+    Player,
+    Operator,
+}
+
+#[derive(Resource, Default)]
+pub struct PlayerPermissions {
+    levels: HashMap<Username, PermissionLevel>,
+}
+
+impl PlayerPermissions {
+    pub fn level(&self, username: &Username) -> PermissionLevel {
+        self.levels.get(username).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, username: Username, level: PermissionLevel) {
+        self.levels.insert(username, level);
+    }
+
+    pub fn operators(&self) -> impl Iterator<Item = &Username> {
+        self.levels
+            .iter()
+            .filter(|(_, level)| **level == PermissionLevel::Operator)
+            .map(|(username, _)| username)
+    }
+}
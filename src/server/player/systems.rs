@@ -1,33 +1,138 @@
-use crate::networking::resources::ActiveConnections;
+use bevy::time::Time;
+use std::collections::HashSet;
+
 use crate::prelude::*;
 
+pub fn players_in_view(
+    players: impl Iterator<Item = (Username, PlayerState)>,
+    viewer: &Username,
+    viewer_position: Vec3,
+    view_radius: f32,
+) -> HashMap<Username, PlayerState> {
+    players
+        .filter(|(username, _)| username != viewer)
+        .filter(|(_, state)| {
+            state.position.distance_squared(viewer_position) <= view_radius.powi(2)
+        })
+        .collect()
+}
+
 pub fn broadcast_player_attributes_system(
-    mut server: ResMut<RenetServer>,
+    mut server: networking_systems::GameServer,
     usernames: Res<ClientUsernames>,
     player_states: Res<player_resources::PlayerStates>,
-    active_connections: Res<ActiveConnections>,
+    config: Res<player_resources::PlayerSyncConfig>,
+    mut sync_state: ResMut<player_resources::PlayerSyncState>,
+    tick: Res<tick_resources::ServerTick>,
+    time: Res<Time>,
 ) {
-    for client_id in server.clients_id() {
-        let other_player_states: HashMap<Username, PlayerState> = player_states
+    if !sync_state.tick(time.delta(), config.interval()) {
+        return;
+    }
+
+    let accepted_clients: HashSet<ClientId> =
+        server.connections().accepted_clients().copied().collect();
+    let connected_players = || {
+        player_states
             .players
             .iter()
             .filter(|(username, _)| {
                 usernames
                     .get_client_id(username)
-                    .is_some_and(|client_id| active_connections.is_accepted(client_id))
-            })
-            .filter(|(username, _)| {
-                usernames
-                    .username_for_client_id(&client_id)
-                    .is_none_or(|own_username| own_username != *username)
+                    .is_some_and(|client_id| accepted_clients.contains(client_id))
             })
-            .map(|(u, s)| (*u, *s))
-            .collect();
+            .map(|(username, state)| (*username, *state))
+    };
+
+    for client_id in server.clients_id() {
+        if !accepted_clients.contains(&client_id) {
+            continue;
+        }
+        let Some(username) = usernames.username_for_client_id(&client_id) else {
+            continue;
+        };
+        let Some(viewer_state) = player_states.players.get(username) else {
+            continue;
+        };
 
-        server.send_message(
+        let players = players_in_view(
+            connected_players(),
+            username,
+            viewer_state.position,
+            config.view_radius,
+        );
+
+        server.send(
             client_id,
-            DefaultChannel::ReliableUnordered,
-            bincode::serialize(&NetworkingMessage::PlayerSync(other_player_states)).unwrap(),
+            DefaultChannel::Unreliable,
+            &NetworkingMessage::PlayerSync {
+                tick: tick.0,
+                server_time: time.elapsed_secs_f64(),
+                players,
+            },
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn state(position: Vec3) -> PlayerState {
+        PlayerState {
+            position,
+            rotation: Quat::IDENTITY,
+        }
+    }
+
+    #[test]
+    fn test_players_in_view() {
+        let viewer = Username::from("Steve");
+        let players = [
+            (viewer, state(Vec3::ZERO)),
+            (Username::from("Alex"), state(Vec3::new(10.0, 0.0, 0.0))),
+            (
+                Username::from("Herobrine"),
+                state(Vec3::new(0.0, 0.0, 500.0)),
+            ),
+        ];
+
+        let visible = players_in_view(players.into_iter(), &viewer, Vec3::ZERO, 100.0);
+
+        assert_eq!(visible.len(), 1);
+        assert!(visible.contains_key(&Username::from("Alex")));
+    }
+
+    #[test]
+    fn test_player_sync_tick_rate() {
+        let config = player_resources::PlayerSyncConfig {
+            tick_rate: 10.0,
+            view_radius: 100.0,
+        };
+        let mut sync_state = player_resources::PlayerSyncState::default();
+        let frame = Duration::from_millis(40);
+
+        assert!(!sync_state.tick(frame, config.interval()));
+        assert!(!sync_state.tick(frame, config.interval()));
+        assert!(sync_state.tick(frame, config.interval()));
+        assert!(!sync_state.tick(frame, config.interval()));
+        assert!(sync_state.tick(frame * 3, config.interval()));
+    }
+
+    #[test]
+    fn test_player_sync_rate_does_not_drift() {
+        let mut sync_state = player_resources::PlayerSyncState::default();
+        let interval = Duration::from_millis(50);
+        let frame = Duration::from_millis(30);
+
+        let syncs = (0..100)
+            .filter(|_| sync_state.tick(frame, interval))
+            .count();
+        assert_eq!(syncs, 60);
+
+        // A stall does not cause a burst of syncs.
+        assert!(sync_state.tick(interval * 10, interval));
+        assert!(!sync_state.tick(frame, interval));
+    }
+}
@@ -4,7 +4,9 @@ pub use std::net::UdpSocket;
 pub use std::time::SystemTime;
 
 // bevy crates
-pub use bevy::app::{App, Last, Plugin, PreUpdate, Startup, Update};
+pub use bevy::app::{
+    App, FixedFirst, FixedLast, FixedUpdate, Last, Plugin, PostStartup, PreUpdate, Startup, Update,
+};
 pub use bevy::ecs::event::*;
 pub use bevy::ecs::message::Message;
 pub use bevy::ecs::message::*;
@@ -30,11 +32,14 @@ pub use rayon::iter::IntoParallelRefMutIterator;
 pub use rayon::iter::ParallelIterator;
 
 pub use lib::*;
+// Shadows `renet::DisconnectReason` from the glob import above.
+pub use lib::DisconnectReason;
 pub use noise::NoiseFn;
 pub use noise::Perlin;
 pub use rsmc as lib;
 
 // my crates
+pub use crate::networking::events as networking_events;
 pub use crate::networking::resources as networking_resources;
 pub use crate::networking::systems as networking_systems;
 
@@ -43,10 +48,37 @@ pub use crate::player::systems as player_systems;
 
 pub use crate::terrain::commands as terrain_commands;
 pub use crate::terrain::events as terrain_events;
+pub use crate::terrain::persistence as terrain_persistence;
 pub use crate::terrain::resources as terrain_resources;
 pub use crate::terrain::systems as terrain_systems;
 pub use crate::terrain::util as terrain_util;
 
+pub use crate::chat::commands as chat_commands;
 pub use crate::chat::events as chat_events;
 pub use crate::chat::resources as chat_resources;
 pub use crate::chat::systems as chat_systems;
+
+pub use crate::console::commands as console_commands;
+pub use crate::console::events as console_events;
+pub use crate::console::resources as console_resources;
+pub use crate::console::systems as console_systems;
+
+pub use crate::access::commands as access_commands;
+pub use crate::access::resources as access_resources;
+pub use crate::access::systems as access_systems;
+
+pub use crate::accounts::commands as accounts_commands;
+pub use crate::accounts::resources as accounts_resources;
+pub use crate::accounts::systems as accounts_systems;
+
+pub use crate::metrics::resources as metrics_resources;
+pub use crate::metrics::systems as metrics_systems;
+
+pub use crate::recording::resources as recording_resources;
+pub use crate::recording::systems as recording_systems;
+
+pub use crate::status::resources as status_resources;
+pub use crate::status::systems as status_systems;
+
+pub use crate::tick::resources as tick_resources;
+pub use crate::tick::systems as tick_systems;
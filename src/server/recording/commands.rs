@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::{prelude::*, recording::replay};
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    #[arg(
+        required = true,
+        help = "Recording written by a server started with --record"
+    )]
+    recording: PathBuf,
+}
+
+/// Replays a recorded session in a headless server and reports where it diverges.
+pub fn replay_session(args: ReplayArgs) -> Result<(), String> {
+    let recording = recording_resources::SessionRecording::read(&args.recording)?;
+    println!(
+        "Replaying {} events over {} ticks from '{}'...",
+        recording.events.len(),
+        recording.last_tick(),
+        args.recording.display()
+    );
+
+    // Removed again when the replay ends, replays running side by side get their own.
+    let directory = TempDir::create("replay")
+        .map_err(|err| format!("Failed to create replay directory: {err}"))?;
+    let report = replay::run(&recording, directory.path())?;
+
+    println!("{report}");
+    if report.diverged() {
+        println!("The replay diverged from the recording");
+    } else {
+        println!("The replay matches the recording");
+    }
+    Ok(())
+}
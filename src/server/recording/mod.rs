@@ -0,0 +1,51 @@
+use std::{fs::File, path::Path};
+
+use crate::prelude::*;
+
+pub mod commands;
+pub mod replay;
+pub mod resources;
+pub mod systems;
+
+/// Records every message the server receives from and sends to players, with
+/// checkpoints of the world state, so that `server replay` can reproduce the session.
+pub struct RecordingPlugin {
+    file: File,
+}
+
+impl RecordingPlugin {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|err| format!("Failed to create recording '{}': {err}", path.display()))?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building RecordingPlugin");
+
+        app.insert_resource(recording_resources::SessionRecorder::new(
+            self.file
+                .try_clone()
+                .expect("Recording file should be clonable"),
+        ));
+        app.add_systems(PostStartup, recording_systems::start_recording_system);
+        app.add_systems(
+            FixedFirst,
+            recording_systems::update_recording_tick_system.after(tick_systems::begin_tick_system),
+        );
+        app.add_systems(FixedLast, recording_systems::record_checkpoint_system);
+        app.add_systems(
+            Update,
+            recording_systems::record_console_commands_system
+                .after(console_systems::read_console_input_system),
+        );
+        app.add_systems(
+            Last,
+            recording_systems::finish_recording_on_exit_system
+                .after(networking_systems::disconnect_all_clients_on_exit_system),
+        );
+    }
+}
@@ -0,0 +1,529 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Display,
+    fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::time::{Fixed, Time, TimeUpdateStrategy};
+
+use crate::{
+    access::AccessPlugin,
+    accounts::AccountsPlugin,
+    networking::NetworkingPlugin,
+    player::PlayerPlugin,
+    prelude::*,
+    recording::RecordingPlugin,
+    terrain::{persistence::WorldSave, TerrainPlugin},
+    tick::TickPlugin,
+};
+use recording_resources::{
+    hash_world, OutboundTarget, RecordedEvent, SessionEvent, SessionRecorder, SessionRecording,
+    WorldChecksum,
+};
+
+const REPLAY_RECORDING_FILE: &str = "replay.rsmcr";
+/// Replays run much faster than wall clock time, so autosaves never come up.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The first checkpoint at which the replay no longer matched the recording.
+#[derive(Debug, PartialEq)]
+pub struct Divergence {
+    pub tick: u64,
+    pub differences: Vec<&'static str>,
+}
+
+#[derive(Debug)]
+pub struct ReplayReport {
+    pub ticks: u64,
+    pub inbound_messages: usize,
+    pub checkpoints: usize,
+    pub divergence: Option<Divergence>,
+    /// Message kinds sent a different number of times, with the recorded and replayed count.
+    pub outbound_differences: Vec<(&'static str, usize, usize)>,
+    /// `None` if the recorded server did not shut down cleanly.
+    pub final_differences: Option<Vec<&'static str>>,
+}
+
+impl ReplayReport {
+    pub fn diverged(&self) -> bool {
+        self.divergence.is_some()
+            || !self.outbound_differences.is_empty()
+            || self
+                .final_differences
+                .as_ref()
+                .is_some_and(|differences| !differences.is_empty())
+    }
+}
+
+impl Display for ReplayReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Replayed {} ticks with {} inbound messages",
+            self.ticks, self.inbound_messages
+        )?;
+
+        match &self.divergence {
+            None => writeln!(f, "Checkpoints: all {} match", self.checkpoints)?,
+            Some(divergence) => writeln!(
+                f,
+                "Checkpoints: diverged at tick {} ({})",
+                divergence.tick,
+                divergence.differences.join(", ")
+            )?,
+        }
+
+        if self.outbound_differences.is_empty() {
+            writeln!(f, "Outbound messages: all match")?;
+        } else {
+            writeln!(f, "Outbound messages:")?;
+            for (kind, recorded, replayed) in &self.outbound_differences {
+                writeln!(f, "  {kind}: {recorded} recorded, {replayed} replayed")?;
+            }
+        }
+
+        match &self.final_differences {
+            None => write!(
+                f,
+                "Final world state: not recorded, the server did not shut down cleanly"
+            ),
+            Some(differences) if differences.is_empty() => {
+                write!(f, "Final world state: reproduced")
+            }
+            Some(differences) => {
+                write!(f, "Final world state: differs ({})", differences.join(", "))
+            }
+        }
+    }
+}
+
+/// Feeds the events of a recording into a headless server through local renet
+/// clients, one tick per update, and compares the outcome with the recording.
+/// `directory` holds the replay server's empty access lists and accounts and
+/// its own recording of the session.
+pub fn run(recording: &SessionRecording, directory: &Path) -> Result<ReplayReport, String> {
+    let mut replay = Replay::start(recording, directory)?;
+    let inbound_messages = replay.feed(&recording.events);
+    let (replayed, checksum, world_hash) = replay.finish()?;
+
+    Ok(compare(
+        &recording.events,
+        &replayed.events,
+        inbound_messages,
+        checksum,
+        world_hash,
+    ))
+}
+
+struct Replay {
+    server: App,
+    clients: HashMap<ClientId, RenetClient>,
+    tick_interval: Duration,
+    output: PathBuf,
+}
+
+impl Replay {
+    fn start(recording: &SessionRecording, directory: &Path) -> Result<Self, String> {
+        let header = &recording.header;
+        fs::create_dir_all(directory).map_err(|err| {
+            format!(
+                "Failed to create replay directory '{}': {err}",
+                directory.display()
+            )
+        })?;
+        let output = directory.join(REPLAY_RECORDING_FILE);
+
+        let mut server = App::new();
+        server.add_plugins(MinimalPlugins);
+        server.insert_resource(TimeUpdateStrategy::ManualDuration(header.tick_interval));
+        server.add_message::<console_events::ConsoleCommandEvent>();
+
+        server.add_plugins(TickPlugin::new(1.0 / header.tick_interval.as_secs_f64())?);
+        // Exactly the recorded interval, so that every update runs one tick.
+        server.insert_resource(Time::<Fixed>::from_duration(header.tick_interval));
+        server.add_plugins(
+            TerrainPlugin::from_world_save(WorldSave {
+                name: format!("{}_replay", header.world.name),
+                generator: header.world.generator.clone(),
+                chunks: header.world.chunks.clone(),
+            })
+            .with_autosave_intervals(AUTOSAVE_INTERVAL, AUTOSAVE_INTERVAL)
+            .with_chunk_streaming(header.chunk_streaming),
+        );
+        server.add_plugins(PlayerPlugin::new(
+            header.spawn_point,
+            header.player_sync.clone(),
+        ));
+        server.add_plugins(NetworkingPlugin::new(
+            (Ipv4Addr::LOCALHOST, 0).into(),
+            MAX_PLAYERS,
+            None,
+        ));
+        #[cfg(feature = "chat")]
+        server.add_plugins(crate::chat::ChatPlugin);
+        // Recorded players were already let in, logins are not part of the recording.
+        server.add_plugins(AccessPlugin::load_from(directory)?);
+        server.add_plugins(AccountsPlugin::load_from(directory, false)?);
+        server.add_plugins(RecordingPlugin::create(&output)?);
+        // Recorded console commands are fed in as messages, stdin is not read.
+        server.add_systems(Update, console_systems::handle_console_commands_system);
+
+        let mut permissions = server
+            .world_mut()
+            .resource_mut::<player_resources::PlayerPermissions>();
+        for username in &header.operators {
+            permissions.set(*username, player_resources::PermissionLevel::Operator);
+        }
+
+        server.finish();
+        server.cleanup();
+        // The first update only runs the startup systems, time starts moving after it.
+        server.update();
+
+        Ok(Self {
+            server,
+            clients: HashMap::new(),
+            tick_interval: header.tick_interval,
+            output,
+        })
+    }
+
+    /// Runs the server up to the tick of the last event, returns the number of
+    /// messages fed to it.
+    fn feed(&mut self, events: &[RecordedEvent]) -> usize {
+        let last_tick = events.last().map_or(0, |event| event.tick);
+        let mut events = events.iter().peekable();
+        let mut inbound_messages = 0;
+
+        loop {
+            let next_tick = self.tick() + 1;
+            if next_tick > last_tick {
+                break;
+            }
+
+            while let Some(event) = events.next_if(|event| event.tick <= next_tick) {
+                if matches!(event.event, SessionEvent::Inbound { .. }) {
+                    inbound_messages += 1;
+                }
+                self.apply(&event.event);
+            }
+            self.exchange_packets();
+            self.server.update();
+        }
+
+        inbound_messages
+    }
+
+    fn tick(&self) -> u64 {
+        self.server
+            .world()
+            .resource::<tick_resources::ServerTick>()
+            .0
+    }
+
+    fn apply(&mut self, event: &SessionEvent) {
+        let world = self.server.world_mut();
+
+        match event {
+            SessionEvent::Connected {
+                client_id,
+                username,
+            } => {
+                world
+                    .resource_mut::<ClientUsernames>()
+                    .insert(*client_id, *username);
+                let client = world
+                    .resource_mut::<RenetServer>()
+                    .new_local_client(*client_id);
+                self.clients.insert(*client_id, client);
+            }
+            SessionEvent::Disconnected { client_id } => {
+                if let Some(mut client) = self.clients.remove(client_id) {
+                    world
+                        .resource_mut::<RenetServer>()
+                        .disconnect_local_client(*client_id, &mut client);
+                }
+            }
+            SessionEvent::Inbound {
+                client_id,
+                channel,
+                message,
+            } => {
+                // Clients the replayed server dropped earlier than the recorded one
+                // show up as a divergence at the next checkpoint.
+                if let Some(client) = self.clients.get_mut(client_id) {
+                    client.send_message(
+                        *channel,
+                        bincode::serialize(message).expect("Message should always be sendable"),
+                    );
+                }
+            }
+            SessionEvent::Console(command) => {
+                world.write_message(console_events::ConsoleCommandEvent(command.clone()));
+            }
+            SessionEvent::Outbound { .. }
+            | SessionEvent::Checkpoint(_)
+            | SessionEvent::Finished { .. } => {}
+        }
+    }
+
+    /// Moves packets between the server and its local clients. Whatever the server
+    /// sent is dropped, it is compared through the replay's own recording instead.
+    fn exchange_packets(&mut self) {
+        let mut server = self.server.world_mut().resource_mut::<RenetServer>();
+        let tick_interval = self.tick_interval;
+
+        self.clients.retain(|client_id, client| {
+            client.update(tick_interval);
+            if server.process_local_client(*client_id, client).is_err() {
+                return false;
+            }
+
+            for channel in [
+                DefaultChannel::ReliableOrdered,
+                DefaultChannel::ReliableUnordered,
+                DefaultChannel::Unreliable,
+            ] {
+                while client.receive_message(channel).is_some() {}
+            }
+            true
+        });
+    }
+
+    fn finish(mut self) -> Result<(SessionRecording, WorldChecksum, u64), String> {
+        let world = self.server.world_mut();
+        let checksum = WorldChecksum::compute(
+            world.resource::<ChunkManager>(),
+            world.resource::<terrain_resources::PastBlockUpdates>(),
+            world.resource::<player_resources::PlayerStates>(),
+        );
+        let world_hash = hash_world(world.resource::<ChunkManager>());
+        world.resource_mut::<SessionRecorder>().flush();
+
+        Ok((SessionRecording::read(&self.output)?, checksum, world_hash))
+    }
+}
+
+fn compare(
+    recorded: &[RecordedEvent],
+    replayed: &[RecordedEvent],
+    inbound_messages: usize,
+    checksum: WorldChecksum,
+    world_hash: u64,
+) -> ReplayReport {
+    let replayed_checkpoints: HashMap<u64, WorldChecksum> = replayed
+        .iter()
+        .filter_map(|event| match event.event {
+            SessionEvent::Checkpoint(checksum) => Some((event.tick, checksum)),
+            _ => None,
+        })
+        .collect();
+
+    let mut checkpoints = 0;
+    let mut divergence = None;
+    for event in recorded {
+        let SessionEvent::Checkpoint(recorded_checksum) = event.event else {
+            continue;
+        };
+        let Some(replayed_checksum) = replayed_checkpoints.get(&event.tick) else {
+            continue;
+        };
+
+        checkpoints += 1;
+        let differences = recorded_checksum.differences(replayed_checksum);
+        if !differences.is_empty() {
+            divergence = Some(Divergence {
+                tick: event.tick,
+                differences,
+            });
+            break;
+        }
+    }
+
+    let recorded_counts = count_outbound(recorded);
+    let replayed_counts = count_outbound(replayed);
+    let kinds: BTreeSet<&'static str> = recorded_counts
+        .keys()
+        .chain(replayed_counts.keys())
+        .copied()
+        .collect();
+    let outbound_differences = kinds
+        .into_iter()
+        .filter_map(|kind| {
+            let recorded = recorded_counts.get(kind).copied().unwrap_or_default();
+            let replayed = replayed_counts.get(kind).copied().unwrap_or_default();
+            (recorded != replayed).then_some((kind, recorded, replayed))
+        })
+        .collect();
+
+    let final_differences = recorded.iter().find_map(|event| match event.event {
+        SessionEvent::Finished {
+            checksum: recorded_checksum,
+            world_hash: recorded_world_hash,
+        } => {
+            let mut differences = recorded_checksum.differences(&checksum);
+            if recorded_world_hash != world_hash {
+                differences.push("world");
+            }
+            Some(differences)
+        }
+        _ => None,
+    });
+
+    ReplayReport {
+        ticks: recorded.last().map_or(0, |event| event.tick),
+        inbound_messages,
+        checkpoints,
+        divergence,
+        outbound_differences,
+        final_differences,
+    }
+}
+
+/// Counts the messages sent to accepted players by kind. Messages to clients that
+/// were still logging in or got turned away have no counterpart in a replay.
+fn count_outbound(events: &[RecordedEvent]) -> BTreeMap<&'static str, usize> {
+    let mut players = HashSet::new();
+    let mut counts = BTreeMap::new();
+
+    for event in events {
+        match &event.event {
+            SessionEvent::Connected { client_id, .. } => {
+                players.insert(*client_id);
+            }
+            SessionEvent::Disconnected { client_id } => {
+                players.remove(client_id);
+            }
+            SessionEvent::Outbound { target, message } => {
+                if matches!(target, OutboundTarget::Client(client_id) if !players.contains(client_id))
+                {
+                    continue;
+                }
+                // Replays never shut down, so the shutdown notice is left out.
+                if matches!(
+                    message,
+                    NetworkingMessage::Kick {
+                        reason: DisconnectReason::ServerShutdown
+                    }
+                ) {
+                    continue;
+                }
+                *counts.entry(message.kind()).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(tick: u64, event: SessionEvent) -> RecordedEvent {
+        RecordedEvent {
+            tick,
+            elapsed: Duration::ZERO,
+            event,
+        }
+    }
+
+    fn checksum(block_updates: u64) -> WorldChecksum {
+        WorldChecksum {
+            block_updates,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare_finds_first_divergent_checkpoint() {
+        let recorded = vec![
+            event(30, SessionEvent::Checkpoint(checksum(0))),
+            event(60, SessionEvent::Checkpoint(checksum(1))),
+            event(90, SessionEvent::Checkpoint(checksum(2))),
+        ];
+        let replayed = vec![
+            event(30, SessionEvent::Checkpoint(checksum(0))),
+            event(60, SessionEvent::Checkpoint(checksum(0))),
+            event(90, SessionEvent::Checkpoint(checksum(0))),
+        ];
+
+        let report = compare(&recorded, &replayed, 0, checksum(0), 0);
+        assert_eq!(
+            report.divergence,
+            Some(Divergence {
+                tick: 60,
+                differences: vec!["block updates"],
+            })
+        );
+        assert_eq!(report.checkpoints, 2);
+        assert_eq!(report.final_differences, None);
+        assert!(report.diverged());
+    }
+
+    #[test]
+    fn test_compare_counts_outbound_messages_to_players() {
+        let accept = || NetworkingMessage::PlayerAccept(PlayerState::default());
+        let recorded = vec![
+            // Sent while logging in, a replay never sees this.
+            event(
+                1,
+                SessionEvent::Outbound {
+                    target: OutboundTarget::Client(1),
+                    message: NetworkingMessage::LoginRequired,
+                },
+            ),
+            event(
+                2,
+                SessionEvent::Connected {
+                    client_id: 1,
+                    username: Username::from("Steve"),
+                },
+            ),
+            event(
+                2,
+                SessionEvent::Outbound {
+                    target: OutboundTarget::Client(1),
+                    message: accept(),
+                },
+            ),
+            event(
+                3,
+                SessionEvent::Outbound {
+                    target: OutboundTarget::All,
+                    message: NetworkingMessage::Kick {
+                        reason: DisconnectReason::ServerShutdown,
+                    },
+                },
+            ),
+            event(
+                3,
+                SessionEvent::Finished {
+                    checksum: checksum(0),
+                    world_hash: 7,
+                },
+            ),
+        ];
+        let mut replayed = recorded[1..3].to_vec();
+
+        let report = compare(&recorded, &replayed, 0, checksum(0), 7);
+        assert!(report.outbound_differences.is_empty());
+        assert_eq!(report.final_differences, Some(Vec::new()));
+        assert!(!report.diverged());
+
+        replayed.push(event(
+            3,
+            SessionEvent::Outbound {
+                target: OutboundTarget::AllExcept(1),
+                message: NetworkingMessage::PlayerJoin(Username::from("Alex")),
+            },
+        ));
+        let report = compare(&recorded, &replayed, 0, checksum(0), 8);
+        assert_eq!(report.outbound_differences, vec![("PlayerJoin", 0, 1)]);
+        assert_eq!(report.final_differences, Some(vec!["world"]));
+    }
+}
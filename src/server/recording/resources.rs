@@ -0,0 +1,335 @@
+use std::{
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{prelude::*, terrain::persistence::WorldSave};
+
+pub const RECORDING_FORMAT_VERSION: u32 = 3;
+/// Ticks between two checkpoints, about once per second at the default tick rate.
+pub const CHECKPOINT_INTERVAL_TICKS: u64 = 30;
+
+/// Written once at the start of a recording, with everything a replay needs to
+/// rebuild the server as it was before the first tick.
+#[derive(Serialize, Deserialize)]
+pub struct SessionHeader {
+    pub version: u32,
+    pub tick_interval: Duration,
+    pub spawn_point: IVec3,
+    pub player_sync: player_resources::PlayerSyncConfig,
+    pub chunk_streaming: terrain_resources::ChunkStreamingConfig,
+    pub operators: Vec<Username>,
+    pub world: WorldSave,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboundTarget {
+    Client(ClientId),
+    All,
+    AllExcept(ClientId),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum SessionEvent {
+    /// The server accepted a player, after any login.
+    Connected {
+        client_id: ClientId,
+        username: Username,
+    },
+    /// An accepted player disconnected.
+    Disconnected {
+        client_id: ClientId,
+    },
+    /// A message from an accepted player that decoded, with the channel it came in on.
+    Inbound {
+        client_id: ClientId,
+        channel: u8,
+        message: NetworkingMessage,
+    },
+    Outbound {
+        target: OutboundTarget,
+        message: NetworkingMessage,
+    },
+    /// A console command that changes what players see, applied after the tick.
+    Console(console_commands::ConsoleCommand),
+    Checkpoint(WorldChecksum),
+    /// Written when the server shuts down, with a hash over every chunk.
+    Finished {
+        checksum: WorldChecksum,
+        world_hash: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedEvent {
+    pub tick: u64,
+    /// Wall clock time since the recording started.
+    pub elapsed: Duration,
+    pub event: SessionEvent,
+}
+
+/// Cheap summary of the state players change, compared between a recording and
+/// its replay.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorldChecksum {
+    pub chunks: u64,
+    pub block_updates: u64,
+    pub blocks: u64,
+    pub players: u64,
+}
+
+impl WorldChecksum {
+    pub fn compute(
+        chunk_manager: &ChunkManager,
+        past_block_updates: &terrain_resources::PastBlockUpdates,
+        player_states: &player_resources::PlayerStates,
+    ) -> Self {
+        let mut blocks = DefaultHasher::new();
+        for update in &past_block_updates.updates {
+            update.position.hash(&mut blocks);
+            update.block.hash(&mut blocks);
+        }
+
+        // Map order differs between runs, so players are hashed sorted by name.
+        let mut players: Vec<_> = player_states.players.iter().collect();
+        players.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        let mut players_hasher = DefaultHasher::new();
+        for (username, state) in players {
+            username.hash(&mut players_hasher);
+            for value in state
+                .position
+                .to_array()
+                .into_iter()
+                .chain(state.rotation.to_array())
+            {
+                value.to_bits().hash(&mut players_hasher);
+            }
+        }
+
+        Self {
+            chunks: chunk_manager.chunks.len() as u64,
+            block_updates: past_block_updates.updates.len() as u64,
+            blocks: blocks.finish(),
+            players: players_hasher.finish(),
+        }
+    }
+
+    /// Names of the parts that differ from `other`.
+    pub fn differences(&self, other: &Self) -> Vec<&'static str> {
+        [
+            ("chunks", self.chunks == other.chunks),
+            ("block updates", self.block_updates == other.block_updates),
+            ("blocks", self.blocks == other.blocks),
+            ("players", self.players == other.players),
+        ]
+        .into_iter()
+        .filter(|(_, equal)| !equal)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// Hashes every block of every chunk, in a fixed chunk order.
+pub fn hash_world(chunk_manager: &ChunkManager) -> u64 {
+    let mut chunks = chunk_manager.all_chunks();
+    chunks.sort_by_key(|chunk| chunk.position.to_array());
+
+    let mut hasher = DefaultHasher::new();
+    for chunk in chunks {
+        chunk.position.hash(&mut hasher);
+        chunk.data.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Appends the events of a session to a file. Write errors end the recording
+/// instead of the server.
+#[derive(Resource)]
+pub struct SessionRecorder {
+    writer: Option<BufWriter<File>>,
+    started_at: Instant,
+    tick: u64,
+}
+
+impl SessionRecorder {
+    pub fn new(file: File) -> Self {
+        Self {
+            writer: Some(BufWriter::new(file)),
+            started_at: Instant::now(),
+            tick: 0,
+        }
+    }
+
+    pub fn write_header(&mut self, header: &SessionHeader) {
+        self.started_at = Instant::now();
+        self.write(header);
+    }
+
+    pub fn set_tick(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn record(&mut self, event: SessionEvent) {
+        let event = RecordedEvent {
+            tick: self.tick,
+            elapsed: self.started_at.elapsed(),
+            event,
+        };
+        self.write(&event);
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(err) = writer.flush() {
+                self.stop(err.to_string());
+            }
+        }
+    }
+
+    fn write(&mut self, value: &impl Serialize) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(err) = bincode::serialize_into(writer, value) {
+                self.stop(err.to_string());
+            }
+        }
+    }
+
+    fn stop(&mut self, error: String) {
+        eprintln!("Stopped recording the session: {error}");
+        self.writer = None;
+    }
+}
+
+/// A recording read back into memory.
+pub struct SessionRecording {
+    pub header: SessionHeader,
+    pub events: Vec<RecordedEvent>,
+}
+
+impl SessionRecording {
+    /// Reads a recording, tolerating a last event that was cut off by a crash.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|err| format!("Failed to open recording '{}': {err}", path.display()))?;
+        let mut reader = BufReader::new(file);
+
+        let header: SessionHeader = bincode::deserialize_from(&mut reader)
+            .map_err(|err| format!("Failed to read recording header: {err}"))?;
+        if header.version != RECORDING_FORMAT_VERSION {
+            return Err(format!(
+                "Recording has format version {}, expected {RECORDING_FORMAT_VERSION}",
+                header.version
+            ));
+        }
+
+        let mut events = Vec::new();
+        loop {
+            match bincode::deserialize_from(&mut reader) {
+                Ok(event) => events.push(event),
+                Err(err) => match *err {
+                    bincode::ErrorKind::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                        break
+                    }
+                    err => return Err(format!("Failed to read recorded event: {err}")),
+                },
+            }
+        }
+
+        Ok(Self { header, events })
+    }
+
+    pub fn last_tick(&self) -> u64 {
+        self.events.last().map_or(0, |event| event.tick)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_recording_round_trip() {
+        let directory = TempDir::create("recording_round_trip").unwrap();
+        let path = directory.join("session.rsmcr");
+        let mut recorder = SessionRecorder::new(File::create(&path).unwrap());
+        recorder.write_header(&SessionHeader {
+            version: RECORDING_FORMAT_VERSION,
+            tick_interval: Duration::from_millis(50),
+            spawn_point: DEFAULT_SPAWN_POINT,
+            player_sync: player_resources::PlayerSyncConfig::default(),
+            chunk_streaming: terrain_resources::ChunkStreamingConfig::default(),
+            operators: vec![Username::from("Steve")],
+            world: WorldSave::default(),
+        });
+        recorder.set_tick(3);
+        recorder.record(SessionEvent::Connected {
+            client_id: 7,
+            username: Username::from("Steve"),
+        });
+        recorder.set_tick(5);
+        recorder.record(SessionEvent::Inbound {
+            client_id: 7,
+            channel: DefaultChannel::ReliableOrdered.into(),
+            message: NetworkingMessage::ChatMessageSend(String::from("hi")),
+        });
+        recorder.flush();
+
+        // A crash can leave half an event at the end.
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.extend_from_slice(&[1, 0, 0]);
+        fs::write(&path, bytes).unwrap();
+
+        let recording = SessionRecording::read(&path).unwrap();
+        assert_eq!(recording.header.operators, vec![Username::from("Steve")]);
+        assert_eq!(recording.events.len(), 2);
+        assert_eq!(recording.last_tick(), 5);
+        assert!(matches!(
+            &recording.events[1].event,
+            SessionEvent::Inbound {
+                client_id: 7,
+                message: NetworkingMessage::ChatMessageSend(message),
+                ..
+            } if message == "hi"
+        ));
+    }
+
+    #[test]
+    fn test_world_checksum_differences() {
+        let chunk_manager = ChunkManager::new();
+        let mut past_block_updates = terrain_resources::PastBlockUpdates::default();
+        let mut player_states = player_resources::PlayerStates::new();
+        player_states
+            .players
+            .insert(Username::from("Steve"), PlayerState::default());
+        let before = WorldChecksum::compute(&chunk_manager, &past_block_updates, &player_states);
+
+        past_block_updates
+            .updates
+            .push(terrain_events::BlockUpdateEvent {
+                position: IVec3::new(1, 2, 3),
+                block: BlockId::Stone,
+            });
+        let after = WorldChecksum::compute(&chunk_manager, &past_block_updates, &player_states);
+
+        assert_eq!(
+            before,
+            WorldChecksum::compute(
+                &chunk_manager,
+                &terrain_resources::PastBlockUpdates::default(),
+                &player_states
+            )
+        );
+        assert_eq!(before.differences(&after), vec!["block updates", "blocks"]);
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::{
+    app::AppExit,
+    time::{Fixed, Time},
+};
+
+use crate::{prelude::*, terrain::persistence::build_world_save_from_resources};
+use console_commands::ConsoleCommand;
+
+use recording_resources::{
+    hash_world, SessionEvent, SessionHeader, SessionRecorder, WorldChecksum,
+    CHECKPOINT_INTERVAL_TICKS, RECORDING_FORMAT_VERSION,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_recording_system(
+    mut recorder: ResMut<SessionRecorder>,
+    chunk_manager: Res<ChunkManager>,
+    generator: Res<terrain_resources::Generator>,
+    world_name: Res<terrain_resources::AutoSaveName>,
+    spawn_point: Res<player_resources::SpawnPoint>,
+    sync_config: Res<player_resources::PlayerSyncConfig>,
+    chunk_streaming: Res<terrain_resources::ChunkStreamingConfig>,
+    permissions: Res<player_resources::PlayerPermissions>,
+    time: Res<Time<Fixed>>,
+) {
+    recorder.write_header(&SessionHeader {
+        version: RECORDING_FORMAT_VERSION,
+        tick_interval: time.timestep(),
+        spawn_point: spawn_point.0,
+        player_sync: sync_config.clone(),
+        chunk_streaming: *chunk_streaming,
+        operators: permissions.operators().copied().collect(),
+        world: build_world_save_from_resources(&world_name.0, &chunk_manager, &generator),
+    });
+    recorder.flush();
+}
+
+pub fn update_recording_tick_system(
+    mut recorder: ResMut<SessionRecorder>,
+    tick: Res<tick_resources::ServerTick>,
+) {
+    recorder.set_tick(tick.0);
+}
+
+/// Records the console commands a replay has to run as well. Saves, backups and
+/// shutdowns only touch files or end the session, account commands carry passwords
+/// and the rest only print.
+pub fn record_console_commands_system(
+    mut recorder: ResMut<SessionRecorder>,
+    mut command_events: MessageReader<console_events::ConsoleCommandEvent>,
+) {
+    for event in command_events.read() {
+        if matches!(
+            event.0,
+            ConsoleCommand::Say(_)
+                | ConsoleCommand::Kick { .. }
+                | ConsoleCommand::Teleport { .. }
+                | ConsoleCommand::Access(_)
+        ) {
+            recorder.record(SessionEvent::Console(event.0.clone()));
+        }
+    }
+}
+
+pub fn record_checkpoint_system(
+    mut recorder: ResMut<SessionRecorder>,
+    chunk_manager: Res<ChunkManager>,
+    past_block_updates: Res<terrain_resources::PastBlockUpdates>,
+    player_states: Res<player_resources::PlayerStates>,
+) {
+    if recorder.tick() % CHECKPOINT_INTERVAL_TICKS != 0 {
+        return;
+    }
+
+    recorder.record(SessionEvent::Checkpoint(WorldChecksum::compute(
+        &chunk_manager,
+        &past_block_updates,
+        &player_states,
+    )));
+    recorder.flush();
+}
+
+pub fn finish_recording_on_exit_system(
+    mut recorder: ResMut<SessionRecorder>,
+    mut exit_events: MessageReader<AppExit>,
+    chunk_manager: Res<ChunkManager>,
+    past_block_updates: Res<terrain_resources::PastBlockUpdates>,
+    player_states: Res<player_resources::PlayerStates>,
+) {
+    if exit_events.read().len() > 0 {
+        recorder.record(SessionEvent::Finished {
+            checksum: WorldChecksum::compute(&chunk_manager, &past_block_updates, &player_states),
+            world_hash: hash_world(&chunk_manager),
+        });
+        recorder.flush();
+        println!("Finished recording the session");
+    }
+}
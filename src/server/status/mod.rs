@@ -0,0 +1,89 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+use crate::prelude::*;
+
+pub mod resources;
+pub mod systems;
+
+/// Answers status queries on a separate UDP port and announces the server on the LAN,
+/// see `lib::status` and `lib::discovery`.
+pub struct StatusPlugin {
+    motd: String,
+    max_players: usize,
+    query_socket: Option<UdpSocket>,
+    lan_announcer: Option<(UdpSocket, SocketAddr, u16)>,
+}
+
+impl StatusPlugin {
+    pub fn new(motd: String, max_players: usize) -> Self {
+        Self {
+            motd,
+            max_players,
+            query_socket: None,
+            lan_announcer: None,
+        }
+    }
+
+    pub fn with_status_queries(mut self, address: SocketAddr) -> Result<Self, String> {
+        let socket = UdpSocket::bind(address)
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(|err| format!("Failed to bind status socket on {address}: {err}"))?;
+        println!("Answering status queries on {address}");
+
+        self.query_socket = Some(socket);
+        Ok(self)
+    }
+
+    /// Broadcasts the status of the server listening on `game_port` to `target`.
+    pub fn with_lan_announcements(
+        mut self,
+        target: SocketAddr,
+        game_port: u16,
+    ) -> Result<Self, String> {
+        let bind_addr: SocketAddr = if target.is_ipv6() {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(bind_addr)
+            .and_then(|socket| socket.set_broadcast(target.is_ipv4()).map(|_| socket))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(|err| format!("Failed to create LAN announcement socket: {err}"))?;
+        println!("Announcing the server on the LAN to {target}");
+
+        self.lan_announcer = Some((socket, target, game_port));
+        Ok(self)
+    }
+}
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building StatusPlugin");
+
+        app.insert_resource(resources::StatusInfo {
+            motd: self.motd.clone(),
+            max_players: self.max_players,
+        });
+
+        if let Some(socket) = &self.query_socket {
+            app.insert_resource(resources::StatusSocket(
+                socket
+                    .try_clone()
+                    .expect("Status socket should be clonable"),
+            ));
+            app.init_resource::<resources::StatusRateLimit>();
+            app.add_systems(Update, status_systems::answer_status_queries_system);
+        }
+
+        if let Some((socket, target, game_port)) = &self.lan_announcer {
+            app.insert_resource(resources::LanAnnouncer::new(
+                socket
+                    .try_clone()
+                    .expect("Announcement socket should be clonable"),
+                *target,
+                *game_port,
+            ));
+            app.add_systems(Update, status_systems::announce_on_lan_system);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    net::{IpAddr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use bevy::time::{Timer, TimerMode};
+
+use crate::prelude::*;
+
+pub const MAX_STATUS_RESPONSES_PER_SECOND: u32 = 20;
+/// Sources beyond this within one second are not answered, which bounds the memory
+/// spent on requests with spoofed source addresses.
+pub const MAX_STATUS_SOURCES_PER_SECOND: usize = 1024;
+
+#[derive(Resource)]
+pub struct StatusSocket(pub UdpSocket);
+
+#[derive(Resource)]
+pub struct StatusInfo {
+    pub motd: String,
+    pub max_players: usize,
+}
+
+#[derive(Resource)]
+pub struct LanAnnouncer {
+    pub socket: UdpSocket,
+    pub target: SocketAddr,
+    pub game_port: u16,
+    timer: Timer,
+}
+
+impl LanAnnouncer {
+    pub fn new(socket: UdpSocket, target: SocketAddr, game_port: u16) -> Self {
+        Self {
+            socket,
+            target,
+            game_port,
+            timer: Timer::new(LAN_ANNOUNCE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+
+    /// Returns whether the next announcement is due.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        self.timer.tick(delta).just_finished()
+    }
+}
+
+/// Caps the responses sent per second to each source address, status requests are
+/// unauthenticated.
+#[derive(Resource, Default)]
+pub struct StatusRateLimit {
+    window_start: Duration,
+    responses: HashMap<IpAddr, u32>,
+}
+
+impl StatusRateLimit {
+    pub fn allow(&mut self, source: IpAddr, now: Duration) -> bool {
+        if now.saturating_sub(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.responses.clear();
+        }
+
+        if !self.responses.contains_key(&source)
+            && self.responses.len() >= MAX_STATUS_SOURCES_PER_SECOND
+        {
+            return false;
+        }
+        let responses = self.responses.entry(source).or_insert(0);
+        if *responses >= MAX_STATUS_RESPONSES_PER_SECOND {
+            return false;
+        }
+        *responses += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_resets_every_second() {
+        let mut rate_limit = StatusRateLimit::default();
+        let source = IpAddr::from([192, 168, 0, 2]);
+        let start = Duration::from_millis(500);
+
+        for _ in 0..MAX_STATUS_RESPONSES_PER_SECOND {
+            assert!(rate_limit.allow(source, start));
+        }
+        assert!(!rate_limit.allow(source, start));
+        assert!(!rate_limit.allow(source, start + Duration::from_millis(999)));
+        assert!(rate_limit.allow(source, start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_rate_limit_is_per_source() {
+        let mut rate_limit = StatusRateLimit::default();
+        let flooder = IpAddr::from([10, 0, 0, 1]);
+
+        for _ in 0..MAX_STATUS_RESPONSES_PER_SECOND {
+            assert!(rate_limit.allow(flooder, Duration::ZERO));
+        }
+        assert!(!rate_limit.allow(flooder, Duration::ZERO));
+        assert!(rate_limit.allow(IpAddr::from([10, 0, 0, 2]), Duration::ZERO));
+
+        for index in 3..MAX_STATUS_SOURCES_PER_SECOND as u32 + 1 {
+            assert!(rate_limit.allow(IpAddr::from(index.to_be_bytes()), Duration::ZERO));
+        }
+        assert!(!rate_limit.allow(IpAddr::from([10, 0, 255, 255]), Duration::ZERO));
+        assert!(rate_limit.allow(IpAddr::from([10, 0, 0, 2]), Duration::ZERO));
+    }
+}
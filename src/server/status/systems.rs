@@ -0,0 +1,83 @@
+use std::io::ErrorKind;
+
+use bevy::time::{Real, Time};
+
+use crate::{networking::resources::ActiveConnections, prelude::*};
+use status_resources::{LanAnnouncer, StatusInfo, StatusRateLimit, StatusSocket};
+
+#[derive(SystemParam)]
+pub struct CurrentStatus<'w> {
+    status_info: Res<'w, StatusInfo>,
+    active_connections: Res<'w, ActiveConnections>,
+    client_usernames: Res<'w, ClientUsernames>,
+    save_name: Res<'w, terrain_resources::AutoSaveName>,
+    time: Res<'w, Time<Real>>,
+}
+
+impl CurrentStatus<'_> {
+    pub fn get(&self) -> ServerStatus {
+        ServerStatus {
+            protocol_version: PROTOCOL_ID,
+            motd: self.status_info.motd.clone(),
+            players: self
+                .active_connections
+                .accepted_usernames(&self.client_usernames),
+            max_players: self.status_info.max_players as u64,
+            world_name: self.save_name.0.clone(),
+            uptime_seconds: self.time.elapsed().as_secs(),
+        }
+    }
+}
+
+pub fn answer_status_queries_system(
+    status_socket: Res<StatusSocket>,
+    mut rate_limit: ResMut<StatusRateLimit>,
+    current_status: CurrentStatus,
+    time: Res<Time<Real>>,
+) {
+    let mut buffer = [0; STATUS_REQUEST_BYTES + 1];
+
+    loop {
+        let (len, address) = match status_socket.0.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(err) => {
+                warn!("Failed to receive status request: {err}");
+                break;
+            }
+        };
+
+        if !is_status_request(&buffer[..len]) || !rate_limit.allow(address.ip(), time.elapsed()) {
+            continue;
+        }
+
+        let response = current_status.get().encode();
+        if let Err(err) = status_socket.0.send_to(&response, address) {
+            warn!("Failed to answer status request from {address}: {err}");
+        }
+    }
+}
+
+pub fn announce_on_lan_system(
+    mut announcer: ResMut<LanAnnouncer>,
+    current_status: CurrentStatus,
+    time: Res<Time<Real>>,
+) {
+    if !announcer.tick(time.delta()) {
+        return;
+    }
+
+    let announcement = LanAnnouncement {
+        game_port: announcer.game_port,
+        status: current_status.get(),
+    };
+    if let Err(err) = announcer
+        .socket
+        .send_to(&announcement.encode(), announcer.target)
+    {
+        warn!(
+            "Failed to send LAN announcement to {}: {err}",
+            announcer.target
+        );
+    }
+}
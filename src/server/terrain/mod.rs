@@ -1,4 +1,9 @@
-use std::io::ErrorKind::{NotFound, PermissionDenied};
+use std::{
+    io::ErrorKind::{NotFound, PermissionDenied},
+    time::Duration,
+};
+
+use chrono::TimeDelta;
 
 use crate::{prelude::*, terrain::persistence::WorldSave};
 
@@ -8,7 +13,7 @@ pub mod resources;
 pub mod systems;
 pub mod util;
 
-mod persistence;
+pub mod persistence;
 
 pub enum TerrainStrategy {
     SeededRandom(String, u32),
@@ -17,6 +22,9 @@ pub enum TerrainStrategy {
 
 pub struct TerrainPlugin {
     strategy: TerrainStrategy,
+    save_interval: TimeDelta,
+    backup_interval: TimeDelta,
+    chunk_streaming: resources::ChunkStreamingConfig,
 }
 
 impl TerrainPlugin {
@@ -35,9 +43,12 @@ impl TerrainPlugin {
                 _ => format!("Unknown Error loading file: {}", err),
             })?;
 
-        Ok(Self {
-            strategy: TerrainStrategy::LoadFromSave(Box::new(world_save)),
-        })
+        Ok(Self::from_world_save(world_save))
+    }
+
+    /// Starts from a world that is already in memory, without reading it from disk.
+    pub fn from_world_save(world_save: WorldSave) -> Self {
+        Self::with_strategy(TerrainStrategy::LoadFromSave(Box::new(world_save)))
     }
 
     pub fn new_with_seed(world_name: String, replace: bool, seed: u32) -> Result<Self, String> {
@@ -47,9 +58,33 @@ impl TerrainPlugin {
                 world_name
             ))
         } else {
-            Ok(Self {
-                strategy: TerrainStrategy::SeededRandom(world_name, seed),
-            })
+            Ok(Self::with_strategy(TerrainStrategy::SeededRandom(
+                world_name, seed,
+            )))
+        }
+    }
+
+    pub fn with_autosave_intervals(
+        mut self,
+        save_interval: Duration,
+        backup_interval: Duration,
+    ) -> Self {
+        self.save_interval = TimeDelta::from_std(save_interval).unwrap_or(TimeDelta::MAX);
+        self.backup_interval = TimeDelta::from_std(backup_interval).unwrap_or(TimeDelta::MAX);
+        self
+    }
+
+    pub fn with_chunk_streaming(mut self, config: resources::ChunkStreamingConfig) -> Self {
+        self.chunk_streaming = config;
+        self
+    }
+
+    fn with_strategy(strategy: TerrainStrategy) -> Self {
+        Self {
+            strategy,
+            save_interval: TimeDelta::seconds(resources::DEFAULT_SAVE_INTERVAL_SECONDS as i64),
+            backup_interval: TimeDelta::seconds(resources::DEFAULT_BACKUP_INTERVAL_SECONDS as i64),
+            chunk_streaming: resources::ChunkStreamingConfig::default(),
         }
     }
 }
@@ -74,13 +109,18 @@ impl Plugin for TerrainPlugin {
 
         app.add_message::<terrain_events::BlockUpdateEvent>();
         app.insert_resource(resources::PastBlockUpdates::default());
-        app.insert_resource(resources::WorldBackupTimer::default());
-        app.insert_resource(resources::WorldSaveTimer::default());
-        app.add_systems(Update, terrain_systems::process_user_chunk_requests_system);
-        app.add_systems(Update, terrain_systems::save_world_system);
-        app.add_systems(Update, terrain_systems::backup_world_system);
+        app.init_resource::<resources::TerrainStats>();
+        app.insert_resource(resources::WorldBackupTimer::new(self.backup_interval));
+        app.insert_resource(resources::WorldSaveTimer::new(self.save_interval));
+        app.add_systems(
+            FixedUpdate,
+            terrain_systems::process_user_chunk_requests_system,
+        );
+        app.add_systems(FixedUpdate, terrain_systems::save_world_system);
+        app.add_systems(FixedUpdate, terrain_systems::backup_world_system);
         app.add_systems(Last, terrain_systems::save_world_on_shutdown_system);
         app.insert_resource(resources::ClientChunkRequests::default());
+        app.insert_resource(self.chunk_streaming);
 
         #[cfg(feature = "generator_visualizer")]
         {
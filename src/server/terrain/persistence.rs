@@ -69,7 +69,7 @@ fn create_or_update_file(world_save: &WorldSave, path: &Path) -> Result<(), Box<
     Ok(())
 }
 
-fn build_world_save_from_resources(
+pub fn build_world_save_from_resources(
     name: &str,
     chunk_manager: &ChunkManager,
     generator: &Generator,
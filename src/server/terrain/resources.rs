@@ -1,4 +1,7 @@
-use std::collections::VecDeque;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
 
 use crate::prelude::*;
 
@@ -7,31 +10,200 @@ use rand::distr::{Alphanumeric, SampleString};
 use serde::{Deserialize, Serialize};
 use terrain_events::BlockUpdateEvent;
 
+/// A chunk a client asked for, with the hash of the copy it has cached, if any.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkRequest {
+    pub position: IVec3,
+    pub cached_hash: Option<u64>,
+}
+
+impl ChunkRequest {
+    pub fn new(position: IVec3) -> Self {
+        Self {
+            position,
+            cached_hash: None,
+        }
+    }
+
+    pub fn cached(position: IVec3, hash: u64) -> Self {
+        Self {
+            position,
+            cached_hash: Some(hash),
+        }
+    }
+}
+
+pub const DEFAULT_CHUNK_BYTES_PER_SECOND: u64 = 1024 * 1024;
+
+/// How chunk batches are sent to clients.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ChunkStreamingConfig {
+    /// Deflate chunk batches when that makes them smaller.
+    pub compression: bool,
+    /// Chunk bytes sent to every client per second, `0` for no limit.
+    pub bytes_per_second: u64,
+}
+
+impl Default for ChunkStreamingConfig {
+    fn default() -> Self {
+        Self {
+            compression: true,
+            bytes_per_second: DEFAULT_CHUNK_BYTES_PER_SECOND,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ClientChunkQueue {
+    requests: VecDeque<ChunkRequest>,
+    /// Positions in `requests`, so that re-sent requests are not queued twice.
+    queued: HashSet<IVec3>,
+    /// Bytes that may still be sent to the client, `None` before the first batch.
+    allowance: Option<f64>,
+}
+
+impl ClientChunkQueue {
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Queues a request unless its position is queued already.
+    pub fn push_back(&mut self, request: ChunkRequest) {
+        if self.queued.insert(request.position) {
+            self.requests.push_back(request);
+        }
+    }
+
+    /// Puts a request back at the front, used for chunks that did not fit a batch.
+    pub fn push_front(&mut self, request: ChunkRequest) {
+        if self.queued.insert(request.position) {
+            self.requests.push_front(request);
+        }
+    }
+
+    pub fn take(&mut self, count: usize) -> Vec<ChunkRequest> {
+        let count = count.min(self.requests.len());
+        let requests: Vec<ChunkRequest> = self.requests.drain(..count).collect();
+        for request in &requests {
+            self.queued.remove(&request.position);
+        }
+        requests
+    }
+
+    /// Adds the budget for `elapsed` and returns how many bytes may be sent now.
+    /// Clients start out with a full second of budget and never save up more.
+    pub fn refill(&mut self, config: &ChunkStreamingConfig, elapsed: Duration) -> f64 {
+        if config.bytes_per_second == 0 {
+            return f64::INFINITY;
+        }
+
+        let budget = config.bytes_per_second as f64;
+        let allowance = self.allowance.map_or(budget, |allowance| {
+            (allowance + budget * elapsed.as_secs_f64()).min(budget)
+        });
+        self.allowance = Some(allowance);
+        allowance
+    }
+
+    /// Takes sent bytes off the allowance. Going below zero delays the next batch
+    /// until the budget caught up.
+    pub fn spend(&mut self, bytes: u64) {
+        if let Some(allowance) = &mut self.allowance {
+            *allowance -= bytes as f64;
+        }
+    }
+
+    /// Whether nothing is queued and the allowance is back at the full budget, so
+    /// that dropping the queue does not forgive any debt.
+    pub fn is_settled(&self, config: &ChunkStreamingConfig) -> bool {
+        self.is_empty()
+            && self
+                .allowance
+                .is_none_or(|allowance| allowance >= config.bytes_per_second as f64)
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct ClientChunkRequests {
-    queues: HashMap<ClientId, VecDeque<IVec3>>,
+    queues: HashMap<ClientId, ClientChunkQueue>,
 }
 
 impl ClientChunkRequests {
-    pub fn enqueue_bulk(&mut self, client_id: ClientId, chunk_positions: &mut VecDeque<IVec3>) {
-        self.queues
-            .entry(client_id)
-            .or_default()
-            .append(chunk_positions);
+    /// Queues requests for a client. Positions that are still queued are skipped,
+    /// clients re-send requests that took too long to be answered.
+    pub fn enqueue_bulk(
+        &mut self,
+        client_id: ClientId,
+        requests: impl IntoIterator<Item = ChunkRequest>,
+    ) {
+        let queue = self.queues.entry(client_id).or_default();
+        for request in requests {
+            queue.push_back(request);
+        }
     }
 
     pub fn remove(&mut self, client_id: &ClientId) {
         self.queues.remove(client_id);
     }
 
+    /// Drops queued requests for `positions`, returning how many were queued.
+    pub fn cancel(&mut self, client_id: &ClientId, positions: &[IVec3]) -> usize {
+        let Some(queue) = self.queues.get_mut(client_id) else {
+            return 0;
+        };
+
+        let cancelled: HashSet<IVec3> = positions
+            .iter()
+            .filter(|position| queue.queued.remove(position))
+            .copied()
+            .collect();
+        if !cancelled.is_empty() {
+            queue
+                .requests
+                .retain(|request| !cancelled.contains(&request.position));
+        }
+        cancelled.len()
+    }
+
+    /// Number of chunks still queued for every client.
+    pub fn queue_lengths(&self) -> impl Iterator<Item = (&ClientId, usize)> {
+        self.queues
+            .iter()
+            .map(|(client_id, queue)| (client_id, queue.len()))
+    }
+
     pub fn retain<F>(&mut self, f: F)
     where
-        F: FnMut(&ClientId, &mut VecDeque<IVec3>) -> bool,
+        F: FnMut(&ClientId, &mut ClientChunkQueue) -> bool,
     {
         self.queues.retain(f)
     }
 }
 
+/// Counters exposed by the metrics endpoint.
+#[derive(Resource, Default)]
+pub struct TerrainStats {
+    pub chunks_generated: u64,
+    pub last_save_duration: Option<Duration>,
+    /// Size of the chunk batches sent to clients before compression.
+    pub chunk_bytes_encoded: u64,
+    pub chunk_bytes_sent: u64,
+}
+
+impl TerrainStats {
+    /// Sent chunk bytes per encoded byte, `1.0` before any chunks were sent.
+    pub fn compression_ratio(&self) -> f64 {
+        match self.chunk_bytes_encoded {
+            0 => 1.0,
+            encoded => self.chunk_bytes_sent as f64 / encoded as f64,
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct AutoSaveName(pub String);
 
@@ -43,6 +215,13 @@ impl AutoSaveName {
     pub fn with_random() -> Self {
         Self(Alphanumeric.sample_string(&mut rand::rng(), 16))
     }
+
+    /// Name and seed of the world, sent to clients to key their chunk caches.
+    pub fn world_identity(&self, generator: &Generator) -> String {
+        let mut identity = format!("{}-{}", self.0, generator.noise.seed);
+        truncate_to_byte_limit(&mut identity, MAX_WORLD_IDENTITY_BYTES);
+        identity
+    }
 }
 
 #[derive(Resource)]
@@ -60,10 +239,17 @@ impl SaveTimer {
     }
 }
 
+pub const DEFAULT_SAVE_INTERVAL_SECONDS: u64 = 30;
+pub const DEFAULT_BACKUP_INTERVAL_SECONDS: u64 = 180;
+
 #[derive(Resource)]
 pub struct WorldBackupTimer(SaveTimer);
 
 impl WorldBackupTimer {
+    pub fn new(interval: TimeDelta) -> Self {
+        Self(SaveTimer::new(interval))
+    }
+
     pub fn reset(&mut self) {
         self.0.reset()
     }
@@ -75,7 +261,7 @@ impl WorldBackupTimer {
 
 impl Default for WorldBackupTimer {
     fn default() -> Self {
-        Self(SaveTimer::new(TimeDelta::seconds(180)))
+        Self::new(TimeDelta::seconds(DEFAULT_BACKUP_INTERVAL_SECONDS as i64))
     }
 }
 
@@ -83,6 +269,10 @@ impl Default for WorldBackupTimer {
 pub struct WorldSaveTimer(SaveTimer);
 
 impl WorldSaveTimer {
+    pub fn new(interval: TimeDelta) -> Self {
+        Self(SaveTimer::new(interval))
+    }
+
     pub fn reset(&mut self) {
         self.0.reset()
     }
@@ -94,7 +284,7 @@ impl WorldSaveTimer {
 
 impl Default for WorldSaveTimer {
     fn default() -> Self {
-        Self(SaveTimer::new(TimeDelta::seconds(30)))
+        Self::new(TimeDelta::seconds(DEFAULT_SAVE_INTERVAL_SECONDS as i64))
     }
 }
 
@@ -355,3 +545,91 @@ mod visualizer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancelled_chunk_requests_leave_the_queue() {
+        let mut requests = ClientChunkRequests::default();
+        requests.enqueue_bulk(1, (0..4).map(|x| ChunkRequest::new(IVec3::new(x, 0, 0))));
+
+        let cancelled =
+            requests.cancel(&1, &[IVec3::new(1, 0, 0), IVec3::new(3, 0, 0), IVec3::ONE]);
+        assert_eq!(cancelled, 2);
+        assert_eq!(requests.cancel(&2, &[IVec3::ZERO]), 0);
+
+        let mut remaining = Vec::new();
+        requests.retain(|_, queue| {
+            remaining.extend(
+                queue
+                    .take(usize::MAX)
+                    .iter()
+                    .map(|request| request.position),
+            );
+            true
+        });
+        assert_eq!(remaining, vec![IVec3::ZERO, IVec3::new(2, 0, 0)]);
+    }
+
+    #[test]
+    fn test_resent_chunk_requests_are_queued_once() {
+        let mut requests = ClientChunkRequests::default();
+        requests.enqueue_bulk(1, (0..3).map(|x| ChunkRequest::new(IVec3::new(x, 0, 0))));
+        requests.enqueue_bulk(1, (0..4).map(|x| ChunkRequest::new(IVec3::new(x, 0, 0))));
+        assert_eq!(requests.queue_lengths().next(), Some((&1, 4)));
+
+        let mut taken = Vec::new();
+        requests.retain(|_, queue| {
+            taken = queue.take(2);
+            true
+        });
+        assert_eq!(taken.len(), 2);
+
+        requests.enqueue_bulk(1, [ChunkRequest::new(IVec3::ZERO)]);
+        assert_eq!(requests.queue_lengths().next(), Some((&1, 3)));
+    }
+
+    #[test]
+    fn test_chunk_allowance_refills_up_to_one_second() {
+        let config = ChunkStreamingConfig {
+            compression: false,
+            bytes_per_second: 1000,
+        };
+        let mut queue = ClientChunkQueue::default();
+
+        assert_eq!(queue.refill(&config, Duration::from_millis(50)), 1000.0);
+        queue.spend(1500);
+        assert_eq!(queue.refill(&config, Duration::from_millis(250)), -250.0);
+        assert_eq!(queue.refill(&config, Duration::from_secs(10)), 1000.0);
+
+        let unlimited = ChunkStreamingConfig {
+            bytes_per_second: 0,
+            ..config
+        };
+        queue.spend(5000);
+        assert_eq!(queue.refill(&unlimited, Duration::ZERO), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_chunk_queue_settles_once_the_allowance_is_full() {
+        let config = ChunkStreamingConfig {
+            compression: false,
+            bytes_per_second: 1000,
+        };
+        let mut queue = ClientChunkQueue::default();
+        assert!(queue.is_settled(&config));
+
+        queue.refill(&config, Duration::ZERO);
+        queue.spend(500);
+        assert!(!queue.is_settled(&config));
+        queue.refill(&config, Duration::from_millis(250));
+        assert!(!queue.is_settled(&config));
+        queue.refill(&config, Duration::from_millis(250));
+        assert!(queue.is_settled(&config));
+
+        queue.push_back(ChunkRequest::new(IVec3::ZERO));
+        assert!(!queue.is_settled(&config));
+    }
+}
@@ -1,8 +1,10 @@
+use bevy::time::Time;
+
 use crate::{
     prelude::*,
     terrain::{persistence::*, resources::Generator},
 };
-use std::cmp::min;
+use std::time::Instant;
 
 pub fn setup_world_system(
     mut chunk_manager: ResMut<ChunkManager>,
@@ -22,24 +24,36 @@ pub fn setup_world_system(
     chunk_manager.insert_chunks(chunks);
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn process_user_chunk_requests_system(
     mut requests: ResMut<terrain_resources::ClientChunkRequests>,
     mut chunk_manager: ResMut<ChunkManager>,
-    mut server: ResMut<RenetServer>,
+    mut server: networking_systems::GameServer,
     generator: Res<terrain_resources::Generator>,
+    mut stats: ResMut<terrain_resources::TerrainStats>,
+    config: Res<terrain_resources::ChunkStreamingConfig>,
+    time: Res<Time>,
 ) {
-    const MAX_REQUESTS_PER_CYCLE_PER_PLAYER: usize = 10;
-
-    requests.retain(|client_id, positions| {
-        if positions.is_empty() {
-            return false;
+    // Queues stay until their allowance refilled, so that clients cannot get rid of
+    // their debt by waiting for the queue to empty and requesting again.
+    requests.retain(|client_id, queue| {
+        let allowance = queue.refill(&config, time.delta());
+        if queue.is_empty() {
+            return !queue.is_settled(&config);
+        }
+        if allowance <= 0.0 {
+            return true;
         }
 
-        let take_count = min(MAX_REQUESTS_PER_CYCLE_PER_PLAYER, positions.len());
-        let positions_to_process: Vec<IVec3> = positions.drain(0..take_count).collect();
+        let requests_to_process = queue.take(MAX_CHUNK_BATCH_RESPONSE_SIZE);
+        let cached_hashes: HashMap<IVec3, u64> = requests_to_process
+            .iter()
+            .filter_map(|request| Some((request.position, request.cached_hash?)))
+            .collect();
 
-        let (existing, generated): (Vec<_>, Vec<_>) = positions_to_process
+        let (existing, generated): (Vec<_>, Vec<_>) = requests_to_process
             .into_iter()
+            .map(|request| request.position)
             .partition(|pos| chunk_manager.has_chunk(pos));
 
         let existing_chunks: Vec<Chunk> = existing
@@ -63,33 +77,117 @@ pub fn process_user_chunk_requests_system(
         for chunk in &generated_chunks {
             chunk_manager.insert_chunk(*chunk);
         }
+        stats.chunks_generated += generated_chunks.len() as u64;
 
-        let chunks: Vec<Chunk> = existing_chunks
-            .into_iter()
-            .chain(generated_chunks)
-            .collect();
+        // Sizes are estimated from the compression seen so far, the allowance is
+        // charged with what was actually sent.
+        let compression_ratio = match config.compression {
+            true => stats.compression_ratio(),
+            false => 1.0,
+        };
+        let mut estimated_bytes = 0.0;
+        let mut unchanged = Vec::new();
+        let mut chunks = Vec::new();
+        let mut deferred = Vec::new();
+        for chunk in existing_chunks.into_iter().chain(generated_chunks) {
+            if cached_hashes.get(&chunk.position) == Some(&chunk.content_hash()) {
+                unchanged.push(chunk.position);
+                continue;
+            }
+
+            let chunk_bytes = bincode::serialized_size(&chunk)
+                .expect("Chunks should always serialize") as f64
+                * compression_ratio;
+            if !chunks.is_empty() && estimated_bytes + chunk_bytes > allowance {
+                deferred.push(chunk.position);
+                continue;
+            }
+            estimated_bytes += chunk_bytes;
+            chunks.push(chunk);
+        }
 
-        let message = bincode::serialize(&NetworkingMessage::ChunkBatchResponse(chunks));
+        for position in deferred.into_iter().rev() {
+            queue.push_front(terrain_resources::ChunkRequest {
+                position,
+                cached_hash: cached_hashes.get(&position).copied(),
+            });
+        }
 
-        server.send_message(
-            *client_id,
-            DefaultChannel::ReliableUnordered,
-            message.unwrap(),
-        );
+        if !chunks.is_empty() {
+            let sent_bytes = send_chunk_batch(
+                &mut server,
+                *client_id,
+                chunks,
+                config.compression,
+                &mut stats,
+            );
+            queue.spend(sent_bytes);
+        }
+        if !unchanged.is_empty() {
+            let message = NetworkingMessage::ChunkBatchUnchanged(unchanged);
+            let bytes = networking_systems::serialize_message(&message);
+            queue.spend(bytes.len() as u64);
+            server.send_serialized(
+                *client_id,
+                DefaultChannel::ReliableUnordered,
+                &message,
+                bytes,
+            );
+        }
 
-        !positions.is_empty()
+        !queue.is_settled(&config)
     });
 }
 
+/// Sends `chunks` as one batch, compressed if that is enabled and makes it
+/// smaller. The chunks are serialized once, compression starts from those bytes.
+/// Returns the size of the sent message.
+fn send_chunk_batch(
+    server: &mut networking_systems::GameServer,
+    client_id: ClientId,
+    chunks: Vec<Chunk>,
+    compression: bool,
+    stats: &mut terrain_resources::TerrainStats,
+) -> u64 {
+    let uncompressed = NetworkingMessage::ChunkBatchResponse(chunks);
+    let encoded = networking_systems::serialize_message(&uncompressed);
+    let compressed = compression
+        .then(|| {
+            let message = NetworkingMessage::CompressedChunkBatchResponse(
+                compress_chunk_batch_response(&encoded),
+            );
+            let bytes = networking_systems::serialize_message(&message);
+            (message, bytes)
+        })
+        .filter(|(_, bytes)| bytes.len() < encoded.len());
+
+    let encoded_bytes = encoded.len() as u64;
+    let (message, bytes) = compressed.unwrap_or((uncompressed, encoded));
+    let sent_bytes = bytes.len() as u64;
+
+    stats.chunk_bytes_encoded += encoded_bytes;
+    stats.chunk_bytes_sent += sent_bytes;
+    server.send_serialized(
+        client_id,
+        DefaultChannel::ReliableUnordered,
+        &message,
+        bytes,
+    );
+    sent_bytes
+}
+
 pub fn save_world_system(
     chunk_manager: Res<ChunkManager>,
     generator: Res<Generator>,
     world_name: ResMut<terrain_resources::AutoSaveName>,
     mut timer: ResMut<terrain_resources::WorldSaveTimer>,
+    mut stats: ResMut<terrain_resources::TerrainStats>,
 ) {
     if timer.is_ready() {
         info!("Saving world...");
+        let started_at = Instant::now();
         if save_world(&world_name.0, &chunk_manager, &generator).is_ok() {
+            stats.last_save_duration = Some(started_at.elapsed());
             timer.reset();
         }
     }
@@ -142,11 +240,11 @@ mod visualizer {
     use rayon::iter::IntoParallelIterator;
 
     use rayon::iter::ParallelIterator;
-    use renet::{DefaultChannel, RenetServer};
+    use renet::DefaultChannel;
     use rsmc::{Chunk, ChunkManager, NetworkingMessage, CHUNK_SIZE};
 
     use super::{
-        terrain_events,
+        networking_systems, terrain_events,
         terrain_resources::{self, NoiseFunctionParams, TextureType},
     };
 
@@ -235,7 +333,7 @@ mod visualizer {
         mut events: MessageReader<terrain_events::WorldRegenerateEvent>,
         mut chunk_manager: ResMut<ChunkManager>,
         generator: ResMut<terrain_resources::Generator>,
-        mut server: ResMut<RenetServer>,
+        mut server: networking_systems::GameServer,
     ) {
         for _ in events.read() {
             info!("Regenerating world");
@@ -258,12 +356,9 @@ mod visualizer {
             info!("Successfully regenerated world");
             info!("Sending chunk requests for all chunks");
 
-            server.broadcast_message(
+            server.broadcast(
                 DefaultChannel::ReliableUnordered,
-                bincode::serialize(
-                    &NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch(),
-                )
-                .unwrap(),
+                &NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch(),
             );
         }
     }
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use bevy::time::{Fixed, Time};
+
+use crate::prelude::*;
+
+pub mod resources;
+pub mod systems;
+
+pub const DEFAULT_TICK_RATE: f64 = 30.0;
+
+pub struct TickPlugin {
+    tick_rate: f64,
+}
+
+impl TickPlugin {
+    pub fn new(tick_rate: f64) -> Result<Self, String> {
+        if !tick_rate.is_finite() || tick_rate <= 0.0 {
+            return Err(format!(
+                "Invalid tick rate '{tick_rate}', it must be a positive number of ticks per second"
+            ));
+        }
+
+        Ok(Self { tick_rate })
+    }
+
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate)
+    }
+}
+
+impl Plugin for TickPlugin {
+    fn build(&self, app: &mut App) {
+        info!("Building TickPlugin");
+
+        app.insert_resource(Time::<Fixed>::from_hz(self.tick_rate));
+        app.insert_resource(resources::ServerTick::default());
+        app.insert_resource(resources::TickStats::default());
+        app.add_systems(FixedFirst, tick_systems::begin_tick_system);
+        app.add_systems(FixedLast, tick_systems::end_tick_system);
+        app.add_systems(Update, tick_systems::handle_console_tps_command_system);
+    }
+}
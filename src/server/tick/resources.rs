@@ -0,0 +1,85 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::prelude::*;
+
+/// Number of the server tick currently being processed.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerTick(pub u64);
+
+#[derive(Resource, Default)]
+pub struct TickStats {
+    started_at: Option<Instant>,
+    durations: VecDeque<Duration>,
+    overruns: u64,
+}
+
+impl TickStats {
+    const WINDOW: usize = 100;
+
+    pub fn begin(&mut self) {
+        self.started_at = Some(Instant::now());
+    }
+
+    pub fn end(&mut self, budget: Duration) {
+        if let Some(started_at) = self.started_at.take() {
+            self.record(started_at.elapsed(), budget);
+        }
+    }
+
+    pub fn record(&mut self, duration: Duration, budget: Duration) {
+        if duration > budget {
+            self.overruns += 1;
+        }
+
+        self.durations.push_back(duration);
+        if self.durations.len() > Self::WINDOW {
+            self.durations.pop_front();
+        }
+    }
+
+    /// Average duration over the last ticks.
+    pub fn average(&self) -> Duration {
+        match self.durations.len() {
+            0 => Duration::ZERO,
+            len => self.durations.iter().sum::<Duration>() / len as u32,
+        }
+    }
+
+    /// Longest duration over the last ticks.
+    pub fn max(&self) -> Duration {
+        self.durations.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Number of ticks that took longer than the tick interval since startup.
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_stats() {
+        let mut stats = TickStats::default();
+        let budget = Duration::from_millis(33);
+
+        stats.record(Duration::from_millis(10), budget);
+        stats.record(Duration::from_millis(50), budget);
+        stats.record(Duration::from_millis(30), budget);
+
+        assert_eq!(stats.average(), Duration::from_millis(30));
+        assert_eq!(stats.max(), Duration::from_millis(50));
+        assert_eq!(stats.overruns(), 1);
+
+        for _ in 0..TickStats::WINDOW {
+            stats.record(Duration::from_millis(1), budget);
+        }
+        assert_eq!(stats.max(), Duration::from_millis(1));
+        assert_eq!(stats.overruns(), 1);
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::time::{Fixed, Time};
+
+use crate::prelude::*;
+
+pub fn begin_tick_system(
+    mut tick: ResMut<tick_resources::ServerTick>,
+    mut stats: ResMut<tick_resources::TickStats>,
+) {
+    tick.0 += 1;
+    stats.begin();
+}
+
+pub fn end_tick_system(mut stats: ResMut<tick_resources::TickStats>, time: Res<Time<Fixed>>) {
+    stats.end(time.timestep());
+}
+
+pub fn handle_console_tps_command_system(
+    mut command_events: MessageReader<console_events::ConsoleCommandEvent>,
+    tick: Res<tick_resources::ServerTick>,
+    stats: Res<tick_resources::TickStats>,
+    time: Res<Time<Fixed>>,
+) {
+    for event in command_events.read() {
+        if event.0 != console_commands::ConsoleCommand::Tps {
+            continue;
+        }
+
+        println!(
+            "Tick {} at {:.1} Hz: avg {:.2} ms, max {:.2} ms, {} overrun(s)",
+            tick.0,
+            1.0 / time.timestep().as_secs_f64(),
+            stats.average().as_secs_f64() * 1000.0,
+            stats.max().as_secs_f64() * 1000.0,
+            stats.overruns()
+        );
+    }
+}
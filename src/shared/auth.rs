@@ -0,0 +1,144 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind, Write},
+    net::SocketAddr,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use bevy_renet::netcode::{ConnectToken, NETCODE_KEY_BYTES};
+
+use super::Username;
+
+pub const PROTOCOL_ID: u64 = 0;
+pub const CONNECT_TOKEN_TIMEOUT_SECONDS: i32 = 15;
+pub const DEFAULT_TOKEN_EXPIRE_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+pub type PrivateKey = [u8; NETCODE_KEY_BYTES];
+
+/// Reads the hex encoded private key at `path`, generating a new one if it does not exist yet.
+pub fn load_or_create_private_key(path: &Path) -> Result<PrivateKey, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => decode_private_key(content.trim())
+            .map_err(|err| format!("Invalid private key in '{}': {}", path.display(), err)),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let private_key: PrivateKey = rand::random();
+            write_private_key(path, &private_key)
+                .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))?;
+            println!("Generated new private key in '{}'", path.display());
+            Ok(private_key)
+        }
+        Err(err) => Err(format!("Failed to read '{}': {}", path.display(), err)),
+    }
+}
+
+fn write_private_key(path: &Path, private_key: &PrivateKey) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let encoded: String = private_key
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    writeln!(options.open(path)?, "{encoded}")
+}
+
+fn decode_private_key(encoded: &str) -> Result<PrivateKey, String> {
+    if encoded.len() != NETCODE_KEY_BYTES * 2 || !encoded.is_ascii() {
+        return Err(format!("expected {} hex characters", NETCODE_KEY_BYTES * 2));
+    }
+
+    let mut private_key = [0u8; NETCODE_KEY_BYTES];
+    for (index, byte) in private_key.iter_mut().enumerate() {
+        let digits = &encoded[index * 2..index * 2 + 2];
+        *byte =
+            u8::from_str_radix(digits, 16).map_err(|_| format!("'{digits}' is not a hex byte"))?;
+    }
+    Ok(private_key)
+}
+
+/// Mints a connect token that lets a client join `server_addresses` as `username`.
+pub fn issue_connect_token(
+    private_key: &PrivateKey,
+    username: &Username,
+    server_addresses: Vec<SocketAddr>,
+    expire_seconds: u64,
+) -> Result<ConnectToken, String> {
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+
+    ConnectToken::generate(
+        current_time,
+        PROTOCOL_ID,
+        expire_seconds,
+        rand::random::<u64>(),
+        CONNECT_TOKEN_TIMEOUT_SECONDS,
+        server_addresses,
+        Some(&username.to_netcode_user_data()),
+        private_key,
+    )
+    .map_err(|err| format!("Failed to generate connect token: {err}"))
+}
+
+pub fn write_connect_token(token: &ConnectToken, path: &Path) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|err| format!("Failed to create '{}': {}", path.display(), err))?;
+    let mut writer = BufWriter::new(file);
+    token
+        .write(&mut writer)
+        .and_then(|_| writer.flush())
+        .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))
+}
+
+pub fn read_connect_token(path: &Path) -> Result<ConnectToken, String> {
+    let file =
+        File::open(path).map_err(|err| format!("Failed to open '{}': {}", path.display(), err))?;
+    ConnectToken::read(&mut BufReader::new(file))
+        .map_err(|err| format!("Invalid connect token in '{}': {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TempDir;
+
+    #[test]
+    fn test_private_key_is_created_once() {
+        let directory = TempDir::create("auth_private_key").unwrap();
+        let path = directory.join("private.key");
+
+        let created = load_or_create_private_key(&path).unwrap();
+        let loaded = load_or_create_private_key(&path).unwrap();
+        assert_eq!(created, loaded);
+
+        fs::write(&path, "not a key").unwrap();
+        assert!(load_or_create_private_key(&path).is_err());
+    }
+
+    #[test]
+    fn test_connect_token_roundtrip() {
+        let directory = TempDir::create("auth_token").unwrap();
+        let path = directory.join("token.bin");
+        let private_key: PrivateKey = rand::random();
+        let server_addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+
+        let token = issue_connect_token(
+            &private_key,
+            &Username::from("Steve"),
+            vec![server_addr],
+            DEFAULT_TOKEN_EXPIRE_SECONDS,
+        )
+        .unwrap();
+        write_connect_token(&token, &path).unwrap();
+
+        let read = read_connect_token(&path).unwrap();
+        assert_eq!(read.client_id, token.client_id);
+        assert_eq!(read.server_addresses[0], Some(server_addr));
+    }
+}
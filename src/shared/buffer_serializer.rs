@@ -49,6 +49,27 @@ fn tokenize_buffer(array: Vec<u8>) -> Vec<RLEToken> {
     vec
 }
 
+pub const fn max_serialized_buffer_len(len: usize) -> usize {
+    len * TOKEN_BYTE_COUNT
+}
+
+/// Returns the length `deserialize_buffer` would produce, or `None` if `bytes`
+/// does not consist of whole tokens.
+pub fn deserialized_buffer_len(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() % TOKEN_BYTE_COUNT != 0 {
+        return None;
+    }
+
+    let len = bytes
+        .chunks_exact(TOKEN_BYTE_COUNT)
+        .map(|token| {
+            let count_bytes = &token[COUNT_OFFSET..COUNT_OFFSET + COUNT_LENGTH];
+            u16::from_le_bytes(count_bytes.try_into().unwrap()) as usize
+        })
+        .sum();
+    Some(len)
+}
+
 pub fn deserialize_buffer(bytes: &[u8]) -> Vec<u8> {
     let mut vec = Vec::<u8>::new();
 
@@ -137,4 +158,15 @@ pub mod tests {
         let deserialized_array = deserialize_buffer(&bytes);
         assert_eq!(array, deserialized_array);
     }
+
+    #[test]
+    fn test_deserialized_buffer_len() {
+        #[rustfmt::skip]
+        let array = vec![1, 1, 1, 1, 2, 2, 2, 3, 3, 3, 3, 3];
+        let bytes = serialize_buffer(array.clone());
+
+        assert_eq!(deserialized_buffer_len(&bytes), Some(array.len()));
+        assert_eq!(deserialized_buffer_len(&bytes[1..]), None);
+        assert!(bytes.len() <= max_serialized_buffer_len(array.len()));
+    }
 }
@@ -79,4 +79,46 @@ impl Chunk {
     pub fn key_eq_pos(key: [i32; 3], position: IVec3) -> bool {
         position.x == key[0] && position.y == key[1] && position.z == key[2]
     }
+
+    /// FNV-1a over the position and blocks. Unlike `DefaultHasher` it is stable
+    /// across builds, so clients can compare it with hashes the server computed.
+    pub fn content_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        let position_bytes = self
+            .position
+            .to_array()
+            .into_iter()
+            .flat_map(i32::to_le_bytes);
+        let block_bytes = self.data.iter().map(|block| u8::from(*block));
+
+        position_bytes
+            .chain(block_bytes)
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(PRIME)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_changes_with_blocks_and_position() {
+        let chunk = Chunk::new(IVec3::new(1, 2, 3));
+        assert_eq!(
+            chunk.content_hash(),
+            Chunk::new(IVec3::new(1, 2, 3)).content_hash()
+        );
+        assert_ne!(
+            chunk.content_hash(),
+            Chunk::new(IVec3::new(1, 2, 4)).content_hash()
+        );
+
+        let mut changed = chunk;
+        changed.set(0, 0, 0, BlockId::Stone);
+        assert_ne!(chunk.content_hash(), changed.content_hash());
+    }
 }
@@ -1,12 +1,29 @@
 use std::collections::HashMap;
 
-use bevy::{log::info, math::IVec3, prelude::Resource};
+use bevy::{
+    log::{info, warn},
+    math::IVec3,
+    prelude::Resource,
+};
 
 use crate::*;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingBlockUpdate {
+    pub position: IVec3,
+    pub block: BlockId,
+}
+
+/// Limits on block updates held for chunks that are not loaded. Updates beyond
+/// them are dropped, so that far away updates cannot grow memory without bound.
+pub const MAX_PENDING_UPDATES_PER_CHUNK: usize = 1024;
+pub const MAX_PENDING_UPDATES: usize = 16 * 1024;
+
 #[derive(Resource)]
 pub struct ChunkManager {
     pub chunks: HashMap<IVec3, Chunk>,
+    pending_updates: HashMap<IVec3, Vec<PendingBlockUpdate>>,
+    pending_update_total: usize,
 }
 
 impl Default for ChunkManager {
@@ -19,6 +36,8 @@ impl ChunkManager {
     pub fn new() -> Self {
         Self {
             chunks: HashMap::new(),
+            pending_updates: HashMap::new(),
+            pending_update_total: 0,
         }
     }
 
@@ -28,7 +47,11 @@ impl ChunkManager {
             .map(|chunk| (chunk.position, chunk))
             .collect();
 
-        Self { chunks }
+        Self {
+            chunks,
+            pending_updates: HashMap::new(),
+            pending_update_total: 0,
+        }
     }
 
     pub fn instantiate_chunks(position: IVec3, render_distance: IVec3) -> Vec<Chunk> {
@@ -86,7 +109,8 @@ impl ChunkManager {
         positions
     }
 
-    pub fn insert_chunk(&mut self, chunk: Chunk) {
+    pub fn insert_chunk(&mut self, mut chunk: Chunk) {
+        self.apply_pending_updates(chunk.position, &mut chunk);
         self.chunks.insert(chunk.position, chunk);
     }
 
@@ -96,7 +120,8 @@ impl ChunkManager {
         }
     }
 
-    pub fn set_chunk(&mut self, position: IVec3, chunk: Chunk) {
+    pub fn set_chunk(&mut self, position: IVec3, mut chunk: Chunk) {
+        self.apply_pending_updates(position, &mut chunk);
         self.chunks.insert(position, chunk);
     }
 
@@ -108,37 +133,82 @@ impl ChunkManager {
         self.chunks.contains_key(position)
     }
 
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
     pub fn get_chunk_mut(&mut self, position: &IVec3) -> Option<&mut Chunk> {
         self.chunks.get_mut(position)
     }
 
     pub fn update_block(&mut self, position: IVec3, block: BlockId) -> Vec<IVec3> {
-        Self::chunk_positions_containing_world_pos(position)
-            .iter()
-            .flat_map(|chunk_position| {
-                let chunk_option = self.get_chunk_mut(chunk_position);
-                match chunk_option {
-                    Some(chunk) => {
-                        let chunk_origin = *chunk_position * CHUNK_SIZE as i32;
-                        let local_position = position - chunk_origin;
+        let mut updated_chunk_positions = Vec::new();
 
-                        info!("Performing local update at {:?}", local_position);
+        for chunk_position in Self::chunk_positions_containing_world_pos(position) {
+            match self.chunks.get_mut(&chunk_position) {
+                Some(chunk) => {
+                    Self::apply_block_update(chunk, chunk_position, position, block);
+                    updated_chunk_positions.push(chunk_position);
+                }
+                None => {
+                    let updates = self.pending_updates.entry(chunk_position).or_default();
+                    if updates.len() >= MAX_PENDING_UPDATES_PER_CHUNK
+                        || self.pending_update_total >= MAX_PENDING_UPDATES
+                    {
+                        warn!(
+                            "Dropping block update at {:?}, too many updates are pending",
+                            position
+                        );
+                        continue;
+                    }
+                    updates.push(PendingBlockUpdate { position, block });
+                    self.pending_update_total += 1;
+                }
+            }
+        }
 
-                        assert!(local_position.x >= -1 && local_position.x <= CHUNK_SIZE as i32);
-                        assert!(local_position.y >= -1 && local_position.y <= CHUNK_SIZE as i32);
-                        assert!(local_position.z >= -1 && local_position.z <= CHUNK_SIZE as i32);
+        updated_chunk_positions
+    }
 
-                        chunk.update(local_position.x, local_position.y, local_position.z, block);
+    pub fn pending_update_count(&self, chunk_position: &IVec3) -> usize {
+        self.pending_updates
+            .get(chunk_position)
+            .map_or(0, |updates| updates.len())
+    }
 
-                        Some(*chunk_position)
-                    }
-                    None => {
-                        // FIXME: we should do something about updates in unloaded chunks..
-                        None
-                    }
-                }
-            })
-            .collect()
+    fn apply_pending_updates(&mut self, chunk_position: IVec3, chunk: &mut Chunk) {
+        let Some(updates) = self.pending_updates.remove(&chunk_position) else {
+            return;
+        };
+        self.pending_update_total -= updates.len();
+
+        info!(
+            "Applying {} pending block updates to chunk {:?}",
+            updates.len(),
+            chunk_position
+        );
+
+        for update in updates {
+            Self::apply_block_update(chunk, chunk_position, update.position, update.block);
+        }
+    }
+
+    fn apply_block_update(
+        chunk: &mut Chunk,
+        chunk_position: IVec3,
+        position: IVec3,
+        block: BlockId,
+    ) {
+        let chunk_origin = chunk_position * CHUNK_SIZE as i32;
+        let local_position = position - chunk_origin;
+
+        info!("Performing local update at {:?}", local_position);
+
+        assert!(local_position.x >= -1 && local_position.x <= CHUNK_SIZE as i32);
+        assert!(local_position.y >= -1 && local_position.y <= CHUNK_SIZE as i32);
+        assert!(local_position.z >= -1 && local_position.z <= CHUNK_SIZE as i32);
+
+        chunk.update(local_position.x, local_position.y, local_position.z, block);
     }
 
     pub fn get_block(&self, position: IVec3) -> Option<BlockId> {
@@ -366,4 +436,92 @@ mod tests {
         assert_eq!(chunk_manager.get_block(grass_position).unwrap(), BlockId::Air);
         assert_eq!(chunk_manager.get_block(tallgrass_position).unwrap(), BlockId::Air);
     }
+
+    #[test]
+    fn test_update_on_chunk_border_updates_neighbour_padding() {
+        let mut chunk_manager = ChunkManager::new();
+        chunk_manager.set_chunk(IVec3::ZERO, Chunk::new(IVec3::ZERO));
+        chunk_manager.set_chunk(IVec3::X, Chunk::new(IVec3::X));
+
+        let border_position = IVec3::new(CHUNK_SIZE as i32 - 1, 1, 1);
+        let updated = chunk_manager.update_block(border_position, BlockId::Stone);
+
+        assert_eq!(updated, vec![IVec3::ZERO, IVec3::X]);
+        assert_eq!(
+            chunk_manager.get_chunk(&IVec3::ZERO).unwrap().get(31, 1, 1),
+            BlockId::Stone
+        );
+        assert_eq!(
+            chunk_manager.get_chunk(&IVec3::X).unwrap().get(-1, 1, 1),
+            BlockId::Stone
+        );
+    }
+
+    #[test]
+    fn test_update_on_chunk_border_queues_update_for_unloaded_neighbour() {
+        let mut chunk_manager = ChunkManager::new();
+        chunk_manager.set_chunk(IVec3::ZERO, Chunk::new(IVec3::ZERO));
+
+        let border_position = IVec3::new(CHUNK_SIZE as i32 - 1, 1, 1);
+        let updated = chunk_manager.update_block(border_position, BlockId::Stone);
+
+        assert_eq!(updated, vec![IVec3::ZERO]);
+        assert_eq!(chunk_manager.pending_update_count(&IVec3::X), 1);
+
+        chunk_manager.insert_chunk(Chunk::new(IVec3::X));
+
+        assert_eq!(chunk_manager.pending_update_count(&IVec3::X), 0);
+        assert_eq!(
+            chunk_manager.get_chunk(&IVec3::X).unwrap().get(-1, 1, 1),
+            BlockId::Stone
+        );
+    }
+
+    #[test]
+    fn test_updates_before_chunk_loads_are_applied_in_order() {
+        let mut chunk_manager = ChunkManager::new();
+        let position = IVec3::new(5, 6, 7);
+
+        assert!(chunk_manager
+            .update_block(position, BlockId::Stone)
+            .is_empty());
+        assert!(chunk_manager
+            .update_block(position, BlockId::Dirt)
+            .is_empty());
+        assert_eq!(chunk_manager.get_block(position), None);
+        assert_eq!(chunk_manager.pending_update_count(&IVec3::ZERO), 2);
+
+        chunk_manager.insert_chunks(vec![Chunk::new(IVec3::ZERO)]);
+
+        assert_eq!(chunk_manager.get_block(position), Some(BlockId::Dirt));
+        assert_eq!(chunk_manager.pending_update_count(&IVec3::ZERO), 0);
+    }
+
+    #[test]
+    fn test_pending_updates_are_capped() {
+        let mut chunk_manager = ChunkManager::new();
+        let position = IVec3::new(5, 6, 7);
+
+        for _ in 0..MAX_PENDING_UPDATES_PER_CHUNK + 1 {
+            chunk_manager.update_block(position, BlockId::Stone);
+        }
+        assert_eq!(
+            chunk_manager.pending_update_count(&IVec3::ZERO),
+            MAX_PENDING_UPDATES_PER_CHUNK
+        );
+
+        let far_away = (1..)
+            .map(|x| IVec3::new(x * 100 * CHUNK_SIZE as i32, 5, 5))
+            .take(MAX_PENDING_UPDATES);
+        for position in far_away {
+            chunk_manager.update_block(position, BlockId::Stone);
+        }
+        assert_eq!(chunk_manager.pending_update_total, MAX_PENDING_UPDATES);
+
+        chunk_manager.insert_chunk(Chunk::new(IVec3::ZERO));
+        assert_eq!(
+            chunk_manager.pending_update_total,
+            MAX_PENDING_UPDATES - MAX_PENDING_UPDATES_PER_CHUNK
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use crate::deserialize_buffer;
+use crate::deserialized_buffer_len;
 use crate::serialize_buffer;
 use crate::BlockId;
 use crate::Chunk;
@@ -54,6 +55,9 @@ impl<'de> Deserialize<'de> for Chunk {
         let ChunkData { data, position } = ChunkData::deserialize(deserializer)?;
         let chunk_data_bytes_u8: Vec<u8> = data.0;
         let bytes_slice: &[u8] = &chunk_data_bytes_u8;
+        if deserialized_buffer_len(bytes_slice) != Some(CHUNK_LENGTH) {
+            return Err(serde::de::Error::custom("Chunk data has an invalid length"));
+        }
         let deserialized_data = deserialize_buffer(bytes_slice);
         let data_as_block_id: [BlockId; CHUNK_LENGTH] = deserialized_data
             .into_iter()
@@ -0,0 +1,87 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use bevy::math::IVec3;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Reads a TOML configuration file, writing the defaults to `path` if it does not exist yet.
+pub fn load_or_create_config<T>(path: &Path) -> Result<T, String>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|err| format!("Failed to parse '{}': {}", path.display(), err)),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let config = T::default();
+            let content = toml::to_string_pretty(&config)
+                .map_err(|err| format!("Failed to serialize default configuration: {err}"))?;
+            fs::write(path, content)
+                .map_err(|err| format!("Failed to write '{}': {}", path.display(), err))?;
+            println!("Wrote default configuration to '{}'", path.display());
+            Ok(config)
+        }
+        Err(err) => Err(format!("Failed to read '{}': {}", path.display(), err)),
+    }
+}
+
+/// Parses `x,y,z` into an `IVec3`, for use as a clap value parser.
+pub fn parse_ivec3(value: &str) -> Result<IVec3, String> {
+    let components = value
+        .split(',')
+        .map(|component| component.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("Invalid vector '{value}': {err}"))?;
+
+    match components[..] {
+        [x, y, z] => Ok(IVec3::new(x, y, z)),
+        _ => Err(format!("Invalid vector '{value}', expected 'x,y,z'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    struct TestConfig {
+        name: String,
+        origin: IVec3,
+    }
+
+    impl Default for TestConfig {
+        fn default() -> Self {
+            Self {
+                name: String::from("rsmc"),
+                origin: IVec3::new(1, 2, 3),
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_config_is_written_and_loaded() {
+        let path = std::env::temp_dir().join("rsmc_config_roundtrip.toml");
+        let _ = fs::remove_file(&path);
+
+        let created: TestConfig = load_or_create_config(&path).unwrap();
+        assert_eq!(created, TestConfig::default());
+        assert!(path.is_file());
+
+        fs::write(&path, "name = \"custom\"\n").unwrap();
+        let loaded: TestConfig = load_or_create_config(&path).unwrap();
+        assert_eq!(loaded.name, "custom");
+        assert_eq!(loaded.origin, IVec3::new(1, 2, 3));
+
+        fs::write(&path, "nmae = \"typo\"\n").unwrap();
+        assert!(load_or_create_config::<TestConfig>(&path).is_err());
+    }
+
+    #[test]
+    fn test_parse_ivec3() {
+        assert_eq!(parse_ivec3("0, 43,-2"), Ok(IVec3::new(0, 43, -2)));
+        assert!(parse_ivec3("1,2").is_err());
+        assert!(parse_ivec3("a,b,c").is_err());
+    }
+}
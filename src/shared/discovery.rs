@@ -0,0 +1,197 @@
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use super::{ServerStatus, MAX_STATUS_RESPONSE_BYTES};
+
+pub const DEFAULT_DISCOVERY_PORT: u16 = 5002;
+pub const DEFAULT_DISCOVERY_DURATION: Duration = Duration::from_secs(3);
+pub const LAN_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+const LAN_ANNOUNCEMENT_MAGIC: &[u8; 8] = b"RSMCLAN\0";
+
+/// Periodically broadcast by servers so that clients on the same network can find them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanAnnouncement {
+    /// Port of the game server, the address is taken from the sender.
+    pub game_port: u16,
+    pub status: ServerStatus,
+}
+
+impl LanAnnouncement {
+    pub fn encode(self) -> Vec<u8> {
+        let mut bytes = LAN_ANNOUNCEMENT_MAGIC.to_vec();
+        bytes.extend_from_slice(&self.game_port.to_le_bytes());
+        bytes.extend(self.status.encode());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let payload = bytes
+            .strip_prefix(LAN_ANNOUNCEMENT_MAGIC)
+            .ok_or_else(|| String::from("Not a LAN announcement"))?;
+        let (game_port, status) = payload
+            .split_first_chunk::<2>()
+            .ok_or_else(|| String::from("Truncated LAN announcement"))?;
+
+        Ok(Self {
+            game_port: u16::from_le_bytes(*game_port),
+            status: ServerStatus::decode(status)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredServer {
+    pub address: SocketAddr,
+    pub status: ServerStatus,
+}
+
+/// Listens for LAN announcements.
+pub struct LanListener {
+    socket: UdpSocket,
+}
+
+impl LanListener {
+    /// Listens on `address`. For a multicast address, the listener binds to the port
+    /// on all interfaces and joins the group instead.
+    pub fn bind(address: SocketAddr) -> Result<Self, String> {
+        let bind_addr = match address.ip() {
+            IpAddr::V4(ip) if ip.is_multicast() => (Ipv4Addr::UNSPECIFIED, address.port()).into(),
+            IpAddr::V6(ip) if ip.is_multicast() => (Ipv6Addr::UNSPECIFIED, address.port()).into(),
+            _ => address,
+        };
+        let socket = UdpSocket::bind(bind_addr)
+            .map_err(|err| format!("Failed to listen for LAN servers on {bind_addr}: {err}"))?;
+
+        let joined = match address.ip() {
+            IpAddr::V4(group) if group.is_multicast() => {
+                socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)
+            }
+            IpAddr::V6(group) if group.is_multicast() => socket.join_multicast_v6(&group, 0),
+            _ => Ok(()),
+        };
+        joined.map_err(|err| format!("Failed to join multicast group {}: {err}", address.ip()))?;
+
+        Ok(Self { socket })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.socket
+            .local_addr()
+            .map_err(|err| format!("Failed to read listener address: {err}"))
+    }
+
+    /// Collects announcements for `duration`, keeping the latest one of every server.
+    pub fn collect(&self, duration: Duration) -> Result<Vec<DiscoveredServer>, String> {
+        let deadline = Instant::now() + duration;
+        let mut servers: Vec<DiscoveredServer> = Vec::new();
+        let mut buffer =
+            vec![0; LAN_ANNOUNCEMENT_MAGIC.len() + 2 + MAX_STATUS_RESPONSE_BYTES as usize];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            self.socket
+                .set_read_timeout(Some(remaining))
+                .map_err(|err| format!("Failed to set timeout: {err}"))?;
+
+            let (len, sender) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    break
+                }
+                Err(err) => return Err(format!("Failed to receive LAN announcement: {err}")),
+            };
+
+            let Ok(announcement) = LanAnnouncement::decode(&buffer[..len]) else {
+                continue;
+            };
+            let server = DiscoveredServer {
+                address: SocketAddr::new(sender.ip(), announcement.game_port),
+                status: announcement.status,
+            };
+            match servers
+                .iter_mut()
+                .find(|known| known.address == server.address)
+            {
+                Some(known) => *known = server,
+                None => servers.push(server),
+            }
+        }
+
+        Ok(servers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_status(world_name: &str) -> ServerStatus {
+        ServerStatus {
+            protocol_version: 0,
+            motd: String::from("Office server"),
+            players: Vec::new(),
+            max_players: 8,
+            world_name: String::from(world_name),
+            uptime_seconds: 10,
+        }
+    }
+
+    #[test]
+    fn test_announcement_roundtrip() {
+        let announcement = LanAnnouncement {
+            game_port: 5000,
+            status: test_status("world"),
+        };
+        let bytes = announcement.clone().encode();
+
+        assert_eq!(LanAnnouncement::decode(&bytes), Ok(announcement));
+        assert!(LanAnnouncement::decode(&bytes[..9]).is_err());
+        assert!(LanAnnouncement::decode(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn test_discovery_on_loopback() {
+        let listener = LanListener::bind((Ipv4Addr::LOCALHOST, 0).into()).unwrap();
+        let target = listener.local_addr().unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+
+        for (game_port, world_name) in [(5000, "old"), (5000, "new"), (6000, "other")] {
+            let announcement = LanAnnouncement {
+                game_port,
+                status: test_status(world_name),
+            };
+            sender.send_to(&announcement.encode(), target).unwrap();
+        }
+        sender.send_to(b"noise", target).unwrap();
+
+        let servers = listener.collect(Duration::from_millis(200)).unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(
+            servers[0].address,
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 5000))
+        );
+        assert_eq!(servers[0].status.world_name, "new");
+        assert_eq!(
+            servers[1].address,
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 6000))
+        );
+    }
+
+    #[test]
+    fn test_multicast_listener_binds_all_interfaces() {
+        let Ok(listener) = LanListener::bind((Ipv4Addr::new(239, 255, 42, 99), 0).into()) else {
+            // Hosts without a multicast route cannot join groups.
+            return;
+        };
+        assert_eq!(
+            listener.local_addr().unwrap().ip(),
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        );
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    io::ErrorKind,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use bevy::ecs::resource::Resource;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Peers that stay silent for this long are forgotten by the proxy.
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
+/// Every peer holds a socket, datagrams from further senders are dropped.
+const MAX_LINK_CONDITIONER_PEERS: usize = 256;
+const IDLE_SLEEP: Duration = Duration::from_millis(1);
+const MAX_DATAGRAM_BYTES: usize = 1500;
+/// Upper bound for `latency_ms` and `jitter_ms`, anything longer only times out.
+pub const MAX_SIMULATED_DELAY_MS: u64 = 60_000;
+
+/// How packets are delayed, dropped and throttled in each direction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkConditionerSettings {
+    pub enabled: bool,
+    /// One way delay added to every packet.
+    pub latency_ms: u64,
+    /// Random variation of the delay in both directions, can reorder packets.
+    pub jitter_ms: u64,
+    /// Share of packets that are dropped, between 0 and 1.
+    pub packet_loss: f64,
+    /// Bandwidth of every client in each direction, `0` for no limit.
+    pub bandwidth_kbps: u64,
+}
+
+impl Default for LinkConditionerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            latency_ms: 100,
+            jitter_ms: 20,
+            packet_loss: 0.02,
+            bandwidth_kbps: 0,
+        }
+    }
+}
+
+impl LinkConditionerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.packet_loss) {
+            return Err(format!(
+                "packet_loss must be between 0 and 1, got {}",
+                self.packet_loss
+            ));
+        }
+        if self.latency_ms > MAX_SIMULATED_DELAY_MS || self.jitter_ms > MAX_SIMULATED_DELAY_MS {
+            return Err(format!(
+                "latency_ms and jitter_ms must not exceed {MAX_SIMULATED_DELAY_MS}, got {} and {}",
+                self.latency_ms, self.jitter_ms
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Decides when a packet sent over one direction of a link arrives, if at all.
+pub struct Link {
+    settings: LinkConditionerSettings,
+    rng: SmallRng,
+    busy_until: Option<Instant>,
+}
+
+impl Link {
+    pub fn new(settings: LinkConditionerSettings, seed: u64) -> Self {
+        Self {
+            settings,
+            rng: SmallRng::seed_from_u64(seed),
+            busy_until: None,
+        }
+    }
+
+    /// Returns the delivery time of a packet of `len` bytes sent at `now`, or `None`
+    /// if it is lost.
+    pub fn schedule(&mut self, now: Instant, len: usize) -> Option<Instant> {
+        if self.settings.packet_loss > 0.0 && self.rng.random_bool(self.settings.packet_loss) {
+            return None;
+        }
+
+        // Packets queue behind each other on a capped link.
+        let sent_at = match self.settings.bandwidth_kbps {
+            0 => now,
+            kbps => {
+                let start = self
+                    .busy_until
+                    .map_or(now, |busy_until| busy_until.max(now));
+                let transmission =
+                    Duration::from_secs_f64(len as f64 * 8.0 / (kbps as f64 * 1000.0));
+                self.busy_until = Some(start + transmission);
+                start + transmission
+            }
+        };
+
+        let jitter = self.settings.jitter_ms as i64;
+        let delay_ms = self.settings.latency_ms as i64 + self.rng.random_range(-jitter..=jitter);
+        Some(sent_at + Duration::from_millis(delay_ms.max(0) as u64))
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Direction {
+    ToUpstream,
+    ToClient,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct DelayedPacket {
+    deliver_at: Instant,
+    sequence: u64,
+    client: SocketAddr,
+    direction: Direction,
+    bytes: Vec<u8>,
+}
+
+struct Peer {
+    socket: UdpSocket,
+    to_upstream: Link,
+    to_client: Link,
+    last_seen: Instant,
+}
+
+/// UDP proxy that forwards datagrams between clients and an upstream server through
+/// conditioned links. Every client gets its own upstream socket so that the server
+/// still tells them apart, by the proxy's address instead of the client's.
+#[derive(Resource)]
+pub struct LinkConditioner {
+    local_addr: SocketAddr,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LinkConditioner {
+    pub fn spawn(
+        listen_addr: SocketAddr,
+        upstream_addr: SocketAddr,
+        settings: LinkConditionerSettings,
+    ) -> Result<Self, String> {
+        settings.validate()?;
+        let socket = UdpSocket::bind(listen_addr)
+            .map_err(|err| format!("Failed to bind link conditioner on {listen_addr}: {err}"))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|err| format!("Failed to configure link conditioner socket: {err}"))?;
+        let local_addr = socket
+            .local_addr()
+            .map_err(|err| format!("Failed to read link conditioner address: {err}"))?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            std::thread::Builder::new()
+                .name(String::from("link conditioner"))
+                .spawn(move || run_proxy(socket, upstream_addr, settings, running))
+                .map_err(|err| format!("Failed to start link conditioner: {err}"))?
+        };
+
+        Ok(Self {
+            local_addr,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for LinkConditioner {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_proxy(
+    socket: UdpSocket,
+    upstream_addr: SocketAddr,
+    settings: LinkConditionerSettings,
+    running: Arc<AtomicBool>,
+) {
+    let mut peers: HashMap<SocketAddr, Peer> = HashMap::new();
+    let mut queue: BinaryHeap<Reverse<DelayedPacket>> = BinaryHeap::new();
+    let mut sequence = 0;
+    let mut buffer = [0; MAX_DATAGRAM_BYTES];
+    let mut full = false;
+
+    while running.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let mut idle = true;
+
+        while let Ok((len, client)) = socket.recv_from(&mut buffer) {
+            idle = false;
+            if !peers.contains_key(&client) {
+                if peers.len() >= MAX_LINK_CONDITIONER_PEERS {
+                    if !full {
+                        eprintln!(
+                            "Link conditioner is forwarding for {MAX_LINK_CONDITIONER_PEERS} peers, dropping packets from new ones"
+                        );
+                        full = true;
+                    }
+                    continue;
+                }
+                match connect_peer(upstream_addr, &settings) {
+                    Ok(peer) => {
+                        peers.insert(client, peer);
+                    }
+                    Err(err) => {
+                        eprintln!("{err}");
+                        continue;
+                    }
+                }
+            }
+            let peer = peers.get_mut(&client).expect("Peer was just connected");
+            peer.last_seen = now;
+            if let Some(deliver_at) = peer.to_upstream.schedule(now, len) {
+                sequence += 1;
+                queue.push(Reverse(DelayedPacket {
+                    deliver_at,
+                    sequence,
+                    client,
+                    direction: Direction::ToUpstream,
+                    bytes: buffer[..len].to_vec(),
+                }));
+            }
+        }
+
+        for (client, peer) in peers.iter_mut() {
+            while let Ok(len) = peer.socket.recv(&mut buffer) {
+                idle = false;
+                if let Some(deliver_at) = peer.to_client.schedule(now, len) {
+                    sequence += 1;
+                    queue.push(Reverse(DelayedPacket {
+                        deliver_at,
+                        sequence,
+                        client: *client,
+                        direction: Direction::ToClient,
+                        bytes: buffer[..len].to_vec(),
+                    }));
+                }
+            }
+        }
+
+        while queue
+            .peek()
+            .is_some_and(|Reverse(packet)| packet.deliver_at <= now)
+        {
+            let Reverse(packet) = queue.pop().expect("Queue should not be empty");
+            idle = false;
+            let result = match packet.direction {
+                Direction::ToUpstream => match peers.get(&packet.client) {
+                    Some(peer) => peer.socket.send(&packet.bytes),
+                    None => continue,
+                },
+                Direction::ToClient => socket.send_to(&packet.bytes, packet.client),
+            };
+            if let Err(err) = result {
+                if err.kind() != ErrorKind::WouldBlock {
+                    eprintln!("Link conditioner failed to forward a packet: {err}");
+                }
+            }
+        }
+
+        peers.retain(|_, peer| now.duration_since(peer.last_seen) < PEER_TIMEOUT);
+        full &= peers.len() >= MAX_LINK_CONDITIONER_PEERS;
+
+        if idle {
+            std::thread::sleep(IDLE_SLEEP);
+        }
+    }
+}
+
+fn connect_peer(
+    upstream_addr: SocketAddr,
+    settings: &LinkConditionerSettings,
+) -> Result<Peer, String> {
+    let bind_addr: SocketAddr = if upstream_addr.is_ipv6() {
+        (Ipv6Addr::LOCALHOST, 0).into()
+    } else {
+        (Ipv4Addr::LOCALHOST, 0).into()
+    };
+    let socket = UdpSocket::bind(bind_addr)
+        .and_then(|socket| {
+            socket.connect(upstream_addr)?;
+            socket.set_nonblocking(true)?;
+            Ok(socket)
+        })
+        .map_err(|err| format!("Link conditioner failed to reach {upstream_addr}: {err}"))?;
+
+    Ok(Peer {
+        socket,
+        to_upstream: Link::new(settings.clone(), rand::random()),
+        to_client: Link::new(settings.clone(), rand::random()),
+        last_seen: Instant::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(latency_ms: u64, jitter_ms: u64, packet_loss: f64) -> LinkConditionerSettings {
+        LinkConditionerSettings {
+            enabled: true,
+            latency_ms,
+            jitter_ms,
+            packet_loss,
+            bandwidth_kbps: 0,
+        }
+    }
+
+    #[test]
+    fn test_link_delays_and_drops_packets() {
+        let now = Instant::now();
+
+        let mut link = Link::new(settings(100, 0, 0.0), 1);
+        assert_eq!(
+            link.schedule(now, 100),
+            Some(now + Duration::from_millis(100))
+        );
+
+        let mut link = Link::new(settings(100, 20, 0.0), 1);
+        for _ in 0..100 {
+            let delay = link.schedule(now, 100).unwrap() - now;
+            assert!(delay >= Duration::from_millis(80) && delay <= Duration::from_millis(120));
+        }
+
+        let mut link = Link::new(settings(0, 0, 1.0), 1);
+        assert!((0..100).all(|_| link.schedule(now, 100).is_none()));
+
+        let mut link = Link::new(settings(0, 0, 0.5), 1);
+        let delivered = (0..1000)
+            .filter(|_| link.schedule(now, 100).is_some())
+            .count();
+        assert!((400..600).contains(&delivered), "{delivered}");
+    }
+
+    #[test]
+    fn test_link_bandwidth_queues_packets() {
+        let now = Instant::now();
+        let mut link = Link::new(
+            LinkConditionerSettings {
+                bandwidth_kbps: 8,
+                ..settings(0, 0, 0.0)
+            },
+            1,
+        );
+
+        // 1000 bytes take one second at 8 kbit/s.
+        assert_eq!(link.schedule(now, 1000), Some(now + Duration::from_secs(1)));
+        assert_eq!(link.schedule(now, 1000), Some(now + Duration::from_secs(2)));
+        let later = now + Duration::from_secs(5);
+        assert_eq!(
+            link.schedule(later, 1000),
+            Some(later + Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_packet_loss_is_rejected() {
+        assert!(settings(0, 0, 1.5).validate().is_err());
+        assert!(settings(0, 0, f64::NAN).validate().is_err());
+        assert!(LinkConditionerSettings::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_excessive_delays_are_rejected() {
+        assert!(settings(MAX_SIMULATED_DELAY_MS + 1, 0, 0.0)
+            .validate()
+            .is_err());
+        assert!(settings(0, u64::MAX, 0.0).validate().is_err());
+        assert!(
+            settings(MAX_SIMULATED_DELAY_MS, MAX_SIMULATED_DELAY_MS, 0.0)
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_proxy_forwards_both_directions() {
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let conditioner = LinkConditioner::spawn(
+            (Ipv4Addr::LOCALHOST, 0).into(),
+            server.local_addr().unwrap(),
+            settings(30, 0, 0.0),
+        )
+        .unwrap();
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+
+        let sent_at = Instant::now();
+        client.send_to(b"ping", conditioner.local_addr()).unwrap();
+        let mut buffer = [0; 16];
+        let (len, proxy_peer) = server.recv_from(&mut buffer).unwrap();
+        assert_eq!(&buffer[..len], b"ping");
+        assert!(sent_at.elapsed() >= Duration::from_millis(30));
+
+        server.send_to(b"pong", proxy_peer).unwrap();
+        let (len, sender) = client.recv_from(&mut buffer).unwrap();
+        assert_eq!(&buffer[..len], b"pong");
+        assert_eq!(sender, conditioner.local_addr());
+        assert!(sent_at.elapsed() >= Duration::from_millis(60));
+    }
+}
@@ -1,13 +1,29 @@
+pub mod auth;
 pub mod blocks;
 pub mod buffer_serializer;
 pub mod chunk;
 pub mod chunk_serializer;
+pub mod config;
+pub mod discovery;
+pub mod link_conditioner;
 pub mod networking;
+pub mod quantization;
+pub mod server_process;
+pub mod status;
+pub mod temp_dir;
 
+pub use auth::*;
 pub use blocks::*;
 pub use buffer_serializer::*;
 pub use chunk::*;
+pub use config::*;
+pub use discovery::*;
+pub use link_conditioner::*;
 pub use networking::*;
+pub use quantization::*;
+pub use server_process::*;
+pub use status::*;
+pub use temp_dir::*;
 
 #[macro_export]
 macro_rules! single_mut {
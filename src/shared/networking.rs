@@ -1,5 +1,7 @@
 use std::{
     fmt::{Debug, Display},
+    io::Read,
+    str::FromStr,
     time::Duration,
 };
 
@@ -8,12 +10,14 @@ use bevy::{
     math::{IVec3, Quat, Vec3},
 };
 use bevy_renet::netcode::NETCODE_USER_DATA_BYTES;
+use bincode::Options;
 use chrono::DateTime;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use renet::{ChannelConfig, ClientId, ConnectionConfig, SendType};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::{BlockId, Chunk};
+use super::{max_serialized_buffer_len, BlockId, Chunk, QuantizedPlayerState, CHUNK_LENGTH};
 
 pub const SERVER_USERNAME: &str = "SERVER";
 pub const MAX_USERNAME_LENGTH_BYTES: usize = 50;
@@ -110,6 +114,229 @@ mod tests {
         assert!(bad_username.is_err());
         assert!(bad_username.err().unwrap().contains("SERVER"));
     }
+
+    fn largest_chunk(position: IVec3) -> Chunk {
+        let mut chunk = Chunk::new(position);
+        for (index, block) in chunk.data.iter_mut().enumerate() {
+            *block = if index % 2 == 0 {
+                BlockId::Stone
+            } else {
+                BlockId::Air
+            };
+        }
+        chunk
+    }
+
+    fn largest_chat_message() -> ChatMessage {
+        ChatMessage {
+            sender: ChatMessageSender::Player(Username::from(
+                "a".repeat(MAX_USERNAME_LENGTH_BYTES),
+            )),
+            message_id: usize::MAX,
+            timestamp: i64::MAX,
+            message: "a".repeat(MAX_CHAT_MESSAGE_BYTES),
+        }
+    }
+
+    #[test]
+    fn test_message_size_limits_fit_largest_messages() {
+        let username = Username::from("a".repeat(MAX_USERNAME_LENGTH_BYTES));
+        let players = (0..MAX_PLAYERS)
+            .map(|index| {
+                let username = Username::from(format!(
+                    "{index:0>width$}",
+                    width = MAX_USERNAME_LENGTH_BYTES
+                ));
+                (username, PlayerState::default())
+            })
+            .collect();
+        let compressed_chunks = vec![u8::MAX; CHUNK_BATCH_RESPONSE_LIMIT as usize];
+
+        let messages = [
+            NetworkingMessage::PlayerAccept(PlayerState::default()),
+            NetworkingMessage::Kick {
+                reason: DisconnectReason::IpBanned("a".repeat(MAX_DISCONNECT_REASON_BYTES)),
+            },
+            NetworkingMessage::PlayerJoin(username),
+            NetworkingMessage::PlayerLeave(username),
+            NetworkingMessage::PlayerUpdate {
+                sequence: u32::MAX,
+                state: PlayerState::default().into(),
+            },
+            NetworkingMessage::PlayerSync {
+                tick: u64::MAX,
+                server_time: f64::MAX,
+                players,
+            },
+            NetworkingMessage::PlayerTeleport(Vec3::ONE),
+            NetworkingMessage::ChunkBatchRequest(vec![IVec3::ONE; MAX_CHUNK_BATCH_REQUEST_SIZE]),
+            NetworkingMessage::ChunkBatchResponse(
+                (0..MAX_CHUNK_BATCH_RESPONSE_SIZE as i32)
+                    .map(|x| largest_chunk(IVec3::new(x, 0, 0)))
+                    .collect(),
+            ),
+            NetworkingMessage::ChatMessageSend("a".repeat(MAX_CHAT_MESSAGE_BYTES)),
+            NetworkingMessage::SingleChatMessageSync(largest_chat_message()),
+            NetworkingMessage::ChatMessageSync(vec![largest_chat_message(); MAX_CHAT_HISTORY_SYNC]),
+            NetworkingMessage::BlockUpdate {
+                position: IVec3::ONE,
+                block: BlockId::Tallgrass,
+            },
+            NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch(),
+            NetworkingMessage::LoginRequired,
+            NetworkingMessage::Login {
+                password: "a".repeat(MAX_PASSWORD_BYTES),
+            },
+            NetworkingMessage::WorldIdentity("a".repeat(MAX_WORLD_IDENTITY_BYTES)),
+            NetworkingMessage::CachedChunkBatchRequest(vec![
+                (IVec3::ONE, u64::MAX);
+                MAX_CHUNK_BATCH_REQUEST_SIZE
+            ]),
+            NetworkingMessage::ChunkBatchUnchanged(vec![IVec3::ONE; MAX_CHUNK_BATCH_RESPONSE_SIZE]),
+            NetworkingMessage::CompressedChunkBatchResponse(compressed_chunks),
+            NetworkingMessage::ChunkRequestCancel(vec![IVec3::ONE; MAX_CHUNK_BATCH_REQUEST_SIZE]),
+        ];
+        assert_eq!(messages.len(), MESSAGE_SIZE_LIMITS.len());
+
+        for (tag, message) in messages.iter().enumerate() {
+            let bytes = bincode::serialize(message).unwrap();
+            assert_eq!(
+                u32::from_le_bytes(bytes[..4].try_into().unwrap()),
+                tag as u32
+            );
+            assert_eq!(MESSAGE_SIZE_LIMITS[tag].0, message.kind());
+
+            let decoded = deserialize_message(&bytes);
+            assert!(
+                decoded.is_ok(),
+                "{} should fit its size limit",
+                message.kind()
+            );
+            assert_eq!(decoded.unwrap().kind(), message.kind());
+        }
+    }
+
+    #[test]
+    fn test_compressed_chunks_round_trip() {
+        let chunks: Vec<Chunk> = (0..MAX_CHUNK_BATCH_RESPONSE_SIZE as i32)
+            .map(|x| largest_chunk(IVec3::new(x, 0, 0)))
+            .collect();
+
+        let compressed = compress_chunks(&chunks);
+        assert!((compressed.len() as u64) < bincode::serialized_size(&chunks).unwrap());
+
+        let decompressed = decompress_chunks(&compressed).unwrap();
+        assert_eq!(decompressed.len(), chunks.len());
+        for (decompressed, chunk) in decompressed.iter().zip(&chunks) {
+            assert_eq!(decompressed.content_hash(), chunk.content_hash());
+        }
+    }
+
+    #[test]
+    fn test_serialized_chunk_batches_compress_like_chunks() {
+        let chunks: Vec<Chunk> = (0..4).map(|x| largest_chunk(IVec3::new(x, 0, 0))).collect();
+        let message_bytes =
+            bincode::serialize(&NetworkingMessage::ChunkBatchResponse(chunks.clone())).unwrap();
+
+        assert_eq!(
+            compress_chunk_batch_response(&message_bytes),
+            compress_chunks(&chunks)
+        );
+    }
+
+    #[test]
+    fn test_oversized_decompressed_chunks_are_rejected() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+        std::io::Write::write_all(
+            &mut encoder,
+            &vec![0; CHUNK_BATCH_RESPONSE_LIMIT as usize + 1],
+        )
+        .unwrap();
+        let bomb = encoder.finish().unwrap();
+
+        assert!(matches!(
+            decompress_chunks(&bomb).map_err(|err| *err),
+            Err(bincode::ErrorKind::SizeLimit)
+        ));
+        assert!(decompress_chunks(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_is_newer_sequence() {
+        assert!(is_newer_sequence(2, 1));
+        assert!(!is_newer_sequence(1, 2));
+        assert!(!is_newer_sequence(7, 7));
+        assert!(is_newer_sequence(3, u32::MAX - 3));
+        assert!(!is_newer_sequence(u32::MAX - 3, 3));
+    }
+
+    #[test]
+    fn test_truncate_to_byte_limit() {
+        let mut text = String::from("héllo");
+        truncate_to_byte_limit(&mut text, 2);
+        assert_eq!(text, "h");
+
+        let mut text = String::from("hello");
+        truncate_to_byte_limit(&mut text, 10);
+        assert_eq!(text, "hello");
+    }
+
+    #[test]
+    fn test_disconnect_reason_truncate() {
+        let mut reason = DisconnectReason::Kicked("a".repeat(MAX_DISCONNECT_REASON_BYTES + 1));
+        reason.truncate();
+        assert_eq!(
+            reason,
+            DisconnectReason::Kicked("a".repeat(MAX_DISCONNECT_REASON_BYTES))
+        );
+
+        let mut reason = DisconnectReason::ServerShutdown;
+        reason.truncate();
+        assert_eq!(reason, DisconnectReason::ServerShutdown);
+    }
+
+    #[test]
+    fn test_terminal_disconnect_reasons() {
+        assert!(DisconnectReason::Banned(String::new()).is_terminal());
+        assert!(DisconnectReason::WrongPassword.is_terminal());
+        assert!(DisconnectReason::TooManyLoginAttempts.is_terminal());
+        assert!(!DisconnectReason::Kicked(String::new()).is_terminal());
+        assert!(!DisconnectReason::ServerShutdown.is_terminal());
+        assert!(!DisconnectReason::UsernameTaken.is_terminal());
+    }
+
+    #[test]
+    fn test_oversized_messages_are_rejected() {
+        let is_size_limit = |result: bincode::Result<NetworkingMessage>| {
+            matches!(
+                result.map_err(|err| *err),
+                Err(bincode::ErrorKind::SizeLimit)
+            )
+        };
+
+        let message = NetworkingMessage::ChatMessageSend("a".repeat(MAX_CHAT_MESSAGE_BYTES + 1));
+        assert!(is_size_limit(deserialize_message(
+            &bincode::serialize(&message).unwrap()
+        )));
+
+        let message = NetworkingMessage::ChunkBatchRequest(vec![
+            IVec3::ONE;
+            MAX_CHUNK_BATCH_REQUEST_SIZE + 1
+        ]);
+        assert!(is_size_limit(deserialize_message(
+            &bincode::serialize(&message).unwrap()
+        )));
+
+        // A tiny message whose length prefix claims a huge string must fail before
+        // the string is allocated.
+        let mut bytes =
+            bincode::serialize(&NetworkingMessage::ChatMessageSend(String::new())).unwrap();
+        bytes[4..12].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        assert!(is_size_limit(deserialize_message(&bytes)));
+
+        assert!(deserialize_message(&[]).is_err());
+        assert!(deserialize_message(&u32::MAX.to_le_bytes()).is_err());
+    }
 }
 
 impl Display for Username {
@@ -136,6 +363,14 @@ impl From<String> for Username {
     }
 }
 
+impl FromStr for Username {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
 impl Serialize for Username {
     fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
         serializer.serialize_str(self.as_str())
@@ -149,6 +384,10 @@ impl<'de> Deserialize<'de> for Username {
     }
 }
 
+/// How far from a player the server accepts block updates from them, above the
+/// client's reach so that updates sent while moving are not rejected.
+pub const MAX_BLOCK_UPDATE_DISTANCE: f32 = 32.0;
+
 pub const DEFAULT_SPAWN_POINT: IVec3 = IVec3::new(0, 43, 0); // TODO: determine spawn point from terain
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -201,41 +440,346 @@ impl ChatMessage {
     }
 }
 
-type RejectReason = String;
+/// Why the server ended a connection, sent with `NetworkingMessage::Kick` and
+/// shown to the player.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DisconnectReason {
+    ServerShutdown,
+    Kicked(String),
+    Banned(String),
+    IpBanned(String),
+    NotWhitelisted,
+    UsernameTaken,
+    AccountRequired,
+    WrongPassword,
+    TooManyLoginAttempts,
+    LoginTimedOut,
+    ProtocolViolation,
+}
+
+impl DisconnectReason {
+    /// Cuts operator supplied text down to what fits into a `Kick` message.
+    pub fn truncate(&mut self) {
+        match self {
+            DisconnectReason::Kicked(text)
+            | DisconnectReason::Banned(text)
+            | DisconnectReason::IpBanned(text) => {
+                truncate_to_byte_limit(text, MAX_DISCONNECT_REASON_BYTES)
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether reconnecting would only be rejected the same way again.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            DisconnectReason::Banned(_)
+                | DisconnectReason::IpBanned(_)
+                | DisconnectReason::NotWhitelisted
+                | DisconnectReason::WrongPassword
+                | DisconnectReason::TooManyLoginAttempts
+                | DisconnectReason::AccountRequired
+        )
+    }
+}
+
+impl Display for DisconnectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisconnectReason::ServerShutdown => write!(f, "The server shut down"),
+            DisconnectReason::Kicked(reason) => write!(f, "Kicked from the server: {reason}"),
+            DisconnectReason::Banned(reason) => {
+                write!(f, "You are banned from this server: {reason}")
+            }
+            DisconnectReason::IpBanned(reason) => {
+                write!(f, "Your IP address is banned from this server: {reason}")
+            }
+            DisconnectReason::NotWhitelisted => {
+                write!(f, "You are not whitelisted on this server")
+            }
+            DisconnectReason::UsernameTaken => write!(
+                f,
+                "Another Client is already connected with that Username. Wait 15 seconds before trying again."
+            ),
+            DisconnectReason::AccountRequired => write!(
+                f,
+                "This server requires an account, ask an operator to register you"
+            ),
+            DisconnectReason::WrongPassword => write!(f, "Wrong password"),
+            DisconnectReason::TooManyLoginAttempts => {
+                write!(f, "Too many failed logins, try again later")
+            }
+            DisconnectReason::LoginTimedOut => write!(f, "Login timed out"),
+            DisconnectReason::ProtocolViolation => {
+                write!(f, "The client sent unexpected or invalid messages")
+            }
+        }
+    }
+}
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum NetworkingMessage {
     PlayerAccept(PlayerState),
-    PlayerReject(RejectReason),
+    /// Sent right before the server drops the connection.
+    Kick {
+        reason: DisconnectReason,
+    },
     PlayerJoin(Username),
     PlayerLeave(Username),
-    PlayerUpdate(PlayerState),
-    PlayerSync(HashMap<Username, PlayerState>),
+    PlayerUpdate {
+        sequence: u32,
+        state: QuantizedPlayerState,
+    },
+    PlayerSync {
+        tick: u64,
+        server_time: f64,
+        players: HashMap<Username, PlayerState>,
+    },
+    PlayerTeleport(Vec3),
     ChunkBatchRequest(Vec<IVec3>),
     ChunkBatchResponse(Vec<Chunk>),
     ChatMessageSend(String),
     SingleChatMessageSync(ChatMessage),
     ChatMessageSync(Vec<ChatMessage>),
-    BlockUpdate { position: IVec3, block: BlockId },
+    BlockUpdate {
+        position: IVec3,
+        block: BlockId,
+    },
     ServerAsksClientNicelyToRerequestChunkBatch(),
+    /// Sent instead of `PlayerAccept` when the username belongs to an account.
+    LoginRequired,
+    /// Carries the password as is, only secure netcode encrypts it on the wire.
+    Login {
+        password: String,
+    },
+    /// Sent right before `PlayerAccept`, names the world so that clients can keep
+    /// a chunk cache per world.
+    WorldIdentity(String),
+    /// Like `ChunkBatchRequest`, with the hash of the copy the client has cached.
+    CachedChunkBatchRequest(Vec<(IVec3, u64)>),
+    /// Answers cached chunks whose hash still matches the server's copy.
+    ChunkBatchUnchanged(Vec<IVec3>),
+    /// A `ChunkBatchResponse` payload compressed with `compress_chunks`.
+    CompressedChunkBatchResponse(Vec<u8>),
+    /// Drops requested chunks the client no longer needs from the server's queue.
+    ChunkRequestCancel(Vec<IVec3>),
+}
+
+impl NetworkingMessage {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetworkingMessage::PlayerAccept(_) => "PlayerAccept",
+            NetworkingMessage::Kick { .. } => "Kick",
+            NetworkingMessage::PlayerJoin(_) => "PlayerJoin",
+            NetworkingMessage::PlayerLeave(_) => "PlayerLeave",
+            NetworkingMessage::PlayerUpdate { .. } => "PlayerUpdate",
+            NetworkingMessage::PlayerSync { .. } => "PlayerSync",
+            NetworkingMessage::PlayerTeleport(_) => "PlayerTeleport",
+            NetworkingMessage::ChunkBatchRequest(_) => "ChunkBatchRequest",
+            NetworkingMessage::ChunkBatchResponse(_) => "ChunkBatchResponse",
+            NetworkingMessage::ChatMessageSend(_) => "ChatMessageSend",
+            NetworkingMessage::SingleChatMessageSync(_) => "SingleChatMessageSync",
+            NetworkingMessage::ChatMessageSync(_) => "ChatMessageSync",
+            NetworkingMessage::BlockUpdate { .. } => "BlockUpdate",
+            NetworkingMessage::ServerAsksClientNicelyToRerequestChunkBatch() => {
+                "ServerAsksClientNicelyToRerequestChunkBatch"
+            }
+            NetworkingMessage::LoginRequired => "LoginRequired",
+            NetworkingMessage::Login { .. } => "Login",
+            NetworkingMessage::WorldIdentity(_) => "WorldIdentity",
+            NetworkingMessage::CachedChunkBatchRequest(_) => "CachedChunkBatchRequest",
+            NetworkingMessage::ChunkBatchUnchanged(_) => "ChunkBatchUnchanged",
+            NetworkingMessage::CompressedChunkBatchResponse(_) => "CompressedChunkBatchResponse",
+            NetworkingMessage::ChunkRequestCancel(_) => "ChunkRequestCancel",
+        }
+    }
+}
+
+pub const MAX_PLAYERS: usize = 64;
+pub const MAX_CHUNK_BATCH_REQUEST_SIZE: usize = 32;
+pub const MAX_CHUNK_BATCH_RESPONSE_SIZE: usize = 10;
+pub const MAX_CHAT_MESSAGE_BYTES: usize = 1024;
+pub const MAX_CHAT_HISTORY_SYNC: usize = 100;
+pub const MAX_DISCONNECT_REASON_BYTES: usize = 1024;
+pub const MAX_PASSWORD_BYTES: usize = 128;
+pub const MAX_WORLD_IDENTITY_BYTES: usize = 128;
+
+// Encoded sizes as produced by `bincode::serialize`, which uses fixed-width
+// integers and `u64` length prefixes.
+const LENGTH_PREFIX_BYTES: u64 = 8;
+const ENUM_TAG_BYTES: u64 = 4;
+const VEC3_BYTES: u64 = 12;
+const USERNAME_BYTES: u64 = LENGTH_PREFIX_BYTES + MAX_USERNAME_LENGTH_BYTES as u64;
+const PLAYER_STATE_BYTES: u64 = VEC3_BYTES + 16;
+const QUANTIZED_PLAYER_STATE_BYTES: u64 = VEC3_BYTES + 2 + 2;
+const CHUNK_BYTES: u64 =
+    LENGTH_PREFIX_BYTES + max_serialized_buffer_len(CHUNK_LENGTH) as u64 + VEC3_BYTES;
+const CHAT_MESSAGE_BYTES: u64 =
+    ENUM_TAG_BYTES + USERNAME_BYTES + 8 + 8 + LENGTH_PREFIX_BYTES + MAX_CHAT_MESSAGE_BYTES as u64;
+
+const PLAYER_SYNC_LIMIT: u64 =
+    8 + 8 + LENGTH_PREFIX_BYTES + MAX_PLAYERS as u64 * (USERNAME_BYTES + PLAYER_STATE_BYTES);
+const CHUNK_BATCH_RESPONSE_LIMIT: u64 =
+    LENGTH_PREFIX_BYTES + MAX_CHUNK_BATCH_RESPONSE_SIZE as u64 * CHUNK_BYTES;
+
+/// Maximum encoded payload size of every message kind, without the variant tag.
+/// Indexed by the variant tag bincode writes for `NetworkingMessage`, so the
+/// order has to match the enum declaration.
+const MESSAGE_SIZE_LIMITS: [(&str, u64); 21] = [
+    ("PlayerAccept", PLAYER_STATE_BYTES),
+    (
+        "Kick",
+        ENUM_TAG_BYTES + LENGTH_PREFIX_BYTES + MAX_DISCONNECT_REASON_BYTES as u64,
+    ),
+    ("PlayerJoin", USERNAME_BYTES),
+    ("PlayerLeave", USERNAME_BYTES),
+    ("PlayerUpdate", 4 + QUANTIZED_PLAYER_STATE_BYTES),
+    ("PlayerSync", PLAYER_SYNC_LIMIT),
+    ("PlayerTeleport", VEC3_BYTES),
+    (
+        "ChunkBatchRequest",
+        LENGTH_PREFIX_BYTES + MAX_CHUNK_BATCH_REQUEST_SIZE as u64 * VEC3_BYTES,
+    ),
+    ("ChunkBatchResponse", CHUNK_BATCH_RESPONSE_LIMIT),
+    (
+        "ChatMessageSend",
+        LENGTH_PREFIX_BYTES + MAX_CHAT_MESSAGE_BYTES as u64,
+    ),
+    ("SingleChatMessageSync", CHAT_MESSAGE_BYTES),
+    (
+        "ChatMessageSync",
+        LENGTH_PREFIX_BYTES + MAX_CHAT_HISTORY_SYNC as u64 * CHAT_MESSAGE_BYTES,
+    ),
+    ("BlockUpdate", VEC3_BYTES + ENUM_TAG_BYTES),
+    ("ServerAsksClientNicelyToRerequestChunkBatch", 0),
+    ("LoginRequired", 0),
+    ("Login", LENGTH_PREFIX_BYTES + MAX_PASSWORD_BYTES as u64),
+    (
+        "WorldIdentity",
+        LENGTH_PREFIX_BYTES + MAX_WORLD_IDENTITY_BYTES as u64,
+    ),
+    (
+        "CachedChunkBatchRequest",
+        LENGTH_PREFIX_BYTES + MAX_CHUNK_BATCH_REQUEST_SIZE as u64 * (VEC3_BYTES + 8),
+    ),
+    (
+        "ChunkBatchUnchanged",
+        LENGTH_PREFIX_BYTES + MAX_CHUNK_BATCH_RESPONSE_SIZE as u64 * VEC3_BYTES,
+    ),
+    // Only sent when smaller than the uncompressed response.
+    (
+        "CompressedChunkBatchResponse",
+        LENGTH_PREFIX_BYTES + CHUNK_BATCH_RESPONSE_LIMIT,
+    ),
+    (
+        "ChunkRequestCancel",
+        LENGTH_PREFIX_BYTES + MAX_CHUNK_BATCH_REQUEST_SIZE as u64 * VEC3_BYTES,
+    ),
+];
+
+/// Compares sequence numbers so that they keep working after wrapping around.
+pub fn is_newer_sequence(sequence: u32, previous: u32) -> bool {
+    sequence != previous && sequence.wrapping_sub(previous) < u32::MAX / 2
+}
+
+pub fn truncate_to_byte_limit(text: &mut String, max_bytes: usize) {
+    if text.len() > max_bytes {
+        let end = (0..=max_bytes)
+            .rev()
+            .find(|index| text.is_char_boundary(*index))
+            .unwrap_or(0);
+        text.truncate(end);
+    }
+}
+
+/// Decodes a message, rejecting it with `ErrorKind::SizeLimit` if it is larger
+/// than its kind allows. Length prefixes are checked against the limit before
+/// anything is allocated.
+pub fn deserialize_message(bytes: &[u8]) -> bincode::Result<NetworkingMessage> {
+    let tag = bytes
+        .get(..ENUM_TAG_BYTES as usize)
+        .map(|tag| u32::from_le_bytes(tag.try_into().unwrap()))
+        .ok_or_else(|| {
+            Box::new(bincode::ErrorKind::Custom(String::from(
+                "missing message tag",
+            )))
+        })?;
+    let (_, limit) = MESSAGE_SIZE_LIMITS.get(tag as usize).ok_or_else(|| {
+        Box::new(bincode::ErrorKind::Custom(format!(
+            "unknown message tag {tag}"
+        )))
+    })?;
+
+    let limit = ENUM_TAG_BYTES + limit;
+    if bytes.len() as u64 > limit {
+        return Err(Box::new(bincode::ErrorKind::SizeLimit));
+    }
+
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+        .deserialize(bytes)
+}
+
+/// Deflates the encoded chunks of a `ChunkBatchResponse`.
+pub fn compress_chunks(chunks: &[Chunk]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    bincode::serialize_into(&mut encoder, chunks).expect("Chunks should always serialize");
+    encoder
+        .finish()
+        .expect("Compressing into memory should not fail")
+}
+
+/// Deflates the chunks of a serialized `ChunkBatchResponse` into the payload of a
+/// `CompressedChunkBatchResponse`, without serializing them again.
+pub fn compress_chunk_batch_response(message_bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    std::io::Write::write_all(&mut encoder, &message_bytes[ENUM_TAG_BYTES as usize..])
+        .expect("Compressing into memory should not fail");
+    encoder
+        .finish()
+        .expect("Compressing into memory should not fail")
 }
 
+/// Inflates a `CompressedChunkBatchResponse`, giving up as soon as the output
+/// exceeds what an uncompressed response may contain.
+pub fn decompress_chunks(bytes: &[u8]) -> bincode::Result<Vec<Chunk>> {
+    let mut decoded = Vec::new();
+    DeflateDecoder::new(bytes)
+        .take(CHUNK_BATCH_RESPONSE_LIMIT + 1)
+        .read_to_end(&mut decoded)?;
+    if decoded.len() as u64 > CHUNK_BATCH_RESPONSE_LIMIT {
+        return Err(Box::new(bincode::ErrorKind::SizeLimit));
+    }
+
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(CHUNK_BATCH_RESPONSE_LIMIT)
+        .deserialize(&decoded)
+}
+
+const UNRELIABLE_CHANNEL_MEMORY_BYTES: usize = 16 * PLAYER_SYNC_LIMIT as usize;
+const RELIABLE_ORDERED_CHANNEL_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+const RELIABLE_UNORDERED_CHANNEL_MEMORY_BYTES: usize = 16 * CHUNK_BATCH_RESPONSE_LIMIT as usize;
+
 const CHANNELS: [ChannelConfig; 3] = [
     ChannelConfig {
         channel_id: 0,
-        max_memory_usage_bytes: 1024 * 1024 * 1024 * 1024,
+        max_memory_usage_bytes: UNRELIABLE_CHANNEL_MEMORY_BYTES,
         send_type: SendType::Unreliable,
     },
     ChannelConfig {
         channel_id: 1,
-        max_memory_usage_bytes: 1024 * 1024 * 1024 * 1024,
+        max_memory_usage_bytes: RELIABLE_ORDERED_CHANNEL_MEMORY_BYTES,
         send_type: SendType::ReliableOrdered {
             resend_time: Duration::from_millis(300),
         },
     },
     ChannelConfig {
         channel_id: 2,
-        max_memory_usage_bytes: 1024 * 1024 * 1024 * 1024,
+        max_memory_usage_bytes: RELIABLE_UNORDERED_CHANNEL_MEMORY_BYTES,
         send_type: SendType::ReliableUnordered {
             resend_time: Duration::from_millis(300),
         },
@@ -245,6 +789,7 @@ const CHANNELS: [ChannelConfig; 3] = [
 pub fn connection_config() -> ConnectionConfig {
     ConnectionConfig {
         client_channels_config: CHANNELS.to_vec(),
+        server_channels_config: CHANNELS.to_vec(),
         ..Default::default()
     }
 }
@@ -0,0 +1,74 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::math::{EulerRot, IVec3, Quat};
+use serde::{Deserialize, Serialize};
+
+use super::PlayerState;
+
+/// Positions are sent in fixed point with this many steps per block.
+pub const POSITION_STEPS_PER_BLOCK: f32 = 1024.0;
+
+/// A `PlayerState` packed for the wire. The position is stored in fixed point
+/// and the rotation is reduced to yaw and pitch, since the camera never rolls.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuantizedPlayerState {
+    position: IVec3,
+    yaw: u16,
+    pitch: i16,
+}
+
+impl From<PlayerState> for QuantizedPlayerState {
+    fn from(state: PlayerState) -> Self {
+        let (yaw, pitch, _) = state.rotation.to_euler(EulerRot::YXZ);
+
+        Self {
+            position: (state.position * POSITION_STEPS_PER_BLOCK)
+                .round()
+                .as_ivec3(),
+            yaw: ((yaw.rem_euclid(2.0 * PI) / (2.0 * PI)) * u16::MAX as f32).round() as u16,
+            pitch: ((pitch.clamp(-FRAC_PI_2, FRAC_PI_2) / FRAC_PI_2) * i16::MAX as f32).round()
+                as i16,
+        }
+    }
+}
+
+impl From<QuantizedPlayerState> for PlayerState {
+    fn from(state: QuantizedPlayerState) -> Self {
+        let yaw = state.yaw as f32 / u16::MAX as f32 * 2.0 * PI;
+        let pitch = state.pitch as f32 / i16::MAX as f32 * FRAC_PI_2;
+
+        Self {
+            position: state.position.as_vec3() / POSITION_STEPS_PER_BLOCK,
+            rotation: Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::Vec3;
+
+    #[test]
+    fn test_quantized_player_state_roundtrip() {
+        let state = PlayerState {
+            position: Vec3::new(-1234.567, 43.21, 9876.5),
+            rotation: Quat::from_euler(EulerRot::YXZ, -2.5, 0.7, 0.0),
+        };
+
+        let restored = PlayerState::from(QuantizedPlayerState::from(state));
+
+        assert!(restored.position.distance(state.position) < 0.01);
+        assert!(restored.rotation.angle_between(state.rotation) < 0.001);
+    }
+
+    #[test]
+    fn test_quantized_player_state_is_smaller() {
+        let state = PlayerState::default();
+
+        let quantized = bincode::serialize(&QuantizedPlayerState::from(state)).unwrap();
+        let full = bincode::serialize(&state).unwrap();
+
+        assert!(quantized.len() < full.len());
+    }
+}
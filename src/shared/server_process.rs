@@ -0,0 +1,139 @@
+use std::{
+    fs,
+    io::Write,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{query_status, ServerStatus, TempDir};
+
+/// Upper bound for `ServerProcess::start` to wait for the world to generate.
+pub const SERVER_START_TIMEOUT: Duration = Duration::from_secs(120);
+/// Attempts with fresh ports when another process took a port before the server bound it.
+const START_ATTEMPTS: usize = 3;
+const LOG_FILE: &str = "server.log";
+
+/// A server binary running in its own `TempDir` on free loopback ports, with its console
+/// on a pipe. Killed when dropped.
+pub struct ServerProcess {
+    child: Child,
+    console: ChildStdin,
+    address: SocketAddr,
+    status_address: SocketAddr,
+    directory: TempDir,
+}
+
+impl ServerProcess {
+    /// Starts `binary` with `args` followed by the address and status port, and waits
+    /// until it answers status queries.
+    pub fn start(binary: &Path, args: &[&str]) -> Result<Self, String> {
+        let mut error = String::new();
+        for _ in 0..START_ATTEMPTS {
+            match Self::start_once(binary, args) {
+                Ok(server) => return Ok(server),
+                Err(attempt_error) => error = attempt_error,
+            }
+        }
+        Err(error)
+    }
+
+    fn start_once(binary: &Path, args: &[&str]) -> Result<Self, String> {
+        let directory = TempDir::create("server_process").map_err(|error| error.to_string())?;
+        let address = free_address()?;
+        let status_address = free_address()?;
+        let log = fs::File::create(directory.join(LOG_FILE)).map_err(|error| error.to_string())?;
+        let error_log = log.try_clone().map_err(|error| error.to_string())?;
+
+        let mut child = Command::new(binary)
+            .args(args)
+            .arg("--address")
+            .arg(address.to_string())
+            .arg("--status-port")
+            .arg(status_address.port().to_string())
+            .current_dir(directory.path())
+            .stdin(Stdio::piped())
+            .stdout(log)
+            .stderr(error_log)
+            .spawn()
+            .map_err(|error| format!("Failed to start {}: {error}", binary.display()))?;
+        let console = child.stdin.take().expect("stdin is piped");
+
+        let mut server = Self {
+            child,
+            console,
+            address,
+            status_address,
+            directory,
+        };
+        server.wait_until_ready()?;
+        Ok(server)
+    }
+
+    fn wait_until_ready(&mut self) -> Result<(), String> {
+        let started = Instant::now();
+        while started.elapsed() < SERVER_START_TIMEOUT {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return Err(format!("The server exited with {status}:\n{}", self.log()));
+            }
+            if self.status().is_ok() {
+                return Ok(());
+            }
+        }
+        Err(format!(
+            "The server did not answer status queries within {SERVER_START_TIMEOUT:?}:\n{}",
+            self.log()
+        ))
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The working directory of the server, with its configuration, worlds and accounts.
+    pub fn directory(&self) -> &Path {
+        self.directory.path()
+    }
+
+    pub fn status(&self) -> Result<ServerStatus, String> {
+        query_status(self.status_address, Duration::from_millis(200))
+    }
+
+    /// Types `line` into the server console.
+    pub fn run_command(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.console, "{line}").map_err(|error| error.to_string())
+    }
+
+    /// Everything the server printed so far.
+    pub fn log(&self) -> String {
+        fs::read_to_string(self.directory.join(LOG_FILE)).unwrap_or_default()
+    }
+
+    /// Polls `condition` until it holds, returns false if it did not within `timeout`.
+    pub fn wait_for(&self, timeout: Duration, mut condition: impl FnMut(&Self) -> bool) -> bool {
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if condition(self) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        condition(self)
+    }
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A loopback address with a port that was free a moment ago.
+fn free_address() -> Result<SocketAddr, String> {
+    UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|socket| socket.local_addr())
+        .map_err(|error| error.to_string())
+}
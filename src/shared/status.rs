@@ -0,0 +1,184 @@
+use std::{
+    fmt::Display,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use super::{truncate_to_byte_limit, Username, MAX_PLAYERS, MAX_USERNAME_LENGTH_BYTES};
+
+pub const DEFAULT_STATUS_PORT: u16 = 5001;
+pub const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(3);
+pub const MAX_MOTD_BYTES: usize = 256;
+pub const MAX_WORLD_NAME_BYTES: usize = 64;
+
+const STATUS_REQUEST_MAGIC: &[u8; 8] = b"RSMCSTAT";
+
+// Encoded sizes as produced by `bincode::serialize`, see `networking.rs`.
+const LENGTH_PREFIX_BYTES: u64 = 8;
+pub const MAX_STATUS_RESPONSE_BYTES: u64 = 8
+    + LENGTH_PREFIX_BYTES
+    + MAX_MOTD_BYTES as u64
+    + LENGTH_PREFIX_BYTES
+    + MAX_PLAYERS as u64 * (LENGTH_PREFIX_BYTES + MAX_USERNAME_LENGTH_BYTES as u64)
+    + 8
+    + LENGTH_PREFIX_BYTES
+    + MAX_WORLD_NAME_BYTES as u64
+    + 8;
+
+/// Requests are padded to the largest response, so that a request with a spoofed
+/// source address is never answered with more bytes than it took to send.
+pub const STATUS_REQUEST_BYTES: usize = MAX_STATUS_RESPONSE_BYTES as usize;
+
+/// Answer to a status query, sent outside of the netcode connection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServerStatus {
+    pub protocol_version: u64,
+    pub motd: String,
+    pub players: Vec<Username>,
+    pub max_players: u64,
+    pub world_name: String,
+    pub uptime_seconds: u64,
+}
+
+impl ServerStatus {
+    /// Cuts text and the player list down to the size limits before serializing.
+    pub fn encode(mut self) -> Vec<u8> {
+        truncate_to_byte_limit(&mut self.motd, MAX_MOTD_BYTES);
+        truncate_to_byte_limit(&mut self.world_name, MAX_WORLD_NAME_BYTES);
+        self.players.truncate(MAX_PLAYERS);
+
+        bincode::serialize(&self).expect("Status should always be serializable")
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .with_limit(MAX_STATUS_RESPONSE_BYTES)
+            .deserialize(bytes)
+            .map_err(|err| format!("Invalid status response: {err}"))
+    }
+}
+
+impl Display for ServerStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let players: Vec<String> = self.players.iter().map(Username::to_string).collect();
+        let uptime = self.uptime_seconds;
+
+        writeln!(f, "{}", self.motd)?;
+        writeln!(f, "World:    {}", self.world_name)?;
+        writeln!(
+            f,
+            "Players:  {}/{} {}",
+            self.players.len(),
+            self.max_players,
+            players.join(", ")
+        )?;
+        writeln!(f, "Protocol: {}", self.protocol_version)?;
+        write!(
+            f,
+            "Uptime:   {}h {}m {}s",
+            uptime / 3600,
+            uptime / 60 % 60,
+            uptime % 60
+        )
+    }
+}
+
+pub fn status_request() -> [u8; STATUS_REQUEST_BYTES] {
+    let mut request = [0; STATUS_REQUEST_BYTES];
+    request[..STATUS_REQUEST_MAGIC.len()].copy_from_slice(STATUS_REQUEST_MAGIC);
+    request
+}
+
+pub fn is_status_request(bytes: &[u8]) -> bool {
+    bytes.len() == STATUS_REQUEST_BYTES && bytes.starts_with(STATUS_REQUEST_MAGIC)
+}
+
+/// Sends a status request to `address` and blocks until it is answered or `timeout` passes.
+pub fn query_status(address: SocketAddr, timeout: Duration) -> Result<ServerStatus, String> {
+    let bind_addr: SocketAddr = if address.is_ipv6() {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind_addr)
+        .map_err(|err| format!("Failed to bind socket on {bind_addr}: {err}"))?;
+    socket
+        .set_read_timeout(Some(timeout))
+        .map_err(|err| format!("Failed to set timeout: {err}"))?;
+    socket
+        .connect(address)
+        .map_err(|err| format!("Failed to reach {address}: {err}"))?;
+    socket
+        .send(&status_request())
+        .map_err(|err| format!("Failed to send status request to {address}: {err}"))?;
+
+    let mut buffer = vec![0; MAX_STATUS_RESPONSE_BYTES as usize];
+    let len = socket
+        .recv(&mut buffer)
+        .map_err(|err| format!("No status response from {address}: {err}"))?;
+    ServerStatus::decode(&buffer[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn largest_status() -> ServerStatus {
+        ServerStatus {
+            protocol_version: u64::MAX,
+            motd: "a".repeat(MAX_MOTD_BYTES + 1),
+            players: vec![Username::from("a".repeat(MAX_USERNAME_LENGTH_BYTES)); MAX_PLAYERS + 1],
+            max_players: MAX_PLAYERS as u64,
+            world_name: "a".repeat(MAX_WORLD_NAME_BYTES + 1),
+            uptime_seconds: u64::MAX,
+        }
+    }
+
+    #[test]
+    fn test_largest_status_fits_its_size_limit() {
+        let bytes = largest_status().encode();
+        assert_eq!(bytes.len() as u64, MAX_STATUS_RESPONSE_BYTES);
+        assert!(bytes.len() <= status_request().len());
+
+        let status = ServerStatus::decode(&bytes).unwrap();
+        assert_eq!(status.motd.len(), MAX_MOTD_BYTES);
+        assert_eq!(status.world_name.len(), MAX_WORLD_NAME_BYTES);
+        assert_eq!(status.players.len(), MAX_PLAYERS);
+    }
+
+    #[test]
+    fn test_status_request() {
+        assert!(is_status_request(&status_request()));
+        assert!(!is_status_request(&status_request()[..8]));
+        assert!(!is_status_request(&[0; STATUS_REQUEST_BYTES]));
+    }
+
+    #[test]
+    fn test_query_status() {
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = server.local_addr().unwrap();
+        let status = ServerStatus {
+            protocol_version: 0,
+            motd: String::from("Hello"),
+            players: vec![Username::from("Steve")],
+            max_players: 8,
+            world_name: String::from("world"),
+            uptime_seconds: 3725,
+        };
+
+        let response = status.clone();
+        let responder = std::thread::spawn(move || {
+            let mut buffer = [0; STATUS_REQUEST_BYTES + 1];
+            let (len, client) = server.recv_from(&mut buffer).unwrap();
+            assert!(is_status_request(&buffer[..len]));
+            server.send_to(&response.encode(), client).unwrap();
+        });
+
+        assert_eq!(query_status(address, DEFAULT_STATUS_TIMEOUT), Ok(status));
+        responder.join().unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+/// A fresh directory under the system temporary directory that no other process or
+/// caller uses, removed with everything in it when dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates `rsmc_<name>_<pid>_<time>_<counter>`, failing instead of reusing a
+    /// directory that is left over from an earlier process.
+    pub fn create(name: &str) -> io::Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos());
+        let path = std::env::temp_dir().join(format!(
+            "rsmc_{name}_{}_{nanos}_{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&path)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_dirs_are_unique_and_removed() {
+        let first = TempDir::create("temp_dir").unwrap();
+        let second = TempDir::create("temp_dir").unwrap();
+        assert_ne!(first.path(), second.path());
+
+        fs::write(first.join("file"), "content").unwrap();
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().is_dir());
+    }
+}
@@ -0,0 +1,21 @@
+//! Runs the server binary as players and operators see it. Building this test also makes
+//! cargo build the binaries that the client's integration tests run against.
+
+use std::path::Path;
+
+use rsmc::{ServerProcess, PROTOCOL_ID};
+
+#[test]
+fn test_generated_world_answers_status_queries() {
+    let server = ServerProcess::start(
+        Path::new(env!("CARGO_BIN_EXE_server")),
+        &["generate-world", "status", "--seed", "7", "--motd", "Hello"],
+    )
+    .unwrap();
+
+    let status = server.status().unwrap();
+    assert_eq!(status.protocol_version, PROTOCOL_ID);
+    assert_eq!(status.motd, "Hello");
+    assert_eq!(status.world_name, "status");
+    assert!(status.players.is_empty());
+}